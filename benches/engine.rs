@@ -0,0 +1,89 @@
+#[path = "../src/klondike/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod klondike;
+
+use crate::klondike::{replay_play, PackedTable, RulesConfig, Table, VariantId};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::collections::HashSet;
+
+const SEED: u64 = 322;
+const SOLVER_NODES: usize = 1000;
+
+fn mid_game_table() -> Table {
+    let mut table = Table::new(SEED);
+    for _ in 0..3 {
+        table.deal_from_stock().expect("deal_from_stock");
+    }
+    table
+}
+
+fn expand_nodes(table: &Table, limit: usize) -> usize {
+    let mut seen = HashSet::new();
+    let mut open = vec![PackedTable::pack(table)];
+    let mut expanded = 0;
+    while let Some(packed) = open.pop() {
+        if expanded == limit {
+            break;
+        }
+        expanded += 1;
+        let table = packed.unpack();
+        for play in table.plays_with_stock() {
+            let mut next = table.clone();
+            replay_play(&mut next, play).expect("replay_play");
+            let next = PackedTable::pack(&next);
+            if seen.insert(next.clone()) {
+                open.push(next);
+            }
+        }
+    }
+    expanded
+}
+
+fn deal(c: &mut Criterion) {
+    c.bench_function("deal klondike", |b| b.iter(|| Table::new(black_box(SEED))));
+    c.bench_function("deal spiderette", |b| {
+        b.iter(|| {
+            Table::with_variant(
+                black_box(SEED),
+                VariantId::Spiderette,
+                RulesConfig::default(),
+            )
+        })
+    });
+}
+
+fn legal_moves(c: &mut Criterion) {
+    let table = mid_game_table();
+    c.bench_function("legal moves", |b| {
+        b.iter(|| black_box(&table).legal_moves())
+    });
+}
+
+fn apply_undo(c: &mut Criterion) {
+    let table = mid_game_table();
+    let play = table.legal_moves()[0];
+    c.bench_function("apply and undo", |b| {
+        b.iter_batched(
+            || table.clone(),
+            |mut next| {
+                let applied = next.apply_move(play).expect("apply_move");
+                next.unapply(applied);
+                next
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn solver_nodes(c: &mut Criterion) {
+    let table = Table::new(SEED);
+    let mut group = c.benchmark_group("solver");
+    group.throughput(Throughput::Elements(SOLVER_NODES as u64));
+    group.bench_function("expand nodes", |b| {
+        b.iter(|| expand_nodes(black_box(&table), SOLVER_NODES))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, deal, legal_moves, apply_undo, solver_nodes);
+criterion_main!(benches);
@@ -0,0 +1,85 @@
+#ifndef KLONDIKE_H
+#define KLONDIKE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define KLONDIKE_OK 0
+#define KLONDIKE_INVALID_ARGUMENT -1
+#define KLONDIKE_ILLEGAL_MOVE -2
+
+#define KLONDIKE_DRAW 0
+#define KLONDIKE_RECYCLE 1
+#define KLONDIKE_MOVE_CARDS 2
+
+/* Stacks are numbered in this order, as in share codes. */
+enum KlondikeStack {
+    KLONDIKE_STOCK,
+    KLONDIKE_WASTE,
+    KLONDIKE_RESERVE1,
+    KLONDIKE_RESERVE2,
+    KLONDIKE_RESERVE3,
+    KLONDIKE_RESERVE4,
+    KLONDIKE_RESERVE5,
+    KLONDIKE_RESERVE6,
+    KLONDIKE_RESERVE7,
+    KLONDIKE_FOUNDATION1,
+    KLONDIKE_FOUNDATION2,
+    KLONDIKE_FOUNDATION3,
+    KLONDIKE_FOUNDATION4,
+    KLONDIKE_FOUNDATION5,
+    KLONDIKE_FOUNDATION6,
+    KLONDIKE_FOUNDATION7,
+    KLONDIKE_FOUNDATION8,
+    KLONDIKE_TABLEAU1,
+    KLONDIKE_TABLEAU2,
+    KLONDIKE_TABLEAU3,
+    KLONDIKE_TABLEAU4,
+    KLONDIKE_TABLEAU5,
+    KLONDIKE_TABLEAU6,
+    KLONDIKE_TABLEAU7,
+    KLONDIKE_TABLEAU8,
+    KLONDIKE_TABLEAU9,
+    KLONDIKE_TABLEAU10,
+};
+
+typedef struct KlondikeHandle KlondikeHandle;
+
+/* The stack fields only matter for KLONDIKE_MOVE_CARDS. source_index counts
+   up from the bottom card of the source stack. */
+typedef struct KlondikeMove {
+    uint8_t kind;
+    uint8_t source_stack;
+    uint8_t source_index;
+    uint8_t target_stack;
+} KlondikeMove;
+
+KlondikeHandle *klondike_new_game(uint64_t seed);
+
+/* Returns NULL if the code is mistyped or any play in it is illegal. */
+KlondikeHandle *klondike_deserialize(const char *code);
+
+void klondike_free(KlondikeHandle *handle);
+
+int klondike_apply_move(KlondikeHandle *handle, KlondikeMove move);
+
+/* Returns the number of legal moves, including a draw or recycle when the
+   stock allows one, writing at most capacity of them. */
+size_t klondike_legal_moves(const KlondikeHandle *handle, KlondikeMove *moves, size_t capacity);
+
+bool klondike_is_won(const KlondikeHandle *handle);
+
+/* Returns the share code length without the NUL. The code is only written
+   when capacity leaves room for the NUL as well. */
+size_t klondike_serialize(const KlondikeHandle *handle, char *buffer, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
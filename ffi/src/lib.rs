@@ -0,0 +1,264 @@
+// The rules engine behind an opaque handle for C callers. Every function
+// accepts a null handle and fails cleanly rather than crashing.
+extern crate alloc;
+
+#[path = "../../src/klondike/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod klondike;
+
+use crate::klondike::{CardIndex, CardLocation, Play, ShareCode, StackId, Table};
+use enum_iterator::IntoEnumIterator;
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int},
+    ptr,
+};
+
+pub const KLONDIKE_OK: c_int = 0;
+pub const KLONDIKE_INVALID_ARGUMENT: c_int = -1;
+pub const KLONDIKE_ILLEGAL_MOVE: c_int = -2;
+
+pub const KLONDIKE_DRAW: u8 = 0;
+pub const KLONDIKE_RECYCLE: u8 = 1;
+pub const KLONDIKE_MOVE_CARDS: u8 = 2;
+
+// Stacks are numbered in StackId order, the same numbering share codes use.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KlondikeMove {
+    pub kind: u8,
+    pub source_stack: u8,
+    pub source_index: u8,
+    pub target_stack: u8,
+}
+
+fn stack_number(stack_id: StackId) -> u8 {
+    StackId::into_enum_iter()
+        .position(|candidate| candidate == stack_id)
+        .unwrap_or(0) as u8
+}
+
+fn stack_id(number: u8) -> Option<StackId> {
+    StackId::into_enum_iter().nth(number as usize)
+}
+
+impl KlondikeMove {
+    fn from_play(play: Play) -> Option<Self> {
+        match play {
+            Play::Setup => None,
+            Play::DrawFromStock => Some(Self {
+                kind: KLONDIKE_DRAW,
+                ..Self::default()
+            }),
+            Play::RecycleWaste => Some(Self {
+                kind: KLONDIKE_RECYCLE,
+                ..Self::default()
+            }),
+            Play::MoveCards(source, target) => Some(Self {
+                kind: KLONDIKE_MOVE_CARDS,
+                source_stack: stack_number(source.stack),
+                source_index: source.index.get() as u8,
+                target_stack: stack_number(target),
+            }),
+        }
+    }
+
+    fn play(self) -> Option<Play> {
+        match self.kind {
+            KLONDIKE_DRAW => Some(Play::DrawFromStock),
+            KLONDIKE_RECYCLE => Some(Play::RecycleWaste),
+            KLONDIKE_MOVE_CARDS => Some(Play::MoveCards(
+                CardLocation::new(
+                    stack_id(self.source_stack)?,
+                    CardIndex::new(self.source_index as usize),
+                ),
+                stack_id(self.target_stack)?,
+            )),
+            _ => None,
+        }
+    }
+}
+
+pub struct KlondikeHandle {
+    seed: u64,
+    table: Table,
+    plays: Vec<Play>,
+}
+
+#[no_mangle]
+pub extern "C" fn klondike_new_game(seed: u64) -> *mut KlondikeHandle {
+    Box::into_raw(Box::new(KlondikeHandle {
+        seed,
+        table: Table::new(seed),
+        plays: Vec::new(),
+    }))
+}
+
+/// # Safety
+/// `code` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn klondike_deserialize(code: *const c_char) -> *mut KlondikeHandle {
+    if code.is_null() {
+        return ptr::null_mut();
+    }
+    let share = match CStr::from_ptr(code).to_str().map(ShareCode::decode) {
+        Ok(Ok(share)) => share,
+        _ => return ptr::null_mut(),
+    };
+    match share.replay() {
+        Ok(table) => Box::into_raw(Box::new(KlondikeHandle {
+            seed: share.seed,
+            table,
+            plays: share.plays,
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `handle` must be null or come from this library, and not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn klondike_free(handle: *mut KlondikeHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live handle from this library.
+#[no_mangle]
+pub unsafe extern "C" fn klondike_apply_move(
+    handle: *mut KlondikeHandle,
+    play: KlondikeMove,
+) -> c_int {
+    let (handle, play) = match (handle.as_mut(), play.play()) {
+        (Some(handle), Some(play)) => (handle, play),
+        _ => return KLONDIKE_INVALID_ARGUMENT,
+    };
+    match handle.table.apply_move(play) {
+        Ok(_) => {
+            handle.plays.push(play);
+            KLONDIKE_OK
+        }
+        Err(_) => KLONDIKE_ILLEGAL_MOVE,
+    }
+}
+
+/// Writes up to `capacity` legal moves to `moves` and returns how many there
+/// are in all, so a caller can size its buffer with a first call. Drawing or
+/// recycling the stock counts as a move when it's possible.
+///
+/// # Safety
+/// `handle` must be null or a live handle, and `moves` must have room for
+/// `capacity` entries.
+#[no_mangle]
+pub unsafe extern "C" fn klondike_legal_moves(
+    handle: *const KlondikeHandle,
+    moves: *mut KlondikeMove,
+    capacity: usize,
+) -> usize {
+    let handle = match handle.as_ref() {
+        Some(handle) => handle,
+        None => return 0,
+    };
+    let legal: Vec<KlondikeMove> = handle
+        .table
+        .plays_with_stock()
+        .into_iter()
+        .filter_map(KlondikeMove::from_play)
+        .collect();
+    if !moves.is_null() {
+        ptr::copy_nonoverlapping(legal.as_ptr(), moves, legal.len().min(capacity));
+    }
+    legal.len()
+}
+
+/// # Safety
+/// `handle` must be null or a live handle from this library.
+#[no_mangle]
+pub unsafe extern "C" fn klondike_is_won(handle: *const KlondikeHandle) -> bool {
+    handle
+        .as_ref()
+        .map(|handle| handle.table.winner())
+        .unwrap_or(false)
+}
+
+/// Writes the game as a NUL-terminated share code and returns its length
+/// without the NUL. Nothing is written unless it all fits.
+///
+/// # Safety
+/// `handle` must be null or a live handle, and `buffer` must have room for
+/// `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn klondike_serialize(
+    handle: *const KlondikeHandle,
+    buffer: *mut c_char,
+    capacity: usize,
+) -> usize {
+    let handle = match handle.as_ref() {
+        Some(handle) => handle,
+        None => return 0,
+    };
+    let code = ShareCode::new(&handle.table, handle.seed, &handle.plays).encode();
+    if !buffer.is_null() && code.len() < capacity {
+        ptr::copy_nonoverlapping(code.as_ptr() as *const c_char, buffer, code.len());
+        *buffer.add(code.len()) = 0;
+    }
+    code.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_game_through_handle() {
+        unsafe {
+            let game = klondike_new_game(322);
+            let count = klondike_legal_moves(game, ptr::null_mut(), 0);
+            assert!(count > 0);
+            let mut moves = vec![KlondikeMove::default(); count];
+            assert_eq!(count, klondike_legal_moves(game, moves.as_mut_ptr(), count));
+            assert_eq!(KLONDIKE_OK, klondike_apply_move(game, moves[0]));
+            let bogus = KlondikeMove {
+                kind: KLONDIKE_MOVE_CARDS,
+                source_stack: stack_number(StackId::Stock),
+                source_index: 0,
+                target_stack: stack_number(StackId::Foundation1),
+            };
+            assert_eq!(KLONDIKE_ILLEGAL_MOVE, klondike_apply_move(game, bogus));
+            assert!(!klondike_is_won(game));
+
+            // Deal through the stock; a live game always has the stock play.
+            while (*game).table.has_cards_in_stock() {
+                let count = klondike_legal_moves(game, moves.as_mut_ptr(), 0);
+                moves.resize(count, KlondikeMove::default());
+                klondike_legal_moves(game, moves.as_mut_ptr(), count);
+                let draw = moves.iter().find(|play| play.kind == KLONDIKE_DRAW);
+                assert_eq!(KLONDIKE_OK, klondike_apply_move(game, *draw.expect("draw")));
+            }
+            let count = klondike_legal_moves(game, ptr::null_mut(), 0);
+            moves.resize(count, KlondikeMove::default());
+            klondike_legal_moves(game, moves.as_mut_ptr(), count);
+            assert!(moves.iter().any(|play| play.kind == KLONDIKE_RECYCLE));
+
+            let length = klondike_serialize(game, ptr::null_mut(), 0);
+            let mut buffer = vec![0 as c_char; length + 1];
+            assert_eq!(length, klondike_serialize(game, buffer.as_mut_ptr(), length + 1));
+            let code = CStr::from_ptr(buffer.as_ptr()).to_owned();
+            let copy = klondike_deserialize(code.as_ptr());
+            assert!(!copy.is_null());
+            assert_eq!((*game).table, (*copy).table);
+            klondike_free(copy);
+            klondike_free(game);
+
+            let garbage = CString::new("garbage").expect("cstring");
+            assert!(klondike_deserialize(garbage.as_ptr()).is_null());
+            assert_eq!(
+                KLONDIKE_INVALID_ARGUMENT,
+                klondike_apply_move(ptr::null_mut(), moves[0])
+            );
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    klondike::{Card, StackId},
+    Resources, CARD_HEIGHT, CARD_WIDTH, SCREEN_CLIP, SCREEN_WIDTH,
+};
+use alloc::vec::Vec;
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDBitmapDrawMode, LCDBitmapFlip, LCDColor, LCDSolidColor, LCD_ROWS},
+};
+use euclid::vec2;
+
+const RECYCLE_DURATION_MS: usize = 400;
+const STRIPE_SPACING: i32 = 6;
+
+const HINT_DURATION_MS: usize = 2000;
+const HINT_DASH: usize = 6;
+const HINT_GAP: usize = 4;
+const HINT_MARCH_MS: usize = 50;
+const HINT_HEAD_LENGTH: f32 = 10.0;
+const HINT_HEAD_WIDTH: f32 = 6.0;
+
+const WATERFALL_STEP_MS: usize = 20;
+const WATERFALL_GRAVITY: i32 = 1;
+const WATERFALL_CRANK_PERCENT: usize = 25;
+const WATERFALL_MAX_SPEED_PERCENT: usize = 800;
+
+// The frames sounds wait for, so they land with the motion rather than with
+// the move behind it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimationEvent {
+    CardsLanded(StackId),
+    FlipCompleted,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RecycleAnimation {
+    from: ScreenPoint,
+    to: ScreenPoint,
+    size: ScreenSize,
+    elapsed_ms: usize,
+    redeals_before: Option<usize>,
+    redeals_after: Option<usize>,
+}
+
+impl RecycleAnimation {
+    pub fn new(
+        from: ScreenPoint,
+        to: ScreenPoint,
+        size: ScreenSize,
+        redeals_before: Option<usize>,
+        redeals_after: Option<usize>,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            size,
+            elapsed_ms: 0,
+            redeals_before,
+            redeals_after,
+        }
+    }
+
+    pub fn tick(&mut self, delta_ms: usize) -> Option<AnimationEvent> {
+        let flipped = self.flipped();
+        self.elapsed_ms = (self.elapsed_ms + delta_ms).min(RECYCLE_DURATION_MS);
+        if !flipped && self.flipped() {
+            Some(AnimationEvent::FlipCompleted)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ms >= RECYCLE_DURATION_MS
+    }
+
+    fn progress_permille(&self) -> i32 {
+        (self.elapsed_ms * 1000 / RECYCLE_DURATION_MS) as i32
+    }
+
+    fn flipped(&self) -> bool {
+        self.progress_permille() >= 500
+    }
+
+    pub fn redeals_shown(&self) -> Option<usize> {
+        if self.flipped() {
+            self.redeals_after
+        } else {
+            self.redeals_before
+        }
+    }
+
+    pub fn draw(&self) -> Result<(), Error> {
+        let graphics = Graphics::get();
+        let progress = self.progress_permille();
+        let card_width = self.size.width;
+        let card_height = self.size.height;
+        let center_x = self.from.x + (self.to.x - self.from.x) * progress / 1000 + card_width / 2;
+        let y = self.from.y + (self.to.y - self.from.y) * progress / 1000;
+        let width = (card_width * (progress - 500).abs() / 500).max(2);
+        let rect = ScreenRect::new(
+            ScreenPoint::new(center_x - width / 2, y),
+            ScreenSize::new(width, card_height),
+        );
+        graphics.fill_rect(rect, LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+        graphics.draw_rect(rect, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+        if self.flipped() {
+            let mut stripe_y = y + STRIPE_SPACING;
+            while stripe_y < y + card_height - STRIPE_SPACING / 2 {
+                graphics.draw_line(
+                    ScreenPoint::new(rect.min_x() + 2, stripe_y),
+                    ScreenPoint::new(rect.max_x() - 2, stripe_y),
+                    1,
+                    LCDColor::Solid(LCDSolidColor::kColorBlack),
+                )?;
+                stripe_y += STRIPE_SPACING;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct HintArrow {
+    from: ScreenPoint,
+    to: ScreenPoint,
+    elapsed_ms: usize,
+}
+
+impl HintArrow {
+    pub fn new(from: ScreenPoint, to: ScreenPoint) -> Self {
+        Self {
+            from,
+            to,
+            elapsed_ms: 0,
+        }
+    }
+
+    pub fn tick(&mut self, delta_ms: usize) {
+        self.elapsed_ms = (self.elapsed_ms + delta_ms).min(HINT_DURATION_MS);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ms >= HINT_DURATION_MS
+    }
+
+    pub fn restart(&mut self) {
+        self.elapsed_ms = 0;
+    }
+
+    pub fn draw(&self) -> Result<(), Error> {
+        let graphics = Graphics::get();
+        let color = || LCDColor::Solid(LCDSolidColor::kColorBlack);
+        let delta = (self.to - self.from).to_f32();
+        let length = delta.length();
+        if length < 1.0 {
+            return Ok(());
+        }
+        let direction = delta / length;
+        let from = self.from.to_f32();
+        let shaft_length = length - HINT_HEAD_LENGTH;
+        let period = HINT_DASH + HINT_GAP;
+        let offset = (self.elapsed_ms / HINT_MARCH_MS) % period;
+        let mut start = offset as f32 - period as f32;
+        while start < shaft_length {
+            let dash_start = start.max(0.0);
+            let dash_end = (start + HINT_DASH as f32).min(shaft_length);
+            if dash_end > dash_start {
+                graphics.draw_line(
+                    (from + direction * dash_start).to_i32(),
+                    (from + direction * dash_end).to_i32(),
+                    2,
+                    color(),
+                )?;
+            }
+            start += period as f32;
+        }
+        let normal = vec2(-direction.y, direction.x);
+        let base = from + direction * shaft_length;
+        graphics.fill_triangle(
+            self.to,
+            (base + normal * HINT_HEAD_WIDTH).to_i32(),
+            (base - normal * HINT_HEAD_WIDTH).to_i32(),
+            color(),
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FallingCard {
+    card: Card,
+    position: ScreenPoint,
+    velocity_x: i32,
+    velocity_y: i32,
+}
+
+#[derive(Debug)]
+pub struct WinWaterfall {
+    pending: Vec<(Card, ScreenPoint)>,
+    falling: Option<FallingCard>,
+    trail: Vec<(Card, ScreenPoint)>,
+    launched: i32,
+    speed_percent: usize,
+    carry_ms: usize,
+}
+
+impl WinWaterfall {
+    pub fn new(launches: Vec<(Card, ScreenPoint)>) -> Self {
+        let mut pending = launches;
+        pending.reverse();
+        Self {
+            pending,
+            falling: None,
+            trail: Vec::new(),
+            launched: 0,
+            speed_percent: 100,
+            carry_ms: 0,
+        }
+    }
+
+    pub fn crank(&mut self, change: f32) {
+        let degrees = change.abs();
+        self.speed_percent =
+            (100 + degrees as usize * WATERFALL_CRANK_PERCENT).min(WATERFALL_MAX_SPEED_PERCENT);
+    }
+
+    pub fn tick(&mut self, delta_ms: usize) {
+        let scaled_ms = self.carry_ms + delta_ms * self.speed_percent / 100;
+        for _ in 0..scaled_ms / WATERFALL_STEP_MS {
+            self.step();
+        }
+        self.carry_ms = scaled_ms % WATERFALL_STEP_MS;
+    }
+
+    fn launch(&mut self) -> Option<FallingCard> {
+        let (card, position) = self.pending.pop()?;
+        let speed = 2 + self.launched % 4;
+        let velocity_x = if self.launched % 2 == 0 {
+            -speed
+        } else {
+            speed
+        };
+        self.launched += 1;
+        Some(FallingCard {
+            card,
+            position,
+            velocity_x,
+            velocity_y: -(self.launched % 5),
+        })
+    }
+
+    fn step(&mut self) {
+        let mut falling = match self.falling.take().or_else(|| self.launch()) {
+            Some(falling) => falling,
+            None => return,
+        };
+        let floor = LCD_ROWS as i32 - CARD_HEIGHT;
+        falling.velocity_y += WATERFALL_GRAVITY;
+        falling.position.x += falling.velocity_x;
+        falling.position.y += falling.velocity_y;
+        if falling.position.y > floor {
+            falling.position.y = floor;
+            falling.velocity_y = -falling.velocity_y * 3 / 4;
+        }
+        self.trail.push((falling.card.clone(), falling.position));
+        if falling.position.x + CARD_WIDTH >= 0 && falling.position.x <= SCREEN_WIDTH {
+            self.falling = Some(falling);
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty() && self.falling.is_none()
+    }
+
+    pub fn draw(&mut self, resources: &Resources) -> Result<(), Error> {
+        for (card, position) in self.trail.drain(..) {
+            let bitmap = resources
+                .card_bitmaps
+                .get(&(card.suit, card.rank))
+                .unwrap_or(&resources.empty);
+            bitmap.draw(
+                None,
+                None,
+                position,
+                LCDBitmapDrawMode::kDrawModeCopy,
+                LCDBitmapFlip::kBitmapUnflipped,
+                SCREEN_CLIP,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::{Rank, Suit};
+
+    #[test]
+    fn test_recycle_badge_ticks_down_halfway() {
+        let mut animation = RecycleAnimation::new(
+            ScreenPoint::new(65, 10),
+            ScreenPoint::new(10, 10),
+            ScreenSize::new(CARD_WIDTH, CARD_HEIGHT),
+            Some(2),
+            Some(1),
+        );
+        assert_eq!(Some(2), animation.redeals_shown());
+        assert_eq!(
+            Some(AnimationEvent::FlipCompleted),
+            animation.tick(RECYCLE_DURATION_MS / 2)
+        );
+        assert_eq!(Some(1), animation.redeals_shown());
+        assert!(!animation.is_finished());
+        assert_eq!(None, animation.tick(RECYCLE_DURATION_MS));
+        assert!(animation.is_finished());
+    }
+
+    #[test]
+    fn test_cranking_speeds_up_waterfall() {
+        let card = Card {
+            suit: Suit::Spade,
+            rank: Rank::King,
+            face_up: true,
+        };
+        let launches = || alloc::vec![(card.clone(), ScreenPoint::new(200, 10))];
+        let mut idle = WinWaterfall::new(launches());
+        let mut cranked = WinWaterfall::new(launches());
+        let mut frames = 0;
+        while !cranked.is_finished() {
+            idle.crank(0.0);
+            idle.tick(WATERFALL_STEP_MS);
+            cranked.crank(30.0);
+            cranked.tick(WATERFALL_STEP_MS);
+            frames += 1;
+            assert!(frames < 1000);
+        }
+        assert!(!idle.is_finished());
+    }
+}
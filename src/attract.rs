@@ -0,0 +1,93 @@
+use crate::{
+    klondike::{replay_play, KlondikeError, Play, ShareCode, Table},
+    panel::draw_panel,
+};
+use alloc::string::String;
+use anyhow::Error;
+use core::mem;
+
+// A solver win on Klondike deal #8, kept as a share code.
+const DEMO_GAME: &str = "22KZG-00000-00000-00400-V5GAN-20BV0-J7XY1-1DFVX-YZ0J1-JM5C1-3V08X-3Y145-\
+    WG3FY-14N09-3E28B-ZZVW2-2SGGJ-JG5BZ-0GSBX-YZFZR-42K10-DR81D-A2BSG-AVW21-\
+    AV0EP-R2NP0-DDRDC-20VV0-26W5P-Q12D0-8K254-VGETW-2BQ0A-D06Q8-14GG2-J49BM-\
+    0CMG1-FA1SF-G85E2-0NVGH-C40AZ-0GAG4-1K105-N85DA-0NAG1-J88CJ-1TC8G-N615B-\
+    GDAC1-BH1V4-GCSE1-690NA-84CH1-JCR0K-E0MRG-NER4B-Q02XG-6K225-90DAG-1AJ0J-\
+    WG2N2-1PB09-JG0CJ-03484-KC0MR-G5ER0-BH02Q-R40QQ-029S";
+const STEP_MS: usize = 400;
+// How long the finished game stays up before the demo starts over.
+const HOLD_MS: usize = 3000;
+
+// The demo that plays on the new game panel once nobody has touched the
+// device for a while. It borrows the table, keeping the player's deal to
+// hand back when a button is pressed.
+#[derive(Debug)]
+pub struct AttractMode {
+    player_table: Table,
+    demo: ShareCode,
+    next: usize,
+    elapsed_ms: usize,
+}
+
+impl AttractMode {
+    pub fn start(table: &mut Table) -> Result<Self, KlondikeError> {
+        let mut demo = ShareCode::decode(DEMO_GAME)?;
+        demo.plays.retain(|play| *play != Play::Setup);
+        let player_table = mem::replace(table, demo.deal());
+        Ok(Self {
+            player_table,
+            demo,
+            next: 0,
+            elapsed_ms: 0,
+        })
+    }
+
+    // Plays the demo forward; returns whether the table changed.
+    pub fn tick(&mut self, table: &mut Table, delta_ms: usize) -> Result<bool, KlondikeError> {
+        self.elapsed_ms += delta_ms;
+        if self.next == self.demo.plays.len() {
+            if self.elapsed_ms < HOLD_MS {
+                return Ok(false);
+            }
+            *table = self.demo.deal();
+            self.next = 0;
+            self.elapsed_ms = 0;
+            return Ok(true);
+        }
+        if self.elapsed_ms < STEP_MS {
+            return Ok(false);
+        }
+        self.elapsed_ms = 0;
+        replay_play(table, self.demo.plays[self.next])?;
+        self.next += 1;
+        Ok(true)
+    }
+
+    pub fn stop(self, table: &mut Table) {
+        *table = self.player_table;
+    }
+
+    pub fn draw() -> Result<(), Error> {
+        draw_panel(&[String::from("Demo game: press any button")], None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_demo_game_is_a_win() {
+        let mut table = Table::new(322);
+        let mut attract = AttractMode::start(&mut table).expect("start");
+        assert_eq!(Table::new(8), table);
+        while attract.next < attract.demo.plays.len() {
+            assert!(attract.tick(&mut table, STEP_MS).expect("play"));
+        }
+        assert_eq!(table.deck_size(), table.foundation_count());
+        assert!(!attract.tick(&mut table, STEP_MS).expect("hold"));
+        assert!(attract.tick(&mut table, HOLD_MS).expect("restart"));
+        assert_eq!(Table::new(8), table);
+        attract.stop(&mut table);
+        assert_eq!(Table::new(322), table);
+    }
+}
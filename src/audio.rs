@@ -0,0 +1,119 @@
+use crate::animation::AnimationEvent;
+use alloc::vec::Vec;
+use anyhow::Error;
+use core::mem::discriminant;
+use crankstart::sound::{AudioSample, SamplePlayer, Sound};
+
+const ARPEGGIO_BASE_RATE: f32 = 0.5;
+const ARPEGGIO_STEPS: &[f32] = &[1.0, 1.26, 1.5, 1.78];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cue {
+    Accepted,
+    Rejected,
+    FoundationGrew(usize),
+}
+
+pub fn arpeggio_rate(foundation_height: usize) -> f32 {
+    let step = foundation_height.saturating_sub(1);
+    let mut rate = ARPEGGIO_BASE_RATE * ARPEGGIO_STEPS[step % ARPEGGIO_STEPS.len()];
+    for _ in 0..step / ARPEGGIO_STEPS.len() {
+        rate *= 2.0;
+    }
+    rate
+}
+
+// Cues held until an animation reaches the moment they go with.
+#[derive(Debug, Default)]
+pub struct PendingCues {
+    cues: Vec<(AnimationEvent, Cue)>,
+}
+
+impl PendingCues {
+    // A cue already waiting on the same kind of moment comes back to be
+    // played now, since the new animation cut the old one short.
+    pub fn wait_for(&mut self, event: AnimationEvent, cue: Cue) -> Option<Cue> {
+        let cut_short = self.reached(event);
+        self.cues.push((event, cue));
+        cut_short
+    }
+
+    pub fn reached(&mut self, event: AnimationEvent) -> Option<Cue> {
+        let index = self
+            .cues
+            .iter()
+            .position(|(waiting, _)| discriminant(waiting) == discriminant(&event))?;
+        Some(self.cues.remove(index).1)
+    }
+
+    pub fn clear(&mut self) {
+        self.cues.clear();
+    }
+}
+
+pub struct Audio {
+    player: SamplePlayer,
+    accept: AudioSample,
+    reject: AudioSample,
+    note: AudioSample,
+}
+
+impl Audio {
+    pub fn load() -> Result<Self, Error> {
+        let sound = Sound::get();
+        Ok(Self {
+            player: sound.get_sample_player()?,
+            accept: sound.load_audio_sample("assets/sounds/accept")?,
+            reject: sound.load_audio_sample("assets/sounds/reject")?,
+            note: sound.load_audio_sample("assets/sounds/note")?,
+        })
+    }
+
+    pub fn play(&self, cue: Cue) -> Result<(), Error> {
+        let (sample, rate) = match cue {
+            Cue::Accepted => (&self.accept, 1.0),
+            Cue::Rejected => (&self.reject, 1.0),
+            Cue::FoundationGrew(height) => (&self.note, arpeggio_rate(height)),
+        };
+        self.player.set_sample(sample)?;
+        self.player.play(1, rate)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::StackId;
+
+    #[test]
+    fn test_arpeggio_rises_as_foundation_grows() {
+        let rates: alloc::vec::Vec<f32> = (1..=13).map(arpeggio_rate).collect();
+        assert!(rates.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ARPEGGIO_BASE_RATE, rates[0]);
+    }
+
+    #[test]
+    fn test_pending_cues() {
+        let mut pending = PendingCues::default();
+        let landed = AnimationEvent::CardsLanded(StackId::Foundation1);
+        assert_eq!(None, pending.wait_for(landed, Cue::FoundationGrew(1)));
+        assert_eq!(
+            None,
+            pending.wait_for(AnimationEvent::FlipCompleted, Cue::Accepted)
+        );
+        assert_eq!(
+            Some(Cue::FoundationGrew(1)),
+            pending.wait_for(landed, Cue::FoundationGrew(2))
+        );
+        assert_eq!(
+            Some(Cue::Accepted),
+            pending.reached(AnimationEvent::FlipCompleted)
+        );
+        assert_eq!(
+            Some(Cue::FoundationGrew(2)),
+            pending.reached(AnimationEvent::CardsLanded(StackId::Foundation2))
+        );
+        assert_eq!(None, pending.reached(landed));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{
+    klondike::{Play, Table},
+    panel::draw_panel,
+    session::{format_time, Session},
+    stats::GameMode,
+    storage::{self, Versioned},
+    thumbnail::Thumbnail,
+    undo::{SavedUndo, UndoHistory},
+};
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use anyhow::Error;
+use crankstart::file::FileSystem;
+use crankstart_sys::FileOptions;
+use enum_iterator::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+
+const COMPACT_AFTER: usize = 64;
+const VISIBLE_SLOTS: usize = 8;
+
+fn snapshot_path(slot: GameMode) -> String {
+    format!("autosave_{:?}.json", slot)
+}
+
+fn journal_path(slot: GameMode) -> String {
+    format!("autosave_{:?}_journal.json", slot)
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum JournalEntry {
+    Play(Play),
+    Undo,
+    Redo,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub sequence: u64,
+    pub session: Session,
+    pub table: Table,
+    #[serde(default)]
+    pub thumbnail: Thumbnail,
+    // Journaled undos can reach back past the snapshot, so it keeps the
+    // history too. Older saves resume with an empty one.
+    #[serde(default)]
+    pub undo: Option<SavedUndo>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct JournalLine {
+    sequence: u64,
+    entry: JournalEntry,
+}
+
+impl Versioned for Snapshot {
+    const VERSION: u32 = 1;
+}
+
+pub struct SavedGame {
+    pub snapshot: Snapshot,
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Debug)]
+pub struct Journal {
+    slot: GameMode,
+    next_sequence: u64,
+    pending: usize,
+}
+
+impl Journal {
+    pub fn new(slot: GameMode) -> Self {
+        Self {
+            slot,
+            next_sequence: 0,
+            pending: 0,
+        }
+    }
+
+    pub fn resume(saved: &SavedGame) -> Self {
+        Self {
+            slot: saved.snapshot.session.mode,
+            next_sequence: saved.snapshot.sequence + saved.entries.len() as u64,
+            pending: saved.entries.len(),
+        }
+    }
+
+    // Rewrites the journal with just the entries that loaded, so a line torn
+    // by a crash isn't left for the next entry to be glued onto.
+    pub fn rewrite(&mut self, saved: &SavedGame) -> Result<(), Error> {
+        let json = journal_text(saved.snapshot.sequence, &saved.entries)?;
+        let file = FileSystem::get().open(&journal_path(self.slot), FileOptions::kFileWrite)?;
+        file.write(json.as_bytes())?;
+        file.flush()
+    }
+
+    pub fn start(
+        &mut self,
+        session: &Session,
+        table: &Table,
+        undo: &UndoHistory,
+    ) -> Result<(), Error> {
+        let snapshot = Snapshot {
+            sequence: self.next_sequence,
+            session: session.clone(),
+            table: table.clone(),
+            thumbnail: Thumbnail::render(table.view()),
+            undo: Some(undo.save(table)),
+        };
+        storage::save(&snapshot_path(self.slot), &snapshot)?;
+        FileSystem::get().open(&journal_path(self.slot), FileOptions::kFileWrite)?;
+        self.pending = 0;
+        Ok(())
+    }
+
+    pub fn append(
+        &mut self,
+        entry: JournalEntry,
+        session: &Session,
+        table: &Table,
+        undo: &UndoHistory,
+    ) -> Result<(), Error> {
+        self.next_sequence += 1;
+        if self.pending + 1 >= COMPACT_AFTER {
+            return self.start(session, table, undo);
+        }
+        let json = journal_line(self.next_sequence - 1, entry)?;
+        let file = FileSystem::get().open(&journal_path(self.slot), FileOptions::kFileAppend)?;
+        file.write(json.as_bytes())?;
+        file.flush()?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<(), Error> {
+        storage::remove(&snapshot_path(self.slot))?;
+        storage::remove(&journal_path(self.slot))?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+fn journal_line(sequence: u64, entry: JournalEntry) -> Result<String, Error> {
+    let mut json = serde_json::to_string(&JournalLine { sequence, entry }).map_err(Error::msg)?;
+    json.push('\n');
+    Ok(json)
+}
+
+fn journal_text(first_sequence: u64, entries: &[JournalEntry]) -> Result<String, Error> {
+    let mut json = String::new();
+    for (sequence, entry) in (first_sequence..).zip(entries) {
+        json.push_str(&journal_line(sequence, *entry)?);
+    }
+    Ok(json)
+}
+
+pub fn parse_journal(json: &str, first_sequence: u64) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    for line in json.lines() {
+        let line: JournalLine = match serde_json::from_str(line) {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.sequence < first_sequence {
+            continue;
+        }
+        if line.sequence != first_sequence + entries.len() as u64 {
+            break;
+        }
+        entries.push(line.entry);
+    }
+    entries
+}
+
+pub fn load(slot: GameMode) -> Result<Option<SavedGame>, Error> {
+    let snapshot: Snapshot = match storage::load(&snapshot_path(slot))? {
+        Some(snapshot) => snapshot,
+        None => return Ok(None),
+    };
+    let file_system = FileSystem::get();
+    let path = journal_path(slot);
+    let json = if file_system.stat(&path).is_ok() {
+        file_system.read_file_as_string(&path)?
+    } else {
+        String::new()
+    };
+    let entries = parse_journal(&json, snapshot.sequence);
+    Ok(Some(SavedGame { snapshot, entries }))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotSummary {
+    pub mode: GameMode,
+    pub name: Option<String>,
+    pub progress: Option<(usize, usize, usize)>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+impl SlotSummary {
+    fn from_snapshot(mode: GameMode, snapshot: Option<&Snapshot>) -> Self {
+        Self {
+            mode,
+            name: None,
+            progress: snapshot.map(|snapshot| {
+                (
+                    snapshot.table.foundation_count(),
+                    snapshot.table.deck_size(),
+                    snapshot.session.elapsed_ms,
+                )
+            }),
+            thumbnail: snapshot.map(|snapshot| snapshot.thumbnail.clone()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let title = match &self.name {
+            Some(name) => format!("{} ({})", name, self.mode.name()),
+            None => String::from(self.mode.name()),
+        };
+        match self.progress {
+            Some((foundation_count, deck_size, elapsed_ms)) => format!(
+                "{}: {}/{} home, {}",
+                title,
+                foundation_count,
+                deck_size,
+                format_time(elapsed_ms)
+            ),
+            None => format!("{}: new game", title),
+        }
+    }
+}
+
+pub struct SlotPicker {
+    slots: Vec<SlotSummary>,
+    selected: usize,
+}
+
+impl SlotPicker {
+    pub fn load(current: GameMode, names: &BTreeMap<GameMode, String>) -> Self {
+        let slots: Vec<SlotSummary> = GameMode::into_enum_iter()
+            .map(|mode| {
+                let snapshot = load(mode).unwrap_or(None).map(|saved| saved.snapshot);
+                SlotSummary {
+                    name: names.get(&mode).cloned(),
+                    ..SlotSummary::from_snapshot(mode, snapshot.as_ref())
+                }
+            })
+            .collect();
+        let selected = slots
+            .iter()
+            .position(|slot| slot.mode == current)
+            .unwrap_or(0);
+        Self { slots, selected }
+    }
+
+    pub fn previous(&mut self) {
+        if self.selected == 0 {
+            self.selected = self.slots.len() - 1;
+        } else {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.slots.len();
+    }
+
+    pub fn selected_mode(&self) -> GameMode {
+        self.slots[self.selected].mode
+    }
+
+    pub fn draw(&self) -> Result<(), Error> {
+        let first = self.selected.saturating_sub(VISIBLE_SLOTS - 1);
+        let lines: Vec<String> = self
+            .slots
+            .iter()
+            .skip(first)
+            .take(VISIBLE_SLOTS)
+            .map(SlotSummary::label)
+            .collect();
+        draw_panel(&lines, Some(self.selected - first))?;
+        match &self.slots[self.selected].thumbnail {
+            Some(thumbnail) => thumbnail.draw_below_panel(lines.len()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        klondike::{RulesConfig, TableCommand, VariantId},
+        scoring::ScoringMode,
+        undo::UndoLimit,
+    };
+    use alloc::vec;
+
+    #[test]
+    fn test_journal_replays_complete_lines_from_snapshot() {
+        let lines: Vec<String> = (0..4)
+            .map(|sequence| {
+                serde_json::to_string(&JournalLine {
+                    sequence,
+                    entry: JournalEntry::Undo,
+                })
+                .expect("line")
+            })
+            .collect();
+        let torn = &lines[3][..lines[3].len() / 2];
+        let json = format!("{}\n{}\n{}\n{}", lines[0], lines[1], lines[2], torn);
+        assert_eq!(3, parse_journal(&json, 0).len());
+        assert_eq!(1, parse_journal(&json, 2).len());
+        assert!(parse_journal(&json, 5).is_empty());
+    }
+
+    #[test]
+    fn test_resume_rewrites_torn_journal() {
+        let entries = [
+            JournalEntry::Play(Play::DrawFromStock),
+            JournalEntry::Undo,
+            JournalEntry::Redo,
+        ];
+        let json = journal_text(10, &entries).expect("journal");
+        let torn = &json[..json.len() - 8];
+        let resumed = parse_journal(torn, 10);
+        assert_eq!(&entries[..2], &resumed[..]);
+
+        let mut json = journal_text(10, &resumed).expect("journal");
+        json.push_str(&journal_line(12, JournalEntry::Play(Play::RecycleWaste)).expect("line"));
+        assert_eq!(
+            vec![
+                JournalEntry::Play(Play::DrawFromStock),
+                JournalEntry::Undo,
+                JournalEntry::Play(Play::RecycleWaste),
+            ],
+            parse_journal(&json, 10)
+        );
+        let mut glued = String::from(torn);
+        glued.push_str(&journal_line(12, JournalEntry::Play(Play::RecycleWaste)).expect("line"));
+        assert_eq!(2, parse_journal(&glued, 10).len());
+    }
+
+    #[test]
+    fn test_undo_journaled_after_compaction() {
+        let mut table = Table::new(322);
+        let mut undo = UndoHistory::new(UndoLimit::Limited(3));
+        let mut tables = Vec::new();
+        for _ in 0..3 {
+            let play = table
+                .legal_moves()
+                .first()
+                .copied()
+                .unwrap_or(Play::DrawFromStock);
+            tables.push(table.clone());
+            let applied = TableCommand::new(play).apply(&mut table).expect("play");
+            undo.push(applied, 0);
+        }
+        undo.undo(&mut table).expect("undo");
+
+        let snapshot = Snapshot {
+            sequence: 64,
+            session: Session::new(322, GameMode::Klondike, ScoringMode::Standard),
+            table: table.clone(),
+            thumbnail: Thumbnail::default(),
+            undo: Some(undo.save(&table)),
+        };
+        let json = serde_json::to_string(&snapshot).expect("snapshot");
+        let snapshot: Snapshot = serde_json::from_str(&json).expect("snapshot");
+        let line = serde_json::to_string(&JournalLine {
+            sequence: 64,
+            entry: JournalEntry::Undo,
+        })
+        .expect("line");
+        assert_eq!(
+            vec![JournalEntry::Undo],
+            parse_journal(&line, snapshot.sequence)
+        );
+
+        let mut table = snapshot.table;
+        let saved = snapshot.undo.expect("undo history");
+        let mut undo = UndoHistory::load(UndoLimit::Limited(3), saved, &table).expect("load");
+        assert_eq!(Some(2), undo.undos_remaining());
+        undo.undo(&mut table).expect("undo");
+        assert_eq!(tables[1], table);
+        assert_eq!(Some(1), undo.undos_remaining());
+    }
+
+    #[test]
+    fn test_slot_summary_shows_progress() {
+        let snapshot = Snapshot {
+            sequence: 0,
+            session: Session::new(7, GameMode::Yukon, ScoringMode::Standard),
+            table: Table::with_variant(7, VariantId::Yukon, RulesConfig::default()),
+            thumbnail: Thumbnail::default(),
+            undo: None,
+        };
+        assert_eq!(
+            "Yukon: 0/52 home, 0:00",
+            SlotSummary::from_snapshot(GameMode::Yukon, Some(&snapshot)).label()
+        );
+        assert_eq!(
+            "Spiderette: new game",
+            SlotSummary::from_snapshot(GameMode::Spiderette, None).label()
+        );
+    }
+}
@@ -0,0 +1,382 @@
+// A desktop stand-in for the Playdate frontend. It drives the same engine,
+// cursor and undo code as the game, but reads commands from the terminal and
+// prints the table as text, so rule and UI changes can be stepped through in
+// a debugger on the host.
+extern crate alloc;
+
+#[path = "../klondike/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod klondike;
+
+#[path = "../cursor.rs"]
+mod cursor;
+
+#[path = "../selection.rs"]
+mod selection;
+
+#[path = "../phase.rs"]
+#[allow(dead_code)]
+mod phase;
+
+#[path = "../undo.rs"]
+#[allow(dead_code)]
+mod undo;
+
+#[cfg(feature = "tui")]
+mod tui;
+
+use crate::{
+    cursor::Cursor,
+    klondike::{
+        Card, CardLocation, KlondikeError, Play, StackId, Table, TableCommand, WASTE_VISIBLE,
+    },
+    phase::{GamePhase, PhaseMachine},
+    selection::Selection,
+    undo::{UndoHistory, UndoLimit},
+};
+use anyhow::Error;
+use argh::FromArgs;
+use std::io::{stdin, stdout, BufRead, Write};
+
+const CELL_WIDTH: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HostCommand {
+    Next,
+    Previous,
+    Confirm,
+    Cancel,
+    Undo,
+    Hint,
+    Quit,
+}
+
+impl HostCommand {
+    fn from_key(key: char) -> Option<Self> {
+        match key.to_ascii_lowercase() {
+            'n' | 'l' => Some(HostCommand::Next),
+            'p' | 'j' => Some(HostCommand::Previous),
+            'a' | 'x' => Some(HostCommand::Confirm),
+            'b' | 'z' => Some(HostCommand::Cancel),
+            'u' => Some(HostCommand::Undo),
+            'h' => Some(HostCommand::Hint),
+            'q' => Some(HostCommand::Quit),
+            _ => None,
+        }
+    }
+}
+
+struct HostGame {
+    seed: u64,
+    table: Table,
+    cursor: Cursor,
+    undo: UndoHistory,
+    phase: PhaseMachine,
+    selection: Selection,
+    moves: usize,
+    message: Option<String>,
+}
+
+impl HostGame {
+    fn new(seed: u64) -> Self {
+        let table = Table::new(seed);
+        let mut cursor = Cursor::new(&table);
+        let selection = Selection::new(&table, &mut cursor);
+        let mut game = Self {
+            seed,
+            cursor,
+            table,
+            undo: UndoHistory::new(UndoLimit::Unlimited),
+            phase: PhaseMachine::default(),
+            selection,
+            moves: 0,
+            message: None,
+        };
+        game.enter_phase(GamePhase::Playing);
+        game
+    }
+
+    fn enter_phase(&mut self, next: GamePhase) {
+        if let Err(err) = self.phase.enter(next) {
+            self.message = Some(err.to_string());
+        }
+    }
+
+    fn pick_up_or_drop(&mut self) -> Result<(), KlondikeError> {
+        if self.table.cards_in_hand() {
+            if let Some(applied) = self.cursor.put_hand_on_target(&mut self.table)? {
+                self.undo.push(applied, 0);
+                self.moves += 1;
+            }
+        } else if self.cursor.source.stack == StackId::Stock {
+            let play = if self.table.get_stack(StackId::Stock).is_empty() {
+                Play::RecycleWaste
+            } else {
+                Play::DrawFromStock
+            };
+            let applied = TableCommand::new(play).apply(&mut self.table)?;
+            self.undo.push(applied, 0);
+            self.moves += 1;
+        } else {
+            self.selection.pick_up(&mut self.table, &mut self.cursor)?;
+        }
+        Ok(())
+    }
+
+    fn run(&mut self, command: HostCommand) {
+        self.message = None;
+        match command {
+            HostCommand::Next => self.selection.next(&self.table, &mut self.cursor),
+            HostCommand::Previous => self.selection.previous(&self.table, &mut self.cursor),
+            HostCommand::Confirm => match self.pick_up_or_drop() {
+                Ok(()) => {
+                    if self.table.winner() {
+                        self.enter_phase(GamePhase::Won);
+                        self.message = Some(String::from("You won!"));
+                    }
+                }
+                Err(err) => self.message = Some(err.explanation().to_string()),
+            },
+            HostCommand::Cancel => {
+                if let Err(err) = self.cursor.return_hand(&mut self.table) {
+                    self.message = Some(err.to_string());
+                }
+            }
+            HostCommand::Undo => match self.undo.undo(&mut self.table) {
+                Ok(_) => {
+                    if self.phase.current() == GamePhase::Won {
+                        self.enter_phase(GamePhase::Playing);
+                    }
+                }
+                Err(err) => self.message = Some(err.explanation().to_string()),
+            },
+            HostCommand::Hint => {
+                self.message = Some(match self.table.hint() {
+                    Some(play) => format!("Try {:?}", play),
+                    None => String::from("No moves left"),
+                })
+            }
+            HostCommand::Quit => (),
+        }
+        if !self.table.cards_in_hand() {
+            self.selection
+                .update_active_cards(&self.table, &mut self.cursor);
+        }
+    }
+
+    fn marker(&self, location: CardLocation) -> char {
+        if self.table.cards_in_hand() {
+            ' '
+        } else if self.cursor.source == location {
+            '>'
+        } else {
+            ' '
+        }
+    }
+
+    fn cell(&self, stack_id: StackId, index: usize, card: Option<&Card>) -> String {
+        let marker = self.marker(CardLocation::new(stack_id, klondike::CardIndex::new(index)));
+        let text = match card {
+            Some(card) if card.face_up => card.to_notation(),
+            Some(_) => String::from("##"),
+            None => String::from("[]"),
+        };
+        format!("{}{:<width$}", marker, text, width = CELL_WIDTH - 1)
+    }
+
+    // One cell per stack across the top, as the game lays them out, with the
+    // tableaux fanned downward below.
+    fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "Seed {}  Moves {}  Cycles {}",
+            self.seed,
+            self.moves,
+            self.table.stock_cycles_used()
+        )];
+        let stock = self.table.get_stack(StackId::Stock);
+        let waste = self.table.get_stack(StackId::Waste);
+        let mut top = self.cell(StackId::Stock, 0, stock.top_card());
+        let shown = waste.len().min(WASTE_VISIBLE);
+        for (index, card) in waste.cards().iter().enumerate().skip(waste.len() - shown) {
+            top.push_str(&self.cell(StackId::Waste, index, Some(card)));
+        }
+        for _ in shown..WASTE_VISIBLE {
+            top.push_str(&" ".repeat(CELL_WIDTH));
+        }
+        for stack_id in self
+            .table
+            .reserve_ids()
+            .iter()
+            .chain(self.table.foundation_ids())
+        {
+            let stack = self.table.get_stack(*stack_id);
+            top.push_str(&self.cell(*stack_id, stack.len().saturating_sub(1), stack.top_card()));
+        }
+        lines.push(top.trim_end().to_string());
+        lines.push(String::new());
+
+        let tableaux = self.table.tableau_ids();
+        let depth = tableaux
+            .iter()
+            .map(|stack_id| self.table.get_stack(*stack_id).len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for row in 0..depth {
+            let line: String = tableaux
+                .iter()
+                .map(|stack_id| {
+                    let stack = self.table.get_stack(*stack_id);
+                    match stack.cards().get(row) {
+                        Some(card) => self.cell(*stack_id, row, Some(card)),
+                        None if row == 0 => self.cell(*stack_id, row, None),
+                        None => " ".repeat(CELL_WIDTH),
+                    }
+                })
+                .collect();
+            lines.push(line.trim_end().to_string());
+        }
+
+        let hand = self.table.get_stack(StackId::Hand);
+        if !hand.is_empty() {
+            lines.push(String::new());
+            lines.push(format!(
+                "Holding {:?} -> {:?}",
+                hand.cards(),
+                self.cursor.target
+            ));
+        }
+        if let Some(message) = &self.message {
+            lines.push(message.clone());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Play on the host with text in place of the Playdate screen
+#[derive(FromArgs, Debug, Clone)]
+struct Opt {
+    /// seed
+    #[argh(option, default = "326")]
+    seed: u64,
+
+    /// take over the terminal and play with the arrow keys
+    #[argh(switch)]
+    tui: bool,
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(game: &mut HostGame) -> Result<(), Error> {
+    tui::run(game)
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_game: &mut HostGame) -> Result<(), Error> {
+    Err(Error::msg("built without the tui feature"))
+}
+
+fn run_lines(game: &mut HostGame) -> Result<(), Error> {
+    let stdin = stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        println!("{}", game.render());
+        print!("n/p move, a pick up/drop, b cancel, u undo, h hint, q quit: ");
+        stdout().flush().map_err(Error::msg)?;
+        let line = match lines.next() {
+            Some(line) => line.map_err(Error::msg)?,
+            None => return Ok(()),
+        };
+        for command in line.chars().filter_map(HostCommand::from_key) {
+            if command == HostCommand::Quit {
+                return Ok(());
+            }
+            game.run(command);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let opt: Opt = argh::from_env();
+    let mut game = HostGame::new(opt.seed);
+    if opt.tui {
+        run_tui(&mut game)
+    } else {
+        run_lines(&mut game)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_SEED: u64 = 326;
+
+    fn press(game: &mut HostGame, keys: &str) {
+        for command in keys.chars().filter_map(HostCommand::from_key) {
+            game.run(command);
+        }
+    }
+
+    #[test]
+    fn test_host_moves_and_undo() {
+        let mut game = HostGame::new(TEST_SEED);
+        let start = game.table.clone();
+        game.run(HostCommand::Confirm);
+        assert_eq!(1, game.moves);
+        assert_ne!(start, game.table);
+        game.run(HostCommand::Undo);
+        assert_eq!(start, game.table);
+
+        let source = match game.table.hint() {
+            Some(Play::MoveCards(source, _)) => source,
+            play => panic!("no move to make: {:?}", play),
+        };
+        while game.cursor.source != source {
+            game.run(HostCommand::Next);
+        }
+        game.run(HostCommand::Confirm);
+        assert!(game.table.cards_in_hand());
+        assert!(game.render().contains("Holding"));
+        game.run(HostCommand::Confirm);
+        assert!(!game.table.cards_in_hand());
+        assert_eq!(2, game.moves);
+    }
+
+    fn press_until(game: &mut HostGame, keys: &str, done: impl Fn(&HostGame) -> bool) {
+        for _ in 0..100 {
+            if done(game) {
+                return;
+            }
+            press(game, keys);
+        }
+        panic!("never got there:\n{}", game.render());
+    }
+
+    // Plays the hinted moves with key presses alone, the way a player would.
+    #[test]
+    fn test_hints_play_through_keys() {
+        let mut game = HostGame::new(TEST_SEED);
+        let start = game.table.clone();
+        for _ in 0..40 {
+            let moves = game.moves;
+            match game.table.hint() {
+                Some(Play::MoveCards(source, target)) => {
+                    press_until(&mut game, "n", |game| game.cursor.source == source);
+                    press(&mut game, "a");
+                    press_until(&mut game, "n", |game| game.cursor.target == target);
+                    press(&mut game, "a");
+                }
+                _ => {
+                    press_until(&mut game, "p", |game| {
+                        game.cursor.source.stack == StackId::Stock
+                    });
+                    press(&mut game, "a");
+                }
+            }
+            assert_eq!(None, game.message);
+            assert_eq!(moves + 1, game.moves, "{}", game.render());
+        }
+        press(&mut game, &"u".repeat(40));
+        assert_eq!(start, game.table);
+    }
+}
@@ -0,0 +1,59 @@
+use super::{HostCommand, HostGame};
+use anyhow::Error;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{read, Event, KeyCode},
+    execute, queue,
+    style::Print,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use std::io::{stdout, Write};
+
+const HELP: &str = "arrows move, enter pick up/drop, esc cancel, u undo, h hint, q quit";
+
+fn key_command(code: KeyCode) -> Option<HostCommand> {
+    match code {
+        KeyCode::Left | KeyCode::Up => Some(HostCommand::Previous),
+        KeyCode::Right | KeyCode::Down => Some(HostCommand::Next),
+        KeyCode::Enter | KeyCode::Char(' ') => Some(HostCommand::Confirm),
+        KeyCode::Esc | KeyCode::Backspace => Some(HostCommand::Cancel),
+        KeyCode::Char(key) => HostCommand::from_key(key),
+        _ => None,
+    }
+}
+
+fn draw(game: &HostGame) -> Result<(), Error> {
+    let mut out = stdout();
+    queue!(out, Clear(ClearType::All)).map_err(Error::msg)?;
+    for (row, line) in game.render().lines().chain(Some(HELP)).enumerate() {
+        queue!(out, MoveTo(0, row as u16), Print(line)).map_err(Error::msg)?;
+    }
+    out.flush().map_err(Error::msg)
+}
+
+fn event_loop(game: &mut HostGame) -> Result<(), Error> {
+    loop {
+        draw(game)?;
+        if let Event::Key(key) = read().map_err(Error::msg)? {
+            match key_command(key.code) {
+                Some(HostCommand::Quit) => return Ok(()),
+                Some(command) => game.run(command),
+                None => (),
+            }
+        }
+    }
+}
+
+// Takes over the terminal until the player quits, putting it back the way
+// it was even if the game loop fails.
+pub fn run(game: &mut HostGame) -> Result<(), Error> {
+    enable_raw_mode().map_err(Error::msg)?;
+    execute!(stdout(), EnterAlternateScreen, Hide).map_err(Error::msg)?;
+    let result = event_loop(game);
+    execute!(stdout(), Show, LeaveAlternateScreen).map_err(Error::msg)?;
+    disable_raw_mode().map_err(Error::msg)?;
+    result
+}
@@ -1,11 +1,12 @@
 use anyhow::Error;
 
-#[path = "../klondike.rs"]
-#[allow(dead_code)]
+#[path = "../klondike/mod.rs"]
+#[allow(dead_code, unused_imports)]
 mod klondike;
 
 use crate::klondike::{
-    ActiveCardIterator, Card, CardPlayIterator, Play, Rank, Source, Stack, StackId, Table,
+    ActiveCardIterator, Card, CardIndex, CardLocation, CardPlayIterator, KlondikeError,
+    PackedTable, Play, Rank, Stack, StackId, Table,
 };
 use argh::FromArgs;
 use core::iter::Iterator;
@@ -13,7 +14,7 @@ use rayon::prelude::*;
 use std::{
     cmp::Ordering,
     collections::HashSet,
-    fs::File,
+    fs::{read_to_string, File},
     io::{stdin, stdout, Write},
 };
 
@@ -56,20 +57,27 @@ impl WeightedPlay {
                 StackId::Foundation1
                 | StackId::Foundation2
                 | StackId::Foundation3
-                | StackId::Foundation4 => (5, 0),
+                | StackId::Foundation4
+                | StackId::Foundation5
+                | StackId::Foundation6
+                | StackId::Foundation7
+                | StackId::Foundation8 => (5, 0),
                 StackId::Tableau1
                 | StackId::Tableau2
                 | StackId::Tableau3
                 | StackId::Tableau4
                 | StackId::Tableau5
                 | StackId::Tableau6
-                | StackId::Tableau7 => match source.stack {
+                | StackId::Tableau7
+                | StackId::Tableau8
+                | StackId::Tableau9
+                | StackId::Tableau10 => match source.stack {
                     StackId::Waste => {
                         let stack = table.get_stack(source.stack);
                         let card = stack.get_card(source.index).expect("get_card");
                         let score = 5;
                         let priority = if card.rank == Rank::King {
-                            Self::waste_king_priority(source, target, stack, card, table)
+                            Self::waste_king_priority(source, target, &stack, card, table)
                         } else {
                             1
                         };
@@ -81,11 +89,18 @@ impl WeightedPlay {
                     | StackId::Tableau4
                     | StackId::Tableau5
                     | StackId::Tableau6
-                    | StackId::Tableau7 => Self::tableau_move(source, target, table),
+                    | StackId::Tableau7
+                    | StackId::Tableau8
+                    | StackId::Tableau9
+                    | StackId::Tableau10 => Self::tableau_move(source, target, table),
                     StackId::Foundation1
                     | StackId::Foundation2
                     | StackId::Foundation3
-                    | StackId::Foundation4 => (-10, 0),
+                    | StackId::Foundation4
+                    | StackId::Foundation5
+                    | StackId::Foundation6
+                    | StackId::Foundation7
+                    | StackId::Foundation8 => (-10, 0),
                     _ => (0, 0),
                 },
                 _ => (0, 0),
@@ -99,12 +114,12 @@ impl WeightedPlay {
         }
     }
 
-    fn tableau_move(source: Source, _target: StackId, table: &Table) -> (isize, isize) {
+    fn tableau_move(source: CardLocation, _target: StackId, table: &Table) -> (isize, isize) {
         let stack = table.get_stack(source.stack);
         let score = 0;
         if stack.is_top_face_up_card(source.index) {
             if stack.len() > 0 {
-                (score, source.index as isize + 1)
+                (score, source.index.get() as isize + 1)
             } else {
                 (score, 1)
             }
@@ -114,7 +129,7 @@ impl WeightedPlay {
     }
 
     fn waste_king_priority(
-        _source: Source,
+        _source: CardLocation,
         _target: StackId,
         _stack: &Stack,
         card: &Card,
@@ -128,7 +143,10 @@ impl WeightedPlay {
                 | StackId::Tableau4
                 | StackId::Tableau5
                 | StackId::Tableau6
-                | StackId::Tableau7 => {
+                | StackId::Tableau7
+                | StackId::Tableau8
+                | StackId::Tableau9
+                | StackId::Tableau10 => {
                     let stack = table.get_stack(queen_card_location.stack);
                     let card = stack.get_card(queen_card_location.index).expect("get_card");
                     if card.face_up {
@@ -181,7 +199,7 @@ impl<'a> Iterator for PlayIterator<'a> {
                     if self.table.has_cards_in_stock() {
                         return Some(Play::DrawFromStock);
                     }
-                    if self.table.has_cards_in_waste() {
+                    if self.table.has_cards_in_waste() && self.table.can_recycle() {
                         return Some(Play::RecycleWaste);
                     }
                 }
@@ -219,7 +237,8 @@ struct SearchNode {
     parent: Option<usize>,
     index: usize,
     play: Play,
-    table: Table,
+    table: PackedTable,
+    hash: u64,
     weighted_plays: Vec<WeightedPlay>,
 }
 
@@ -233,12 +252,13 @@ impl SearchNode {
             parent,
             index,
             play,
-            table,
+            table: PackedTable::pack(&table),
+            hash: table.position_hash(),
             weighted_plays,
         }
     }
 
-    fn filter_play(&self, play: &Play, previous_plays: &Vec<Play>) -> Option<Play> {
+    fn filter_play(play: &Play, previous_plays: &Vec<Play>, table: &Table) -> Option<Play> {
         match play {
             Play::RecycleWaste => {
                 if previous_plays.len() > 0 {
@@ -261,17 +281,25 @@ impl SearchNode {
                 StackId::Foundation1
                 | StackId::Foundation2
                 | StackId::Foundation3
-                | StackId::Foundation4 => Some(*play),
+                | StackId::Foundation4
+                | StackId::Foundation5
+                | StackId::Foundation6
+                | StackId::Foundation7
+                | StackId::Foundation8 => Some(*play),
                 _ => match source.stack {
                     StackId::Foundation1
                     | StackId::Foundation2
                     | StackId::Foundation3
-                    | StackId::Foundation4 => None,
+                    | StackId::Foundation4
+                    | StackId::Foundation5
+                    | StackId::Foundation6
+                    | StackId::Foundation7
+                    | StackId::Foundation8 => None,
                     StackId::Waste => Some(*play),
                     _ => {
-                        let stack = self.table.get_stack(source.stack);
-                        if source.index == 0 {
-                            if stack.get_card(0).expect("get_card").rank == Rank::King {
+                        let stack = table.get_stack(source.stack);
+                        if source.index == CardIndex::BOTTOM {
+                            if stack.get_card(source.index).expect("get_card").rank == Rank::King {
                                 return None;
                             } else {
                                 Some(*play)
@@ -292,15 +320,19 @@ impl SearchNode {
         &mut self,
         next_index: usize,
         previous_plays: &Vec<Play>,
+        seen: &HashSet<u64>,
         stepping: bool,
     ) -> Option<SearchNode> {
         while let Some(weighted_play) = self.weighted_plays.pop() {
             if stepping {
                 println!("chose {:?}", weighted_play);
             }
-            let table = self.table.clone();
-            if let Some(play) = self.filter_play(&weighted_play.play, previous_plays) {
+            let table = self.table.unpack();
+            if let Some(play) = Self::filter_play(&weighted_play.play, previous_plays, &table) {
                 let new_table = make_move(play, &table);
+                if seen.contains(&new_table.position_hash()) {
+                    continue;
+                }
                 return Some(Self::new(
                     Some(self.index),
                     next_index,
@@ -317,7 +349,8 @@ fn test_plays_iter(table: Table, verbose: bool, start_stepping: bool) -> Option<
     let mut stepping = start_stepping;
     let mut max_foundation = 0;
     let mut search_nodes = Vec::new();
-    let mut tables: HashSet<Table> = HashSet::new();
+    let mut positions: HashSet<u64> = HashSet::new();
+    positions.insert(table.position_hash());
     search_nodes.push(SearchNode::new(None, 0, Play::Setup, table));
     let mut iterations = 0;
     while search_nodes.len() > 0 {
@@ -353,7 +386,7 @@ fn test_plays_iter(table: Table, verbose: bool, start_stepping: bool) -> Option<
                 println!("table: {:#?}", search_nodes[last_index].table);
             }
         }
-        let cards_in_foundation = search_nodes[last_index].table.cards_in_foundation();
+        let cards_in_foundation = search_nodes[last_index].table.foundation_count();
         if cards_in_foundation > max_foundation {
             max_foundation = cards_in_foundation;
             if verbose {
@@ -362,8 +395,9 @@ fn test_plays_iter(table: Table, verbose: bool, start_stepping: bool) -> Option<
                 println!("table: {:#?}", search_nodes[last_index].table);
             }
         }
-        if let Some(node) = search_nodes[last_index].search(len, &plays, stepping) {
+        if let Some(node) = search_nodes[last_index].search(len, &plays, &positions, stepping) {
             if node.table.winner() {
+                plays.push(search_nodes[last_index].play);
                 plays.push(node.play);
                 if verbose {
                     println!("Winner! {:#?}", node.table);
@@ -377,7 +411,7 @@ fn test_plays_iter(table: Table, verbose: bool, start_stepping: bool) -> Option<
                     println!("{:#?}", node.weighted_plays);
                 }
             }
-            tables.insert(node.table.clone());
+            positions.insert(node.hash);
             search_nodes.push(node);
         } else {
             search_nodes.pop();
@@ -418,28 +452,57 @@ fn test_plays_iter(table: Table, verbose: bool, start_stepping: bool) -> Option<
     None
 }
 
-fn make_move(play: Play, table: &Table) -> Table {
-    let mut new_table = table.clone();
+fn try_make_move(play: Play, table: &Table) -> Result<Table, KlondikeError> {
     match play {
-        Play::DrawFromStock => {
-            assert!(new_table.has_cards_in_stock());
-            new_table.deal_from_stock()
-        }
+        Play::DrawFromStock if !table.has_cards_in_stock() => Err(KlondikeError::NoCardsToDeal),
         Play::RecycleWaste => {
-            assert!(!new_table.has_cards_in_stock());
-            new_table.recycle_waste();
-        }
-        Play::MoveCards(source, stack_id) => {
-            new_table.take_selected_cards_from_stack(source.stack, source.index);
-            new_table.put_hand_on_stack(source, stack_id);
+            let mut new_table = table.clone();
+            new_table.recycle_waste()?;
+            Ok(new_table)
         }
         Play::Setup => panic!("Unhandled play"),
+        _ => table.preview(play),
+    }
+}
+
+fn make_move(play: Play, table: &Table) -> Table {
+    try_make_move(play, table).expect("make_move")
+}
+
+// states[i] is the position before steps[i]; any step that returns to an
+// earlier position drops everything since then.
+fn cut_loops<S: PartialEq, P: Copy>(states: &[S], steps: &[P]) -> Vec<P> {
+    let mut seen: Vec<&S> = vec![&states[0]];
+    let mut kept = Vec::new();
+    for (step, state) in steps.iter().zip(&states[1..]) {
+        if let Some(index) = seen.iter().position(|seen| *seen == state) {
+            seen.truncate(index + 1);
+            kept.truncate(index);
+        } else {
+            seen.push(state);
+            kept.push(*step);
+        }
     }
-    new_table
+    kept
+}
+
+fn shorten_plays(table: &Table, plays: &[Play]) -> Result<Vec<Play>, KlondikeError> {
+    let plays: Vec<Play> = plays
+        .iter()
+        .copied()
+        .filter(|play| *play != Play::Setup)
+        .collect();
+    let mut current = table.clone();
+    let mut states = vec![PackedTable::pack(table)];
+    for play in &plays {
+        current = try_make_move(*play, &current)?;
+        states.push(PackedTable::pack(&current));
+    }
+    Ok(cut_loops(&states, &plays))
 }
 
 /// Options
-#[derive(FromArgs, Debug, Clone, Copy)]
+#[derive(FromArgs, Debug, Clone)]
 struct Opt {
     /// verbose
     #[argh(switch)]
@@ -456,11 +519,26 @@ struct Opt {
     /// step at start
     #[argh(switch)]
     start_stepping: bool,
+
+    /// print the par move count of each win, with loops cut out
+    #[argh(switch)]
+    par: bool,
+
+    /// read winning plays from a previous run instead of solving
+    #[argh(option)]
+    plays: Option<String>,
 }
 
 fn main() -> Result<(), Error> {
     let opt: Opt = argh::from_env();
 
+    if let Some(path) = &opt.plays {
+        let json = read_to_string(path).map_err(Error::msg)?;
+        let wins: Vec<(u64, Vec<Play>)> = serde_json::from_str(&json).map_err(Error::msg)?;
+        print_par(&wins);
+        return Ok(());
+    }
+
     let wins: Vec<(u64, Vec<Play>)> = (opt.seed..opt.seed + opt.count)
         .into_par_iter()
         .filter_map(|seed| {
@@ -477,6 +555,9 @@ fn main() -> Result<(), Error> {
         .collect();
     let seeds: Vec<u64> = wins.iter().map(|(seed, _)| *seed).collect();
     println!("wins = {:?}", seeds);
+    if opt.par {
+        print_par(&wins);
+    }
     let winning_plays = serde_json::to_string(&wins).map_err(Error::msg)?;
     let mut file = File::create(format!(
         "winning_plays_{}_{}.json",
@@ -490,10 +571,19 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+fn print_par(wins: &[(u64, Vec<Play>)]) {
+    for (seed, plays) in wins {
+        match shorten_plays(&Table::new(*seed), plays) {
+            Ok(plays) => println!("({}, {}),", seed, plays.len()),
+            Err(err) => eprintln!("{} no longer replays: {}", seed, err),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::klondike::Suit;
+    use crate::klondike::{RulesConfig, Suit};
 
     const TEST_SEED: u64 = 324;
 
@@ -508,10 +598,10 @@ mod test {
         );
 
         while work_table.has_cards_in_stock() {
-            work_table.deal_from_stock();
+            work_table.deal_from_stock().expect("deal_from_stock");
         }
 
-        work_table.recycle_waste();
+        work_table.recycle_waste().expect("recycle_waste");
 
         assert_eq!(
             table.get_stack(StackId::Stock),
@@ -519,21 +609,78 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_recycle_limit() {
+        let rules = RulesConfig {
+            max_recycles: Some(0),
+            ..RulesConfig::default()
+        };
+        let mut table = Table::with_rules(TEST_SEED, rules);
+        while table.has_cards_in_stock() {
+            table.deal_from_stock().expect("deal_from_stock");
+        }
+        assert!(table.stock_is_dead());
+        assert_eq!(Err(KlondikeError::NoRedealsLeft), table.deal_from_stock());
+        assert_eq!(0, table.stock_cycles_used());
+    }
+
     #[test]
     fn test_find_card() {
         let mut table = Table::new(TEST_SEED);
-        table.deal_from_stock();
+        table.deal_from_stock().expect("deal_from_stock");
         println!("table = {:#?}", table);
         let queen_card_location = table.find_card(Rank::Queen, Suit::Diamond);
-        assert_eq!(Some(Source::new(StackId::Tableau3, 0)), queen_card_location);
+        assert_eq!(
+            Some(CardLocation::new(StackId::Tableau3, CardIndex::new(0))),
+            queen_card_location
+        );
 
         let two_diamonds_card_location = table.find_card(Rank::Two, Suit::Diamond);
         assert_eq!(
-            Some(Source::new(StackId::Stock, 6)),
+            Some(CardLocation::new(StackId::Stock, CardIndex::new(6))),
             two_diamonds_card_location
         );
 
         let waste_card_location = table.find_card(Rank::Nine, Suit::Club);
-        assert_eq!(Some(Source::new(StackId::Waste, 2)), waste_card_location);
+        assert_eq!(
+            Some(CardLocation::new(StackId::Waste, CardIndex::new(2))),
+            waste_card_location
+        );
+    }
+
+    #[test]
+    fn test_pick_up_with_cards_in_hand() {
+        let mut table = Table::new(TEST_SEED);
+        table
+            .take_top_card_from_stack(StackId::Tableau1)
+            .expect("take_top_card_from_stack");
+        assert_eq!(
+            Err(KlondikeError::HandNotEmpty),
+            table.take_selected_cards_from_stack(StackId::Tableau2, CardIndex::new(1))
+        );
+        assert_eq!(
+            Err(KlondikeError::HandNotEmpty),
+            table.take_top_card_from_stack(StackId::Tableau3)
+        );
+        assert_eq!(1, table.get_stack(StackId::Hand).len());
+        assert_eq!(52, table.card_count());
+    }
+
+    #[test]
+    fn test_cut_loops() {
+        let states = [0, 1, 2, 1, 3, 4, 3, 5];
+        let steps = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+        assert_eq!(vec!['a', 'd', 'g'], cut_loops(&states, &steps));
+    }
+
+    #[test]
+    fn test_card_count_is_stable() {
+        let mut table = Table::new(TEST_SEED);
+        for _ in 0..200 {
+            assert_eq!(52, table.card_count());
+            let play = PlayIterator::new(&table).last().expect("play");
+            table = make_move(play, &table);
+        }
+        assert_eq!(52, table.card_count());
     }
 }
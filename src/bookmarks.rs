@@ -0,0 +1,67 @@
+use crate::{panel::draw_panel, stats::GameMode};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+pub const BOOKMARK_LIMIT: usize = 50;
+
+const VISIBLE_BOOKMARKS: usize = 8;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Bookmark {
+    pub seed: u64,
+    pub mode: GameMode,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl Bookmark {
+    fn label(&self) -> String {
+        match &self.note {
+            Some(note) => format!("{} #{} {}", self.mode.name(), self.seed, note),
+            None => format!("{} #{}", self.mode.name(), self.seed),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BookmarkBrowser {
+    selected: usize,
+}
+
+impl BookmarkBrowser {
+    pub fn previous(&mut self, bookmarks: &[Bookmark]) {
+        if self.selected == 0 {
+            self.selected = bookmarks.len().saturating_sub(1);
+        } else {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn next(&mut self, bookmarks: &[Bookmark]) {
+        if self.selected + 1 >= bookmarks.len() {
+            self.selected = 0;
+        } else {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selected_bookmark<'a>(&self, bookmarks: &'a [Bookmark]) -> Option<&'a Bookmark> {
+        bookmarks.iter().rev().nth(self.selected)
+    }
+
+    pub fn draw(&self, bookmarks: &[Bookmark]) -> Result<(), Error> {
+        if bookmarks.is_empty() {
+            return draw_panel(&[String::from("No bookmarked deals yet")], None);
+        }
+        let first = self.selected.saturating_sub(VISIBLE_BOOKMARKS - 1);
+        let lines: Vec<String> = bookmarks
+            .iter()
+            .rev()
+            .skip(first)
+            .take(VISIBLE_BOOKMARKS)
+            .map(Bookmark::label)
+            .collect();
+        draw_panel(&lines, Some(self.selected - first))
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    panel::{PANEL_MARGIN, PANEL_TOP},
+    stats::GameResult,
+};
+use alloc::{collections::BTreeMap, format, string::String};
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDColor, LCDSolidColor, LCD_COLUMNS},
+};
+
+// Daily deals count days from the Playdate epoch, 2000-01-01.
+const DAYS_BEFORE_EPOCH: i64 = 10_957;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const CELL_WIDTH: i32 = 36;
+const CELL_HEIGHT: i32 = 22;
+const MARK_SIZE: i32 = 6;
+
+// The day number of the player's local date, given the system clock's seconds
+// since the epoch (UTC) and the timezone offset in seconds east of UTC.
+pub fn local_day(seconds: usize, timezone_offset: i32) -> u32 {
+    ((seconds as i64 + i64::from(timezone_offset)).max(0) / SECONDS_PER_DAY) as u32
+}
+
+pub fn date_for_day(day: u32) -> (i32, u32, u32) {
+    let days = day as i64 + DAYS_BEFORE_EPOCH + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = (year_of_era + era * 400) as i32 + if month <= 2 { 1 } else { 0 };
+    (year, month, day_of_month)
+}
+
+// Monday is 0; the epoch fell on a Saturday.
+fn weekday(day: u32) -> u32 {
+    (day + 5) % 7
+}
+
+pub fn date_label(day: u32) -> String {
+    let (year, month, day_of_month) = date_for_day(day);
+    format!(
+        "{} {} {}",
+        day_of_month,
+        &MONTH_NAMES[month as usize - 1][..3],
+        year
+    )
+}
+
+#[derive(Debug)]
+pub struct CalendarView {
+    today: u32,
+    selected: u32,
+}
+
+impl CalendarView {
+    pub fn new(today: u32) -> Self {
+        Self {
+            today,
+            selected: today,
+        }
+    }
+
+    pub fn move_by(&mut self, delta: i32) {
+        let selected = self.selected as i64 + delta as i64;
+        self.selected = selected.max(0).min(self.today as i64) as u32;
+    }
+
+    pub fn selected_day(&self) -> u32 {
+        self.selected
+    }
+
+    fn month_start(&self) -> u32 {
+        let (_, _, day_of_month) = date_for_day(self.selected);
+        self.selected + 1 - day_of_month
+    }
+
+    pub fn draw(&self, results: Option<&BTreeMap<u32, GameResult>>) -> Result<(), Error> {
+        let graphics = Graphics::get();
+        let first = self.month_start();
+        let (year, month, _) = date_for_day(first);
+        let first_column = weekday(first) as i32;
+        let mut days_in_month = 28;
+        while date_for_day(first + days_in_month).1 == month {
+            days_in_month += 1;
+        }
+        let rows = (first_column + days_in_month as i32 + 6) / 7;
+
+        let width = CELL_WIDTH * 7 + PANEL_MARGIN * 2;
+        let height = CELL_HEIGHT * (rows + 3) + PANEL_MARGIN * 2;
+        let left = (LCD_COLUMNS as i32 - width) / 2;
+        let rect = ScreenRect::new(
+            ScreenPoint::new(left, PANEL_TOP),
+            ScreenSize::new(width, height),
+        );
+        graphics.fill_rect(rect, LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+        graphics.draw_rect(rect, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+
+        let grid_left = left + PANEL_MARGIN;
+        let mut top = PANEL_TOP + PANEL_MARGIN;
+        graphics.draw_text(
+            &format!("{} {}", MONTH_NAMES[month as usize - 1], year),
+            ScreenPoint::new(grid_left, top),
+        )?;
+        top += CELL_HEIGHT;
+        for (column, label) in ["M", "T", "W", "T", "F", "S", "S"].iter().enumerate() {
+            graphics.draw_text(
+                label,
+                ScreenPoint::new(grid_left + column as i32 * CELL_WIDTH + 4, top),
+            )?;
+        }
+        top += CELL_HEIGHT;
+
+        for offset in 0..days_in_month {
+            let day = first + offset;
+            let slot = first_column + offset as i32;
+            let origin = ScreenPoint::new(
+                grid_left + (slot % 7) * CELL_WIDTH,
+                top + (slot / 7) * CELL_HEIGHT,
+            );
+            if day <= self.today {
+                graphics.draw_text(
+                    &format!("{}", offset + 1),
+                    origin + ScreenPoint::new(4, 2).to_vector(),
+                )?;
+            }
+            let mark = ScreenRect::new(
+                origin + ScreenPoint::new(CELL_WIDTH - MARK_SIZE - 3, 3).to_vector(),
+                ScreenSize::new(MARK_SIZE, MARK_SIZE),
+            );
+            match results.and_then(|results| results.get(&day)) {
+                Some(GameResult::Won) => graphics.fill_ellipse(
+                    mark,
+                    0.0,
+                    360.0,
+                    LCDColor::Solid(LCDSolidColor::kColorBlack),
+                )?,
+                Some(GameResult::Lost) | Some(GameResult::Abandoned) => graphics.draw_ellipse(
+                    mark,
+                    1,
+                    0.0,
+                    360.0,
+                    LCDColor::Solid(LCDSolidColor::kColorBlack),
+                )?,
+                None => (),
+            }
+            if day == self.selected {
+                graphics.draw_rect(
+                    ScreenRect::new(origin, ScreenSize::new(CELL_WIDTH - 1, CELL_HEIGHT - 1)),
+                    LCDColor::Solid(LCDSolidColor::kColorBlack),
+                )?;
+            }
+        }
+
+        let status = match results.and_then(|results| results.get(&self.selected)) {
+            Some(GameResult::Won) => "won",
+            Some(GameResult::Lost) | Some(GameResult::Abandoned) => "not won yet",
+            None => "not played",
+        };
+        graphics.draw_text(
+            &format!("{}: {}", date_label(self.selected), status),
+            ScreenPoint::new(grid_left, top + rows * CELL_HEIGHT),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        assert_eq!((2000, 1, 1), date_for_day(0));
+        assert_eq!(5, weekday(0));
+        assert_eq!((2000, 2, 29), date_for_day(59));
+        assert_eq!((2000, 3, 1), date_for_day(60));
+        assert_eq!((2001, 1, 1), date_for_day(366));
+        assert_eq!((2024, 2, 29), date_for_day(8825));
+        assert_eq!(3, weekday(8825));
+
+        let evening = 8825 * 86_400 + 22 * 3_600;
+        assert_eq!(8825, local_day(evening, 0));
+        assert_eq!(8826, local_day(evening, 3 * 3_600));
+        assert_eq!(8824, local_day(8825 * 86_400, -5 * 3_600));
+        assert_eq!(0, local_day(0, -3_600));
+
+        let mut view = CalendarView::new(60);
+        assert_eq!(60, view.month_start());
+        view.move_by(-7);
+        assert_eq!(31, view.month_start());
+        view.move_by(30);
+        assert_eq!(60, view.selected_day());
+        view.move_by(-100);
+        assert_eq!(0, view.selected_day());
+    }
+}
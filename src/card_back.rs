@@ -0,0 +1,117 @@
+use crate::{
+    panel::{draw_panel, LINE_HEIGHT, PANEL_MARGIN, PANEL_TOP},
+    CARD_WIDTH, SCREEN_CLIP,
+};
+use alloc::{format, string::String, vec};
+use anyhow::Error;
+use crankstart::{
+    geometry::ScreenPoint,
+    graphics::{Bitmap, LCDBitmapDrawMode, LCDBitmapFlip, LCD_COLUMNS},
+};
+use enum_iterator::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+
+const PREVIEW_LINES: usize = 4;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, IntoEnumIterator, PartialEq, Serialize)]
+pub enum CardBack {
+    // Settings saved with the old picture back, which was really the joker's
+    // cell, get this one.
+    #[serde(alias = "Picture")]
+    Mosaic,
+    Lattice,
+    Stripes,
+}
+
+impl CardBack {
+    pub fn bitmap_index(self) -> usize {
+        match self {
+            CardBack::Mosaic => 2,
+            CardBack::Lattice => 3,
+            CardBack::Stripes => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CardBack::Mosaic => "Mosaic",
+            CardBack::Lattice => "Lattice",
+            CardBack::Stripes => "Stripes",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CardBack::Mosaic => CardBack::Lattice,
+            CardBack::Lattice => CardBack::Stripes,
+            CardBack::Stripes => CardBack::Mosaic,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            CardBack::Mosaic => CardBack::Stripes,
+            CardBack::Lattice => CardBack::Mosaic,
+            CardBack::Stripes => CardBack::Lattice,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CardBackPicker {
+    pub selected: CardBack,
+}
+
+impl CardBackPicker {
+    pub fn new(current: CardBack) -> Self {
+        Self { selected: current }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.previous();
+    }
+
+    pub fn next(&mut self) {
+        self.selected = self.selected.next();
+    }
+
+    pub fn draw(&self, preview: &Bitmap) -> Result<(), Error> {
+        let mut lines = vec![format!("Card back: {}", self.selected.name())];
+        lines.extend((0..PREVIEW_LINES).map(|_| String::new()));
+        lines.push(String::from("Left/Right to browse, A to choose"));
+        draw_panel(&lines, None)?;
+        let position = ScreenPoint::new(
+            (LCD_COLUMNS as i32 - CARD_WIDTH) / 2,
+            PANEL_TOP + PANEL_MARGIN + LINE_HEIGHT,
+        );
+        preview.draw(
+            None,
+            None,
+            position,
+            LCDBitmapDrawMode::kDrawModeCopy,
+            LCDBitmapFlip::kBitmapUnflipped,
+            SCREEN_CLIP,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_backs_use_back_cells() {
+        // Cells 2 to 4 of the card sheet are backs; cell 1 is the joker.
+        let cells: Vec<usize> = CardBack::into_enum_iter()
+            .map(CardBack::bitmap_index)
+            .collect();
+        assert_eq!(vec![2, 3, 4], cells);
+        assert_eq!(CardBack::Mosaic, CardBack::Stripes.next());
+        assert_eq!(
+            CardBack::Mosaic,
+            serde_json::from_str::<CardBack>("\"Picture\"").expect("old back")
+        );
+    }
+}
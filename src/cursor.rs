@@ -0,0 +1,130 @@
+use crate::klondike::{
+    AppliedCommand, CardIndex, CardLocation, KlondikeError, Play, StackId, Table, TableCommand,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursor {
+    pub source: CardLocation,
+    pub target: StackId,
+}
+
+impl Cursor {
+    pub fn new(table: &Table) -> Self {
+        Self {
+            source: CardLocation::new(
+                StackId::Stock,
+                table
+                    .get_stack(StackId::Stock)
+                    .next_active_card(None)
+                    .unwrap_or(CardIndex::BOTTOM),
+            ),
+            target: StackId::Stock,
+        }
+    }
+
+    pub fn hand_taken(&mut self, table: &Table) {
+        if let Some(origin) = table.hand_origin {
+            self.target = table.best_target().unwrap_or(origin.stack);
+        }
+    }
+
+    // Dropping cards back where they came from isn't a move, so there's no
+    // command to hand back.
+    pub fn put_hand_on_target(
+        &mut self,
+        table: &mut Table,
+    ) -> Result<Option<AppliedCommand>, KlondikeError> {
+        let target = self.target;
+        let origin = table.hand_origin.ok_or(KlondikeError::HandEmpty)?;
+        if origin.stack == target {
+            self.return_hand(table)?;
+            return Ok(None);
+        }
+        table.check_drop(target)?;
+        table.return_hand()?;
+        let index = CardIndex::new(table.get_stack(target).len());
+        let play = Play::MoveCards(CardLocation::new(origin.stack, origin.index), target);
+        let applied = TableCommand::new(play).apply(table)?;
+        self.source = CardLocation {
+            stack: target,
+            index: index.min(table.get_stack(target).top_card_index()),
+        };
+        Ok(Some(applied))
+    }
+
+    pub fn return_hand(&mut self, table: &mut Table) -> Result<(), KlondikeError> {
+        let origin = table.hand_origin.ok_or(KlondikeError::HandEmpty)?;
+        table.return_hand()?;
+        self.source = CardLocation::new(origin.stack, origin.index);
+        self.target = origin.stack;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::{ActiveCardIterator, CardPlayIterator, TABLEAUX};
+
+    const TEST_SEED: u64 = 324;
+
+    #[test]
+    fn test_target_follows_hand() {
+        let mut table = Table::new(TEST_SEED);
+        let mut cursor = Cursor::new(&table);
+        let play = ActiveCardIterator::new(&table)
+            .flat_map(|source| {
+                let card = table.get_stack(source.stack).get_card(source.index);
+                CardPlayIterator::new(&table, card.expect("card"), source)
+            })
+            .next()
+            .expect("play");
+        let (source, target) = match play {
+            Play::MoveCards(source, target) => (source, target),
+            _ => panic!("unexpected play {:?}", play),
+        };
+
+        table
+            .take_selected_cards_from_stack(source.stack, source.index)
+            .expect("take_selected_cards_from_stack");
+        cursor.hand_taken(&table);
+        assert_eq!(target, cursor.target);
+
+        cursor
+            .put_hand_on_target(&mut table)
+            .expect("put_hand_on_target");
+        assert_eq!(target, cursor.target);
+        assert_eq!(target, cursor.source.stack);
+    }
+
+    #[test]
+    fn test_target_without_destination() {
+        let mut table = Table::new(TEST_SEED);
+        let mut cursor = Cursor::new(&table);
+        let source = TABLEAUX
+            .iter()
+            .map(|stack_id| {
+                CardLocation::new(*stack_id, table.get_stack(*stack_id).top_card_index())
+            })
+            .find(|source| {
+                let card = table.get_stack(source.stack).get_card(source.index);
+                CardPlayIterator::new(&table, card.expect("card"), *source)
+                    .next()
+                    .is_none()
+            })
+            .expect("source");
+
+        table
+            .take_selected_cards_from_stack(source.stack, source.index)
+            .expect("take_selected_cards_from_stack");
+        cursor.hand_taken(&table);
+        assert_eq!(source.stack, cursor.target);
+
+        cursor
+            .put_hand_on_target(&mut table)
+            .expect("put_hand_on_target");
+        assert_eq!(source, cursor.source);
+        assert_eq!(source.stack, cursor.target);
+        assert!(!table.cards_in_hand());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{animation::AnimationEvent, klondike::StackId, StackView, MARGIN, SCREEN_WIDTH};
+use alloc::format;
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize, ScreenVector},
+    graphics::{Graphics, LCDColor, LCDSolidColor},
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+const THUNK_DURATION_MS: usize = 150;
+const THUNK_DEPTH: i32 = 3;
+const FLASH_DURATION_MS: usize = 450;
+const FLASH_BLINK_MS: usize = 75;
+const SHAKE_DURATION_MS: usize = 240;
+const SHAKE_STEP_MS: usize = 30;
+const SHAKE_AMPLITUDE: i32 = 3;
+const COMBO_DURATION_MS: usize = 900;
+const COMBO_RISE: i32 = 12;
+const REASON_DURATION_MS: usize = 1800;
+const REASON_HEIGHT: i32 = 22;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableEvent {
+    CardsLanded(StackId),
+    SuitCompleted(StackId),
+    InvalidMove,
+    Combo(StackId, usize),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct EffectSettings {
+    pub thunk: bool,
+    pub flash: bool,
+    pub shake: bool,
+    pub combo: bool,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self {
+            thunk: true,
+            flash: true,
+            shake: true,
+            combo: true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Effects {
+    thunk: Option<(StackId, usize)>,
+    flash: Option<(StackId, usize)>,
+    shake: Option<usize>,
+    combo: Option<(StackId, usize, usize)>,
+    reason: Option<(&'static str, usize)>,
+    // A landing with the thunk turned off, reported on the next tick.
+    landed: Option<StackId>,
+}
+
+impl Effects {
+    // Shows why the last action was refused. Not an effect the player can
+    // turn off, since it's the only place the reason appears.
+    pub fn explain(&mut self, reason: &'static str) {
+        self.reason = Some((reason, 0));
+    }
+
+    pub fn trigger(&mut self, event: TableEvent, settings: &EffectSettings) {
+        match event {
+            TableEvent::CardsLanded(stack_id) => {
+                if settings.thunk {
+                    self.thunk = Some((stack_id, 0));
+                    self.landed = None;
+                } else {
+                    self.landed = Some(stack_id);
+                }
+            }
+            TableEvent::SuitCompleted(stack_id) => {
+                if settings.flash {
+                    self.flash = Some((stack_id, 0));
+                }
+            }
+            TableEvent::InvalidMove => {
+                if settings.shake {
+                    self.shake = Some(0);
+                }
+            }
+            TableEvent::Combo(stack_id, count) => {
+                if settings.combo {
+                    self.combo = Some((stack_id, count, 0));
+                }
+            }
+        }
+    }
+
+    // Returns the landing whose thunk settled this tick.
+    pub fn tick(&mut self, delta_ms: usize) -> Option<AnimationEvent> {
+        let thunk = self
+            .thunk
+            .map(|(stack_id, elapsed)| (stack_id, elapsed + delta_ms));
+        self.thunk = thunk.filter(|(_, elapsed)| *elapsed < THUNK_DURATION_MS);
+        let landed = match (thunk, self.thunk) {
+            (Some((stack_id, _)), None) => Some(stack_id),
+            _ => self.landed.take(),
+        };
+        self.flash = self
+            .flash
+            .map(|(stack_id, elapsed)| (stack_id, elapsed + delta_ms))
+            .filter(|(_, elapsed)| *elapsed < FLASH_DURATION_MS);
+        self.shake = self
+            .shake
+            .map(|elapsed| elapsed + delta_ms)
+            .filter(|elapsed| *elapsed < SHAKE_DURATION_MS);
+        self.combo = self
+            .combo
+            .map(|(stack_id, count, elapsed)| (stack_id, count, elapsed + delta_ms))
+            .filter(|(_, _, elapsed)| *elapsed < COMBO_DURATION_MS);
+        self.reason = self
+            .reason
+            .map(|(reason, elapsed)| (reason, elapsed + delta_ms))
+            .filter(|(_, elapsed)| *elapsed < REASON_DURATION_MS);
+        landed.map(AnimationEvent::CardsLanded)
+    }
+
+    pub fn stack_offset(&self, stack_id: StackId) -> ScreenVector {
+        match self.thunk {
+            Some((thunk_id, elapsed)) if thunk_id == stack_id => {
+                let remaining = (THUNK_DURATION_MS - elapsed) as i32;
+                ScreenVector::new(0, THUNK_DEPTH * remaining / THUNK_DURATION_MS as i32)
+            }
+            _ => ScreenVector::zero(),
+        }
+    }
+
+    pub fn shake_offset(&self) -> ScreenVector {
+        match self.shake {
+            Some(elapsed) => {
+                let remaining = (SHAKE_DURATION_MS - elapsed) as i32;
+                let amplitude = SHAKE_AMPLITUDE * remaining / SHAKE_DURATION_MS as i32;
+                if (elapsed / SHAKE_STEP_MS) & 1 == 0 {
+                    ScreenVector::new(amplitude, 0)
+                } else {
+                    ScreenVector::new(-amplitude, 0)
+                }
+            }
+            None => ScreenVector::zero(),
+        }
+    }
+
+    pub fn draw_flash(&self, views: &HashMap<StackId, StackView>) -> Result<(), Error> {
+        if let Some((stack_id, elapsed)) = self.flash {
+            if (elapsed / FLASH_BLINK_MS) & 1 == 0 {
+                if let Some(view) = views.get(&stack_id) {
+                    Graphics::get().fill_rect(
+                        ScreenRect::new(view.position, view.metrics.size()),
+                        LCDColor::Solid(LCDSolidColor::kColorXOR),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The combo count floats up from the foundation it landed on.
+    pub fn draw_combo(&self, views: &HashMap<StackId, StackView>) -> Result<(), Error> {
+        if let Some((stack_id, count, elapsed)) = self.combo {
+            if let Some(view) = views.get(&stack_id) {
+                let rise = COMBO_RISE * elapsed as i32 / COMBO_DURATION_MS as i32;
+                Graphics::get().draw_text(
+                    &format!("x{}!", count),
+                    view.position + ScreenVector::new(4, -rise),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw_reason(&self, bottom: i32) -> Result<(), Error> {
+        if let Some((reason, _)) = self.reason {
+            let graphics = Graphics::get();
+            let rect = ScreenRect::new(
+                ScreenPoint::new(MARGIN, bottom - REASON_HEIGHT - 4),
+                ScreenSize::new(SCREEN_WIDTH - 2 * MARGIN, REASON_HEIGHT),
+            );
+            graphics.fill_rect(rect, LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+            graphics.draw_rect(rect, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+            graphics.draw_text(reason, rect.origin + ScreenVector::new(6, 2))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_disabled_effects_do_not_trigger() {
+        let settings = EffectSettings {
+            thunk: false,
+            flash: true,
+            shake: false,
+            combo: true,
+        };
+        let mut effects = Effects::default();
+        effects.trigger(TableEvent::CardsLanded(StackId::Tableau1), &settings);
+        effects.trigger(TableEvent::InvalidMove, &settings);
+        assert_eq!(
+            ScreenVector::zero(),
+            effects.stack_offset(StackId::Tableau1)
+        );
+        assert_eq!(ScreenVector::zero(), effects.shake_offset());
+
+        effects.trigger(
+            TableEvent::CardsLanded(StackId::Tableau1),
+            &EffectSettings::default(),
+        );
+        assert_ne!(
+            ScreenVector::zero(),
+            effects.stack_offset(StackId::Tableau1)
+        );
+        assert_eq!(None, effects.tick(THUNK_DURATION_MS / 2));
+        assert_eq!(
+            Some(AnimationEvent::CardsLanded(StackId::Tableau1)),
+            effects.tick(THUNK_DURATION_MS)
+        );
+        assert_eq!(
+            ScreenVector::zero(),
+            effects.stack_offset(StackId::Tableau1)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    history::{History, HistoryEntry},
+    stats::{GameResult, Stats},
+    storage,
+};
+use alloc::{format, string::String};
+use anyhow::Error;
+use serde::Serialize;
+
+pub const STATS_EXPORT_PATH: &str = "stats_export.json";
+pub const HISTORY_EXPORT_PATH: &str = "history_export.csv";
+
+const HISTORY_HEADER: &str = "seed,mode,result,elapsed_ms,moves,undos,score,relaxed";
+
+#[derive(Serialize)]
+struct StatsExport<'a> {
+    stats: &'a Stats,
+    history: &'a History,
+}
+
+fn history_row(entry: &HistoryEntry) -> String {
+    let result = match entry.result {
+        GameResult::Won => "won",
+        GameResult::Lost => "lost",
+        GameResult::Abandoned => "abandoned",
+    };
+    format!(
+        "{},{},{},{},{},{},{},{}",
+        entry.seed,
+        entry.mode.name(),
+        result,
+        entry.elapsed_ms,
+        entry.moves,
+        entry.undos,
+        entry.score,
+        entry.relaxed
+    )
+}
+
+pub fn history_csv(history: &History) -> String {
+    let mut csv = String::from(HISTORY_HEADER);
+    csv.push('\n');
+    for entry in &history.entries {
+        csv.push_str(&history_row(entry));
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn export(stats: &Stats, history: &History) -> Result<(), Error> {
+    let json = serde_json::to_string_pretty(&StatsExport { stats, history }).map_err(Error::msg)?;
+    storage::write_text(STATS_EXPORT_PATH, &json)?;
+    storage::write_text(HISTORY_EXPORT_PATH, &history_csv(history))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scoring::ScoringMode, session::Session, stats::GameMode};
+
+    #[test]
+    fn test_history_csv() {
+        let mut history = History::default();
+        let mut session = Session::new(42, GameMode::Spiderette, ScoringMode::Standard);
+        session.moves = 80;
+        session.elapsed_ms = 61_000;
+        history.record(&session, GameResult::Won);
+        session.relaxed = true;
+        history.record(&session, GameResult::Lost);
+        assert_eq!(
+            "seed,mode,result,elapsed_ms,moves,undos,score,relaxed\n\
+             42,Spiderette,won,61000,80,0,0,false\n\
+             42,Spiderette,lost,61000,80,0,0,true\n",
+            history_csv(&history)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize, ScreenVector},
+    graphics::{Graphics, LCDColor, LCDSolidColor},
+};
+
+const ANT_DASH: i32 = 4;
+const ANT_STEP_MS: usize = 80;
+const MARQUEE_OUTSET: i32 = 2;
+const PULSE_STEP_MS: usize = 120;
+const PULSE_MAX_OUTSET: i32 = 3;
+const GHOST_DOT: i32 = 1;
+const PLAYABLE_TAB_WIDTH: i32 = 8;
+const PLAYABLE_TAB_HEIGHT: i32 = 3;
+
+fn black() -> LCDColor {
+    LCDColor::Solid(LCDSolidColor::kColorBlack)
+}
+
+fn outset(rect: ScreenRect, amount: i32) -> ScreenRect {
+    rect.inflate(amount, amount)
+}
+
+fn draw_ants(
+    graphics: &Graphics,
+    from: ScreenPoint,
+    step: ScreenVector,
+    length: i32,
+    dash: i32,
+    phase: i32,
+) -> Result<(), Error> {
+    let mut position = 0;
+    while position < length {
+        let dash_end = (position + dash).min(length);
+        if (position + phase) / dash % 2 == 0 {
+            graphics.draw_line(
+                from + step * position,
+                from + step * (dash_end - 1),
+                1,
+                black(),
+            )?;
+        }
+        position = dash_end;
+    }
+    Ok(())
+}
+
+pub fn draw_marquee(rect: ScreenRect, clock_ms: usize) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    let rect = outset(rect, MARQUEE_OUTSET);
+    let phase = (clock_ms / ANT_STEP_MS) as i32 % (ANT_DASH * 2);
+    let width = rect.size.width;
+    let height = rect.size.height;
+    let top_left = rect.origin;
+    let top_right = ScreenPoint::new(rect.max_x(), rect.min_y());
+    let bottom_right = ScreenPoint::new(rect.max_x(), rect.max_y());
+    let bottom_left = ScreenPoint::new(rect.min_x(), rect.max_y());
+    draw_ants(
+        &graphics,
+        top_left,
+        ScreenVector::new(1, 0),
+        width,
+        ANT_DASH,
+        ANT_DASH * 2 - phase,
+    )?;
+    draw_ants(
+        &graphics,
+        top_right,
+        ScreenVector::new(0, 1),
+        height,
+        ANT_DASH,
+        ANT_DASH * 2 - phase + width,
+    )?;
+    draw_ants(
+        &graphics,
+        bottom_right,
+        ScreenVector::new(-1, 0),
+        width,
+        ANT_DASH,
+        ANT_DASH * 2 - phase + width + height,
+    )?;
+    draw_ants(
+        &graphics,
+        bottom_left,
+        ScreenVector::new(0, -1),
+        height,
+        ANT_DASH,
+        ANT_DASH * 2 - phase + width * 2 + height,
+    )?;
+    Ok(())
+}
+
+pub fn draw_pulse(rect: ScreenRect, clock_ms: usize) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    let step = (clock_ms / PULSE_STEP_MS) as i32 % (PULSE_MAX_OUTSET * 2);
+    let amount = if step < PULSE_MAX_OUTSET {
+        step
+    } else {
+        PULSE_MAX_OUTSET * 2 - step
+    };
+    graphics.draw_rect(outset(rect, amount + 1), black())?;
+    graphics.draw_rect(outset(rect, amount + 2), black())?;
+    Ok(())
+}
+
+fn draw_dotted_rect(graphics: &Graphics, rect: ScreenRect) -> Result<(), Error> {
+    let width = rect.size.width;
+    let height = rect.size.height;
+    let top_right = ScreenPoint::new(rect.max_x() - 1, rect.min_y());
+    let bottom_left = ScreenPoint::new(rect.min_x(), rect.max_y() - 1);
+    draw_ants(
+        graphics,
+        rect.origin,
+        ScreenVector::new(1, 0),
+        width,
+        GHOST_DOT,
+        0,
+    )?;
+    draw_ants(
+        graphics,
+        rect.origin,
+        ScreenVector::new(0, 1),
+        height,
+        GHOST_DOT,
+        0,
+    )?;
+    draw_ants(
+        graphics,
+        bottom_left,
+        ScreenVector::new(1, 0),
+        width,
+        GHOST_DOT,
+        height - 1,
+    )?;
+    draw_ants(
+        graphics,
+        top_right,
+        ScreenVector::new(0, 1),
+        height,
+        GHOST_DOT,
+        width - 1,
+    )?;
+    Ok(())
+}
+
+pub fn draw_ghost(card_rects: &[ScreenRect]) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    for rect in card_rects {
+        draw_dotted_rect(&graphics, *rect)?;
+    }
+    Ok(())
+}
+
+pub fn draw_playable_tabs(card_rects: &[ScreenRect]) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    for rect in card_rects {
+        let tab = ScreenRect::new(
+            ScreenPoint::new(
+                rect.origin.x + (rect.size.width - PLAYABLE_TAB_WIDTH) / 2,
+                rect.origin.y - PLAYABLE_TAB_HEIGHT,
+            ),
+            ScreenSize::new(PLAYABLE_TAB_WIDTH, PLAYABLE_TAB_HEIGHT),
+        );
+        graphics.fill_rect(tab, black())?;
+    }
+    Ok(())
+}
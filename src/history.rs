@@ -0,0 +1,157 @@
+use crate::{
+    panel::draw_panel,
+    session::{format_time, Session},
+    stats::{GameMode, GameResult},
+    storage::Versioned,
+    wildcard::Modifier,
+};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+pub const HISTORY_PATH: &str = "history.json";
+pub const HISTORY_LIMIT: usize = 50;
+
+const VISIBLE_ENTRIES: usize = 8;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HistoryEntry {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub result: GameResult,
+    pub elapsed_ms: usize,
+    pub moves: usize,
+    #[serde(default)]
+    pub undos: usize,
+    pub score: i32,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(default)]
+    pub relaxed: bool,
+}
+
+impl HistoryEntry {
+    fn label(&self) -> String {
+        let result = match self.result {
+            GameResult::Won => "Won",
+            GameResult::Lost => "Lost",
+            GameResult::Abandoned => "Quit",
+        };
+        let wild = if self.modifiers.is_empty() { "" } else { " *" };
+        let relaxed = if self.relaxed { " R" } else { "" };
+        format!(
+            "#{} {} {} {}m {}pts{}{}",
+            self.seed,
+            result,
+            format_time(self.elapsed_ms),
+            self.moves,
+            self.score,
+            wild,
+            relaxed
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl Versioned for History {
+    const VERSION: u32 = 1;
+}
+
+impl History {
+    pub fn record(&mut self, session: &Session, result: GameResult) {
+        self.entries.push(HistoryEntry {
+            seed: session.seed,
+            mode: session.mode,
+            result,
+            elapsed_ms: session.elapsed_ms,
+            moves: session.moves,
+            undos: session.undos,
+            score: session.score,
+            modifiers: session.modifiers.clone(),
+            relaxed: session.relaxed,
+        });
+        if self.entries.len() > HISTORY_LIMIT {
+            let excess = self.entries.len() - HISTORY_LIMIT;
+            self.entries.drain(..excess);
+        }
+    }
+
+    pub fn newest_first(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HistoryBrowser {
+    selected: usize,
+}
+
+impl HistoryBrowser {
+    pub fn previous(&mut self, history: &History) {
+        if self.selected == 0 {
+            self.selected = history.entries.len().saturating_sub(1);
+        } else {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn next(&mut self, history: &History) {
+        if self.selected + 1 >= history.entries.len() {
+            self.selected = 0;
+        } else {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selected_entry<'a>(&self, history: &'a History) -> Option<&'a HistoryEntry> {
+        history.newest_first().nth(self.selected)
+    }
+
+    pub fn draw(&self, history: &History) -> Result<(), Error> {
+        if history.entries.is_empty() {
+            return draw_panel(&[String::from("No games played yet")], None);
+        }
+        let first = self.selected.saturating_sub(VISIBLE_ENTRIES - 1);
+        let lines: Vec<String> = history
+            .newest_first()
+            .skip(first)
+            .take(VISIBLE_ENTRIES)
+            .map(HistoryEntry::label)
+            .collect();
+        draw_panel(&lines, Some(self.selected - first))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scoring::ScoringMode;
+
+    #[test]
+    fn test_history_limit() {
+        let mut history = History::default();
+        for seed in 0..(HISTORY_LIMIT as u64 + 5) {
+            history.record(
+                &Session::new(seed, GameMode::Klondike, ScoringMode::Standard),
+                GameResult::Lost,
+            );
+        }
+        assert_eq!(HISTORY_LIMIT, history.entries.len());
+        assert_eq!(Some(5), history.entries.first().map(|entry| entry.seed));
+
+        let mut browser = HistoryBrowser::default();
+        assert_eq!(
+            Some(HISTORY_LIMIT as u64 + 4),
+            browser.selected_entry(&history).map(|entry| entry.seed)
+        );
+        browser.previous(&history);
+        assert_eq!(
+            Some(5),
+            browser.selected_entry(&history).map(|entry| entry.seed)
+        );
+    }
+}
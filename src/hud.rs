@@ -0,0 +1,83 @@
+use crate::{
+    klondike::TableView,
+    layout::hud_rect,
+    session::{format_time, Session},
+    undo::UndoHistory,
+};
+use alloc::format;
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDColor, LCDSolidColor},
+};
+
+const BADGE_SIZE: i32 = 16;
+
+pub fn draw_redeals_badge(position: ScreenPoint, redeals: usize) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    let rect = ScreenRect::new(
+        position - ScreenPoint::new(BADGE_SIZE / 2, BADGE_SIZE / 2).to_vector(),
+        ScreenSize::new(BADGE_SIZE, BADGE_SIZE),
+    );
+    graphics.fill_ellipse(
+        rect,
+        0.0,
+        360.0,
+        LCDColor::Solid(LCDSolidColor::kColorWhite),
+    )?;
+    graphics.draw_ellipse(
+        rect,
+        1,
+        0.0,
+        360.0,
+        LCDColor::Solid(LCDSolidColor::kColorBlack),
+    )?;
+    graphics.draw_text(
+        &format!("{}", redeals),
+        rect.origin + ScreenPoint::new(5, 0).to_vector(),
+    )?;
+    Ok(())
+}
+
+pub fn draw_hud(session: &Session, table: TableView, undo: &UndoHistory) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    let rect = hud_rect();
+    graphics.fill_rect(rect, LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+    graphics.draw_line(
+        rect.origin,
+        ScreenPoint::new(rect.max_x(), rect.origin.y),
+        1,
+        LCDColor::Solid(LCDSolidColor::kColorBlack),
+    )?;
+    let cycles = match table.rules().max_recycles {
+        Some(max_recycles) => format!("{}/{}", table.stock_cycles_used(), max_recycles),
+        None => format!("{}", table.stock_cycles_used()),
+    };
+    let moves = match session.moves_remaining() {
+        Some(remaining) => format!("{} left", remaining),
+        None => format!("{}", session.moves),
+    };
+    let score = match session.speed_percent() {
+        Some(percent) if percent > 100 => {
+            format!(
+                "{} x{}.{}",
+                session.score,
+                percent / 100,
+                percent % 100 / 10
+            )
+        }
+        _ => format!("{}", session.score),
+    };
+    let mut text = format!(
+        "{}  Moves: {}  Score: {}  Cycles: {}",
+        format_time(session.clock_ms()),
+        moves,
+        score,
+        cycles
+    );
+    if let Some(undos_remaining) = undo.undos_remaining() {
+        text.push_str(&format!("  Undo: {}", undos_remaining));
+    }
+    graphics.draw_text(&text, rect.origin + ScreenPoint::new(4, 1).to_vector())?;
+    Ok(())
+}
@@ -0,0 +1,371 @@
+use alloc::{vec, vec::Vec};
+use crankstart::system::PDButtons;
+use serde::{Deserialize, Serialize};
+
+const CRANK_THRESHHOLD: i32 = 10;
+const REPEAT_BUTTONS: &[PDButtons] = &[
+    PDButtons::kButtonLeft,
+    PDButtons::kButtonRight,
+    PDButtons::kButtonUp,
+    PDButtons::kButtonDown,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Next,
+    Previous,
+    Confirm,
+    Cancel,
+    Undo,
+    OpenMenu,
+    Hint,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ControlScheme {
+    Standard,
+    CrankOnly,
+}
+
+impl ControlScheme {
+    pub fn name(self) -> &'static str {
+        match self {
+            ControlScheme::Standard => "Standard",
+            ControlScheme::CrankOnly => "Crank only",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ControlScheme::Standard => ControlScheme::CrankOnly,
+            ControlScheme::CrankOnly => ControlScheme::Standard,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RepeatTimings {
+    pub initial_delay_ms: usize,
+    pub start_interval_ms: usize,
+    pub min_interval_ms: usize,
+    pub acceleration_percent: usize,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum RepeatSpeed {
+    Off,
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl RepeatSpeed {
+    pub fn timings(self) -> Option<RepeatTimings> {
+        match self {
+            RepeatSpeed::Off => None,
+            RepeatSpeed::Slow => Some(RepeatTimings {
+                initial_delay_ms: 500,
+                start_interval_ms: 200,
+                min_interval_ms: 100,
+                acceleration_percent: 90,
+            }),
+            RepeatSpeed::Normal => Some(RepeatTimings {
+                initial_delay_ms: 350,
+                start_interval_ms: 150,
+                min_interval_ms: 50,
+                acceleration_percent: 80,
+            }),
+            RepeatSpeed::Fast => Some(RepeatTimings {
+                initial_delay_ms: 250,
+                start_interval_ms: 100,
+                min_interval_ms: 30,
+                acceleration_percent: 70,
+            }),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RepeatSpeed::Off => "Off",
+            RepeatSpeed::Slow => "Slow",
+            RepeatSpeed::Normal => "Normal",
+            RepeatSpeed::Fast => "Fast",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            RepeatSpeed::Off => RepeatSpeed::Slow,
+            RepeatSpeed::Slow => RepeatSpeed::Normal,
+            RepeatSpeed::Normal => RepeatSpeed::Fast,
+            RepeatSpeed::Fast => RepeatSpeed::Off,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct HeldButton {
+    button: PDButtons,
+    held_ms: usize,
+    next_repeat_ms: usize,
+    interval_ms: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RawInput {
+    pub held: PDButtons,
+    pub pushed: PDButtons,
+    pub crank_change: f32,
+    pub crank_docked: bool,
+    pub delta_ms: usize,
+}
+
+impl RawInput {
+    pub fn is_idle(&self) -> bool {
+        self.held == PDButtons(0) && self.pushed == PDButtons(0) && self.crank_change == 0.0
+    }
+
+    fn pressed(&self, button: PDButtons) -> bool {
+        (self.pushed & button) == button
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InputMapper {
+    crank_threshhold: i32,
+    last_docked: Option<bool>,
+    held: Option<HeldButton>,
+}
+
+impl InputMapper {
+    fn repeated(&mut self, input: &RawInput, repeat: RepeatSpeed) -> PDButtons {
+        let none = PDButtons(0);
+        let timings = match repeat.timings() {
+            Some(timings) => timings,
+            None => return none,
+        };
+        let button = match REPEAT_BUTTONS
+            .iter()
+            .find(|button| (input.held & **button) == **button)
+        {
+            Some(button) => *button,
+            None => {
+                self.held = None;
+                return none;
+            }
+        };
+        let mut held = match self.held {
+            Some(held) if held.button == button => held,
+            _ => {
+                self.held = Some(HeldButton {
+                    button,
+                    held_ms: 0,
+                    next_repeat_ms: timings.initial_delay_ms,
+                    interval_ms: timings.start_interval_ms,
+                });
+                return none;
+            }
+        };
+        held.held_ms += input.delta_ms;
+        let fired = held.held_ms >= held.next_repeat_ms;
+        if fired {
+            held.next_repeat_ms = held.held_ms + held.interval_ms;
+            held.interval_ms = (held.interval_ms * timings.acceleration_percent / 100)
+                .max(timings.min_interval_ms);
+        }
+        self.held = Some(held);
+        if fired {
+            button
+        } else {
+            none
+        }
+    }
+
+    fn crank_step(&mut self, change: f32) -> i32 {
+        self.crank_threshhold += change as i32;
+        if self.crank_threshhold > CRANK_THRESHHOLD {
+            self.crank_threshhold = -CRANK_THRESHHOLD;
+            1
+        } else if self.crank_threshhold < -CRANK_THRESHHOLD {
+            self.crank_threshhold = CRANK_THRESHHOLD;
+            -1
+        } else {
+            0
+        }
+    }
+
+    fn just_docked(&mut self, docked: bool) -> bool {
+        let was_docked = self.last_docked.replace(docked);
+        docked && was_docked == Some(false)
+    }
+
+    pub fn commands(
+        &mut self,
+        scheme: ControlScheme,
+        repeat: RepeatSpeed,
+        input: &RawInput,
+        holding: bool,
+    ) -> Vec<Command> {
+        let navigation = PDButtons::kButtonLeft | PDButtons::kButtonRight;
+        let input = &RawInput {
+            pushed: input.pushed | (self.repeated(input, repeat) & navigation),
+            ..*input
+        };
+        let mut commands = match self.crank_step(input.crank_change) {
+            1 => vec![Command::Next],
+            -1 => vec![Command::Previous],
+            _ => Vec::new(),
+        };
+        let just_docked = self.just_docked(input.crank_docked);
+        let button = match scheme {
+            ControlScheme::Standard => {
+                if input.pressed(PDButtons::kButtonUp) && !holding {
+                    Some(Command::OpenMenu)
+                } else if input.pressed(PDButtons::kButtonDown) {
+                    Some(Command::Undo)
+                } else if input.pressed(PDButtons::kButtonB) && holding {
+                    Some(Command::Cancel)
+                } else if input.pressed(PDButtons::kButtonA) || input.pressed(PDButtons::kButtonB) {
+                    Some(Command::Confirm)
+                } else if input.pressed(PDButtons::kButtonLeft) {
+                    Some(Command::Previous)
+                } else if input.pressed(PDButtons::kButtonRight) {
+                    Some(Command::Next)
+                } else {
+                    None
+                }
+            }
+            ControlScheme::CrankOnly => {
+                if just_docked {
+                    Some(Command::Confirm)
+                } else if input.pressed(PDButtons::kButtonA) && holding {
+                    Some(Command::Cancel)
+                } else if input.pressed(PDButtons::kButtonA) {
+                    Some(Command::OpenMenu)
+                } else {
+                    None
+                }
+            }
+        };
+        commands.extend(button);
+        commands
+    }
+
+    pub fn overlay_buttons(
+        &mut self,
+        scheme: ControlScheme,
+        repeat: RepeatSpeed,
+        input: &RawInput,
+    ) -> PDButtons {
+        let pushed = input.pushed | self.repeated(input, repeat);
+        match scheme {
+            ControlScheme::Standard => {
+                self.last_docked = Some(input.crank_docked);
+                pushed
+            }
+            ControlScheme::CrankOnly => {
+                let mut pushed = pushed;
+                match self.crank_step(input.crank_change) {
+                    1 => pushed = pushed | PDButtons::kButtonDown | PDButtons::kButtonRight,
+                    -1 => pushed = pushed | PDButtons::kButtonUp | PDButtons::kButtonLeft,
+                    _ => (),
+                }
+                if self.just_docked(input.crank_docked) {
+                    pushed = pushed | PDButtons::kButtonB;
+                }
+                pushed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn input(pushed: PDButtons, crank_change: f32, crank_docked: bool) -> RawInput {
+        RawInput {
+            held: pushed,
+            pushed,
+            crank_change,
+            crank_docked,
+            delta_ms: 20,
+        }
+    }
+
+    fn held(button: PDButtons, delta_ms: usize) -> RawInput {
+        RawInput {
+            held: button,
+            pushed: PDButtons(0),
+            crank_change: 0.0,
+            crank_docked: true,
+            delta_ms,
+        }
+    }
+
+    #[test]
+    fn test_held_direction_repeats_faster() {
+        let mut mapper = InputMapper::default();
+        let scheme = ControlScheme::Standard;
+        let timings = RepeatSpeed::Normal.timings().expect("timings");
+        let right = PDButtons::kButtonRight;
+        assert!(mapper
+            .commands(scheme, RepeatSpeed::Normal, &held(right, 0), false)
+            .is_empty());
+        let mut fired_at = Vec::new();
+        let mut elapsed = 0;
+        while fired_at.len() < 4 {
+            elapsed += 10;
+            if !mapper
+                .commands(scheme, RepeatSpeed::Normal, &held(right, 10), false)
+                .is_empty()
+            {
+                fired_at.push(elapsed);
+            }
+        }
+        assert_eq!(timings.initial_delay_ms, fired_at[0]);
+        assert!(fired_at[3] - fired_at[2] < fired_at[1] - fired_at[0]);
+        assert!(mapper
+            .commands(scheme, RepeatSpeed::Off, &held(right, 1000), false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_crank_only_docking_confirms() {
+        let mut mapper = InputMapper::default();
+        let none = PDButtons(0);
+        let scheme = ControlScheme::CrankOnly;
+        assert!(mapper
+            .commands(scheme, RepeatSpeed::Off, &input(none, 0.0, true), false)
+            .is_empty());
+        assert!(mapper
+            .commands(scheme, RepeatSpeed::Off, &input(none, 0.0, false), false)
+            .is_empty());
+        assert_eq!(
+            vec![Command::Next],
+            mapper.commands(scheme, RepeatSpeed::Off, &input(none, 15.0, false), false)
+        );
+        assert_eq!(
+            vec![Command::Confirm],
+            mapper.commands(scheme, RepeatSpeed::Off, &input(none, 0.0, true), false)
+        );
+        assert_eq!(
+            vec![Command::Cancel],
+            mapper.commands(
+                scheme,
+                RepeatSpeed::Off,
+                &input(PDButtons::kButtonA, 0.0, true),
+                true
+            )
+        );
+        assert_eq!(
+            vec![Command::Confirm],
+            mapper.commands(
+                ControlScheme::Standard,
+                RepeatSpeed::Off,
+                &input(PDButtons::kButtonA, 0.0, true),
+                false
+            )
+        );
+    }
+}
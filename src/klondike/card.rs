@@ -0,0 +1,318 @@
+use super::*;
+use alloc::{fmt, string::String, vec::Vec};
+use enum_iterator::IntoEnumIterator;
+use rand::{seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Hash)]
+pub enum Color {
+    Black,
+    Red,
+}
+
+#[derive(
+    Clone, Copy, Deserialize, Eq, Hash, IntoEnumIterator, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum Suit {
+    Diamond = 2,
+    Club = 1,
+    Heart = 3,
+    Spade = 4,
+}
+
+impl fmt::Debug for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let s = match self {
+            Suit::Diamond => "♦️",
+            Suit::Club => "♣️",
+            Suit::Heart => "♥️",
+            Suit::Spade => "♠️",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Suit {
+    fn color(&self) -> Color {
+        match self {
+            Suit::Diamond | Suit::Heart => Color::Red,
+            Suit::Club | Suit::Spade => Color::Black,
+        }
+    }
+}
+
+const SUITS: [Suit; 4] = [Suit::Diamond, Suit::Club, Suit::Heart, Suit::Spade];
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    IntoEnumIterator,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub enum Rank {
+    Ace = 1,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+}
+
+impl Rank {
+    pub fn next_wrapping(self) -> Rank {
+        RANKS[self as usize % RANKS.len()]
+    }
+
+    pub fn previous_wrapping(self) -> Rank {
+        match self {
+            Rank::Ace => Rank::King,
+            Rank::Two => Rank::Ace,
+            Rank::Three => Rank::Two,
+            Rank::Four => Rank::Three,
+            Rank::Five => Rank::Four,
+            Rank::Six => Rank::Five,
+            Rank::Seven => Rank::Six,
+            Rank::Eight => Rank::Seven,
+            Rank::Nine => Rank::Eight,
+            Rank::Ten => Rank::Nine,
+            Rank::Jack => Rank::Ten,
+            Rank::Queen => Rank::Jack,
+            Rank::King => Rank::Queen,
+        }
+    }
+}
+
+impl From<Rank> for &'static str {
+    fn from(rank: Rank) -> Self {
+        let label = match rank {
+            Rank::Ace => "A",
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "T",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+        };
+        label
+    }
+}
+
+#[derive(Clone, Deserialize, Eq, Ord, PartialEq, PartialOrd, Hash, Serialize)]
+pub struct Card {
+    pub suit: Suit,
+    pub rank: Rank,
+    pub face_up: bool,
+}
+
+const fn ordered_deck() -> [Card; 52] {
+    let mut cards = [NO_CARD; 52];
+    let mut index = 0;
+    while index < 52 {
+        cards[index] = Card {
+            suit: SUITS[index / 13],
+            rank: RANKS[index % 13],
+            face_up: false,
+        };
+        index += 1;
+    }
+    cards
+}
+
+impl Card {
+    pub const ORDERED_DECK: [Card; 52] = ordered_deck();
+
+    pub fn is_same_color(&self, other: &Card) -> bool {
+        self.suit.color() == other.suit.color()
+    }
+
+    pub fn is_one_below(&self, other: &Card) -> bool {
+        let delta = other.rank as i32 - self.rank as i32;
+        delta == 1
+    }
+
+    pub fn is_one_below_wrapping(&self, other: &Card, wrap_ranks: bool) -> bool {
+        self.is_one_below(other)
+            || (wrap_ranks && self.rank == Rank::King && other.rank == Rank::Ace)
+    }
+
+    // "7D" is a face up seven of diamonds, "-7D" the same card face down.
+    pub fn from_notation(text: &str) -> Result<Card, KlondikeError> {
+        let (face_up, text) = match text.strip_prefix('-') {
+            Some(rest) => (false, rest),
+            None => (true, text),
+        };
+        let mut chars = text.chars();
+        let rank = chars
+            .next()
+            .and_then(|label| {
+                RANKS.iter().copied().find(|rank| {
+                    let rank_label: &str = (*rank).into();
+                    rank_label.starts_with(label)
+                })
+            })
+            .ok_or(KlondikeError::InvalidNotation)?;
+        let suit = match chars.next() {
+            Some('D') => Suit::Diamond,
+            Some('C') => Suit::Club,
+            Some('H') => Suit::Heart,
+            Some('S') => Suit::Spade,
+            _ => return Err(KlondikeError::InvalidNotation),
+        };
+        if chars.next().is_some() {
+            return Err(KlondikeError::InvalidNotation);
+        }
+        Ok(Card {
+            suit,
+            rank,
+            face_up,
+        })
+    }
+
+    pub fn to_notation(&self) -> String {
+        let mut text = String::from(if self.face_up { "" } else { "-" });
+        text.push_str(self.rank.into());
+        text.push(match self.suit {
+            Suit::Diamond => 'D',
+            Suit::Club => 'C',
+            Suit::Heart => 'H',
+            Suit::Spade => 'S',
+        });
+        text
+    }
+}
+
+impl fmt::Debug for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let face_up = if self.face_up { "" } else { "-" };
+        let rank: &str = self.rank.into();
+        f.write_fmt(format_args!("{}{}{:?}", face_up, rank, self.suit))
+    }
+}
+
+pub(super) const NO_CARD: Card = Card {
+    suit: Suit::Spade,
+    rank: Rank::Ace,
+    face_up: false,
+};
+
+pub(super) const RANKS: [Rank; 13] = [
+    Rank::Ace,
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+];
+
+pub(super) fn card_code(card: &Card) -> u8 {
+    (card.suit as u8 - 1) << 4 | card.rank as u8
+}
+
+pub(super) fn card_from_code(code: u8, face_up: bool) -> Card {
+    let suit = match code >> 4 {
+        0 => Suit::Club,
+        1 => Suit::Diamond,
+        2 => Suit::Heart,
+        _ => Suit::Spade,
+    };
+    Card {
+        suit,
+        rank: RANKS[(code & 0xf) as usize - 1],
+        face_up,
+    }
+}
+
+pub fn make_deck(seed: u64) -> Vec<Card> {
+    make_decks(seed, 1)
+}
+
+pub fn make_decks(seed: u64, deck_count: usize) -> Vec<Card> {
+    let mut rng = rand_pcg::Pcg32::seed_from_u64(seed);
+
+    let mut cards: Vec<Card> = (0..deck_count)
+        .flat_map(|_| Card::ORDERED_DECK.iter().cloned())
+        .collect();
+    cards.shuffle(&mut rng);
+    cards
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deck_is_shuffled_ordered_deck() {
+        assert_eq!(
+            Card {
+                suit: Suit::Diamond,
+                rank: Rank::Ace,
+                face_up: false,
+            },
+            Card::ORDERED_DECK[0]
+        );
+        assert_eq!(Rank::King, Card::ORDERED_DECK[51].rank);
+        let mut deck = make_deck(1);
+        assert_ne!(&Card::ORDERED_DECK[..], &deck[..]);
+        deck.sort();
+        let mut ordered = Card::ORDERED_DECK.to_vec();
+        ordered.sort();
+        assert_eq!(ordered, deck);
+    }
+
+    #[test]
+    fn test_card_notation() {
+        assert_eq!(
+            Ok(Card {
+                suit: Suit::Diamond,
+                rank: Rank::Seven,
+                face_up: true,
+            }),
+            Card::from_notation("7D")
+        );
+        let ten = Card::from_notation("-TS").expect("ten");
+        assert_eq!(
+            (Rank::Ten, Suit::Spade, false),
+            (ten.rank, ten.suit, ten.face_up)
+        );
+        assert_eq!("-TS", ten.to_notation());
+        assert_eq!(
+            Err(KlondikeError::InvalidNotation),
+            Card::from_notation("1S")
+        );
+        assert_eq!(
+            Err(KlondikeError::InvalidNotation),
+            Card::from_notation("KX")
+        );
+        assert_eq!(
+            Err(KlondikeError::InvalidNotation),
+            Card::from_notation("KSS")
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use super::{AppliedMove, KlondikeError, Play, Table};
+
+// A table mutation as a value. Applying one keeps the move's delta so it can
+// be taken back.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableCommand {
+    play: Play,
+}
+
+impl TableCommand {
+    pub fn new(play: Play) -> Self {
+        Self { play }
+    }
+
+    pub fn play(&self) -> Play {
+        self.play
+    }
+
+    pub fn apply(self, table: &mut Table) -> Result<AppliedCommand, KlondikeError> {
+        Ok(AppliedCommand {
+            command: self,
+            applied: table.apply_move(self.play)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AppliedCommand {
+    command: TableCommand,
+    applied: AppliedMove,
+}
+
+impl AppliedCommand {
+    pub fn command(&self) -> TableCommand {
+        self.command
+    }
+
+    // Whether the move flipped the card it uncovered.
+    pub fn revealed(&self) -> bool {
+        self.applied.revealed()
+    }
+
+    pub fn revert(self, table: &mut Table) -> TableCommand {
+        table.unapply(self.applied);
+        self.command
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply_and_revert() {
+        let mut table = Table::new(322);
+        let start = table.clone();
+        let play = *table.legal_moves().first().expect("play");
+        let applied = TableCommand::new(play).apply(&mut table).expect("apply");
+        assert_ne!(start, table);
+        let moved = table.clone();
+        let dealt = TableCommand::new(Play::DrawFromStock)
+            .apply(&mut table)
+            .expect("deal");
+        assert!(!dealt.revealed());
+
+        assert_eq!(Play::DrawFromStock, dealt.revert(&mut table).play());
+        assert_eq!(moved, table);
+        let command = applied.revert(&mut table);
+        assert_eq!(start, table);
+        command.apply(&mut table).expect("redo");
+        assert_ne!(start, table);
+    }
+}
@@ -0,0 +1,32 @@
+extern crate alloc;
+
+mod card;
+mod command;
+mod daily;
+mod moves;
+mod nav;
+mod piles;
+mod position;
+#[cfg(all(test, feature = "proptests"))]
+mod proptests;
+mod share;
+mod stack;
+mod table;
+mod view;
+
+pub use self::{
+    card::{Card, Color, Rank, Suit},
+    command::{AppliedCommand, TableCommand},
+    daily::{daily_hash, daily_klondike_seed, WINABLE_SEEDS},
+    moves::{CardPlayIterator, MovesIter, Play},
+    nav::ActiveCardIterator,
+    piles::{Foundation, Tableau},
+    position::{CardIndex, CardLocation, FoundationIndex, TableauIndex},
+    share::{replay_play, ShareCode},
+    stack::{Stack, StackId, StackType, FOUNDATIONS, MAX_CARDS, RESERVES, TABLEAUX, WASTE_VISIBLE},
+    table::{
+        AppliedMove, BuildRule, HandOrigin, KlondikeError, PackedTable, RulesConfig, Table,
+        Variant, VariantId,
+    },
+    view::TableView,
+};
@@ -0,0 +1,228 @@
+use super::*;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+pub enum Play {
+    Setup,
+    DrawFromStock,
+    RecycleWaste,
+    MoveCards(CardLocation, StackId),
+}
+
+#[derive(Debug)]
+pub struct CardPlayIterator<'a> {
+    table: &'a Table,
+    card: &'a Card,
+    source: CardLocation,
+    play: Option<Play>,
+}
+
+impl<'a> CardPlayIterator<'a> {
+    pub fn new(table: &'a Table, card: &'a Card, source: CardLocation) -> Self {
+        let play = Self::next_legal_play(table, card, source, StackId::Waste);
+        Self {
+            table,
+            card,
+            source,
+            play,
+        }
+    }
+
+    pub fn next_legal_play(
+        table: &'a Table,
+        card: &'a Card,
+        source: CardLocation,
+        start: StackId,
+    ) -> Option<Play> {
+        let mut target = Some(start);
+        loop {
+            if let Some(current_target) = target {
+                let stack = table.get_stack(current_target);
+                let source_stack = table.get_stack(source.stack);
+                let moving_cards_count = source_stack.len() - source.index.get();
+                assert!(moving_cards_count > 0);
+                if table.has_stack(current_target)
+                    && stack.can_play_card(card, moving_cards_count, &table.rules)
+                {
+                    return Some(Play::MoveCards(source, current_target));
+                }
+                target = current_target.next_no_wrap();
+            } else {
+                break;
+            }
+        }
+        None
+    }
+}
+
+impl<'a> Iterator for CardPlayIterator<'a> {
+    type Item = Play;
+
+    fn next(&mut self) -> Option<Play> {
+        let next_play = self.play;
+        if let Some(play) = next_play {
+            loop {
+                match play {
+                    Play::DrawFromStock | Play::RecycleWaste => {
+                        return None;
+                    }
+                    _ => {
+                        if let Some(legal) = next_play {
+                            match legal {
+                                Play::MoveCards(_, target) => {
+                                    let next_target = target.next_no_wrap();
+                                    if let Some(next_target) = next_target {
+                                        self.play = Self::next_legal_play(
+                                            self.table,
+                                            self.card,
+                                            self.source,
+                                            next_target,
+                                        );
+                                    } else {
+                                        self.play = None;
+                                    }
+                                }
+                                _ => {
+                                    self.play = None;
+                                }
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        next_play
+    }
+}
+
+// Every legal move, found one source card at a time so nothing is collected.
+pub struct MovesIter<'a> {
+    table: &'a Table,
+    sources: ActiveCardIterator<'a>,
+    plays: Option<(CardLocation, CardPlayIterator<'a>)>,
+}
+
+impl<'a> MovesIter<'a> {
+    fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            sources: ActiveCardIterator::new(table),
+            plays: None,
+        }
+    }
+
+    fn is_legal(table: &Table, source: CardLocation, play: &Play) -> bool {
+        match play {
+            Play::MoveCards(_, target) => {
+                *target != source.stack
+                    && table.has_stack(*target)
+                    && match table.get_stack(*target).stack_type {
+                        StackType::Foundation => table.variant.variant().foundation_accepts_drops(),
+                        StackType::Tableau | StackType::Reserve => true,
+                        StackType::Stock | StackType::Waste | StackType::Hand => false,
+                    }
+            }
+            Play::Setup | Play::DrawFromStock | Play::RecycleWaste => false,
+        }
+    }
+
+    fn next_source(&mut self) -> Option<(CardLocation, CardPlayIterator<'a>)> {
+        let table = self.table;
+        self.sources.find_map(|source| {
+            if table.check_pick_up(source.stack, source.index).is_err() {
+                return None;
+            }
+            table
+                .get_stack(source.stack)
+                .get_card(source.index)
+                .map(|card| (source, CardPlayIterator::new(table, card, source)))
+        })
+    }
+}
+
+impl<'a> Iterator for MovesIter<'a> {
+    type Item = Play;
+
+    fn next(&mut self) -> Option<Play> {
+        loop {
+            if let Some((source, plays)) = &mut self.plays {
+                let (table, source) = (self.table, *source);
+                if let Some(play) = plays.find(|play| Self::is_legal(table, source, play)) {
+                    return Some(play);
+                }
+            }
+            self.plays = Some(self.next_source()?);
+        }
+    }
+}
+
+impl Table {
+    fn hint_priority(&self, source: CardLocation, target: StackId) -> Option<usize> {
+        if target == source.stack || !self.has_stack(target) {
+            return None;
+        }
+        let source_stack = self.get_stack(source.stack);
+        match (source_stack.stack_type, self.get_stack(target).stack_type) {
+            (StackType::Foundation, _) => None,
+            (_, StackType::Foundation) => {
+                if self.variant.variant().foundation_accepts_drops() {
+                    Some(3)
+                } else {
+                    None
+                }
+            }
+            (StackType::Tableau, StackType::Tableau) => {
+                let below = source
+                    .index
+                    .previous()
+                    .and_then(|index| source_stack.get_card(index));
+                if below.map(|card| !card.face_up).unwrap_or(false) {
+                    Some(2)
+                } else {
+                    None
+                }
+            }
+            (_, StackType::Tableau) => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn moves_iter(&self) -> MovesIter<'_> {
+        MovesIter::new(self)
+    }
+
+    pub fn legal_moves(&self) -> Vec<Play> {
+        self.moves_iter().collect()
+    }
+
+    // The legal moves plus the stock play, which moves_iter leaves out.
+    pub fn plays_with_stock(&self) -> Vec<Play> {
+        let mut plays = self.legal_moves();
+        if self.has_cards_in_stock() {
+            plays.push(Play::DrawFromStock);
+        } else if self.has_cards_in_waste() && self.can_recycle() {
+            plays.push(Play::RecycleWaste);
+        }
+        plays
+    }
+
+    pub fn hint(&self) -> Option<Play> {
+        let mut best: Option<(usize, Play)> = None;
+        for play in self.moves_iter() {
+            if let Play::MoveCards(source, target) = play {
+                if let Some(priority) = self.hint_priority(source, target) {
+                    if best.map(|(best, _)| priority > best).unwrap_or(true) {
+                        best = Some((priority, play));
+                    }
+                }
+            }
+        }
+        match best {
+            Some((_, play)) => Some(play),
+            None if !self.stock_is_dead() => Some(Play::DrawFromStock),
+            None => None,
+        }
+    }
+}
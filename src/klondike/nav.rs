@@ -0,0 +1,109 @@
+use super::*;
+use enum_iterator::IntoEnumIterator;
+
+impl Table {
+    pub fn next_active_card(&self, from: CardLocation) -> Option<CardLocation> {
+        let start_stack = from.stack;
+        let mut source = from;
+        let mut start = Some(source.index);
+        let mut wrapped = false;
+        loop {
+            let source_stack = self.get_stack(source.stack);
+            if let Some(index) = source_stack.next_active_card(start) {
+                return Some(CardLocation {
+                    stack: source.stack,
+                    index,
+                });
+            } else if wrapped {
+                return None;
+            } else {
+                source.stack = source.stack.next();
+                start = None;
+                wrapped = source.stack == start_stack;
+            }
+        }
+    }
+
+    pub fn previous_active_card(&self, from: CardLocation) -> Option<CardLocation> {
+        let start_stack = from.stack;
+        let mut source = from;
+        let mut start = Some(source.index);
+        let mut wrapped = false;
+        loop {
+            let source_stack = self.get_stack(source.stack);
+            if let Some(index) = source_stack.previous_active_card(start) {
+                return Some(CardLocation {
+                    stack: source.stack,
+                    index,
+                });
+            } else if wrapped {
+                return None;
+            } else {
+                source.stack = source.stack.previous();
+                start = None;
+                wrapped = source.stack == start_stack;
+            }
+        }
+    }
+}
+
+pub struct ActiveCardIterator<'a> {
+    table: &'a Table,
+    source: Option<CardLocation>,
+}
+
+impl<'a> ActiveCardIterator<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        let stacks = StackId::into_enum_iter();
+        let source = stacks
+            .filter_map(|stack_id| {
+                let stack = table.get_stack(stack_id);
+                let active_index = table.next_pick_up_index(stack_id, None);
+                if active_index.is_some()
+                    && table.is_pick_up_source(stack_id)
+                    && (stack_id == StackId::Waste
+                        || stack.is_top_face_up_card(active_index.unwrap()))
+                {
+                    Some(CardLocation {
+                        stack: stack_id,
+                        index: active_index.unwrap(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .nth(0);
+
+        Self { table, source }
+    }
+}
+
+impl<'a> Iterator for ActiveCardIterator<'a> {
+    type Item = CardLocation;
+
+    fn next(&mut self) -> Option<CardLocation> {
+        let next = self.source;
+        if let Some(mut source) = next {
+            let mut start = Some(source.index);
+            loop {
+                let next_index = self.table.next_pick_up_index(source.stack, start);
+                if next_index.is_some() && self.table.is_pick_up_source(source.stack) {
+                    let source = CardLocation {
+                        stack: source.stack,
+                        index: next_index.unwrap(),
+                    };
+                    self.source = Some(source);
+                    break;
+                } else {
+                    source.stack = source.stack.next();
+                    if source.stack == StackId::Stock {
+                        self.source = None;
+                        break;
+                    }
+                    start = None;
+                }
+            }
+        }
+        next
+    }
+}
@@ -0,0 +1,168 @@
+use super::{BuildRule, Card, Rank, RulesConfig, Stack, StackId, StackType, Suit, Table};
+
+// A foundation stack along with the rules it's built under.
+#[derive(Clone, Copy, Debug)]
+pub struct Foundation<'a> {
+    stack: Stack<'a>,
+    rules: &'a RulesConfig,
+}
+
+impl<'a> Foundation<'a> {
+    pub fn new(stack: Stack<'a>, rules: &'a RulesConfig) -> Option<Self> {
+        if stack.stack_type == StackType::Foundation {
+            Some(Self { stack, rules })
+        } else {
+            None
+        }
+    }
+
+    pub fn stack(&self) -> Stack<'a> {
+        self.stack
+    }
+
+    // Set by the first card played.
+    pub fn suit(&self) -> Option<Suit> {
+        self.stack.bottom_card().map(|card| card.suit)
+    }
+
+    // Each slot takes one suit; the second deck's slots repeat the first's.
+    fn slot_suit(&self) -> Option<Suit> {
+        match self.stack.stack_id {
+            StackId::Foundation1 | StackId::Foundation5 => Some(Suit::Spade),
+            StackId::Foundation2 | StackId::Foundation6 => Some(Suit::Club),
+            StackId::Foundation3 | StackId::Foundation7 => Some(Suit::Heart),
+            StackId::Foundation4 | StackId::Foundation8 => Some(Suit::Diamond),
+            _ => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.stack.len() >= 13
+    }
+
+    pub fn next_needed_rank(&self) -> Option<Rank> {
+        if self.is_complete() {
+            return None;
+        }
+        match self.stack.top_card() {
+            Some(card) if card.rank == Rank::King && !self.rules.wrap_ranks => None,
+            Some(card) => Some(card.rank.next_wrapping()),
+            None => Some(self.rules.foundation_base),
+        }
+    }
+
+    pub fn can_accept_card(&self, card: &Card) -> bool {
+        self.suit().or_else(|| self.slot_suit()) == Some(card.suit)
+            && self.next_needed_rank() == Some(card.rank)
+    }
+}
+
+// A tableau stack along with the rules it's built under.
+#[derive(Clone, Copy, Debug)]
+pub struct Tableau<'a> {
+    stack: Stack<'a>,
+    rules: &'a RulesConfig,
+}
+
+impl<'a> Tableau<'a> {
+    pub fn new(stack: Stack<'a>, rules: &'a RulesConfig) -> Option<Self> {
+        if stack.stack_type == StackType::Tableau {
+            Some(Self { stack, rules })
+        } else {
+            None
+        }
+    }
+
+    pub fn stack(&self) -> Stack<'a> {
+        self.stack
+    }
+
+    pub fn face_down_count(&self) -> usize {
+        self.stack
+            .cards()
+            .iter()
+            .take_while(|card| !card.face_up)
+            .count()
+    }
+
+    // The face up cards on top that are built on each other, which is the
+    // most that could move as a group.
+    pub fn face_up_run(&self) -> &'a [Card] {
+        let cards = self.stack.cards();
+        let mut start = cards.len();
+        while start > 0
+            && cards[start - 1].face_up
+            && (start == cards.len() || self.builds_on(&cards[start - 1], &cards[start]))
+        {
+            start -= 1;
+        }
+        &cards[start..]
+    }
+
+    fn builds_on(&self, below: &Card, card: &Card) -> bool {
+        let follows_suit = match self.rules.build {
+            BuildRule::AlternateColors => !below.is_same_color(card),
+            BuildRule::SameSuit => below.suit == card.suit,
+            BuildRule::AnySuit => true,
+        };
+        follows_suit && card.is_one_below_wrapping(below, self.rules.wrap_ranks)
+    }
+
+    pub fn can_accept_card(&self, card: &Card) -> bool {
+        match self.stack.top_card() {
+            Some(top_card) => self.builds_on(top_card, card),
+            None => {
+                self.rules.any_card_on_empty_tableau
+                    || card.rank == self.rules.foundation_base.previous_wrapping()
+            }
+        }
+    }
+}
+
+impl Table {
+    pub fn foundations(&self) -> impl Iterator<Item = Foundation<'_>> {
+        self.foundation_ids()
+            .iter()
+            .filter_map(move |stack_id| Foundation::new(self.get_stack(*stack_id), &self.rules))
+    }
+
+    pub fn tableaux(&self) -> impl Iterator<Item = Tableau<'_>> {
+        self.tableau_ids()
+            .iter()
+            .filter_map(move |stack_id| Tableau::new(self.get_stack(*stack_id), &self.rules))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn card(text: &str) -> Card {
+        Card::from_notation(text).expect("card")
+    }
+
+    #[test]
+    fn test_foundation_and_tableau_rules() {
+        let rules = RulesConfig::default();
+        let spades = [card("AS"), card("2S")];
+        let foundation =
+            Foundation::new(Stack::new(StackId::Foundation1, &spades), &rules).expect("foundation");
+        assert_eq!(Some(Suit::Spade), foundation.suit());
+        assert_eq!(Some(Rank::Three), foundation.next_needed_rank());
+        assert!(foundation.can_accept_card(&card("3S")));
+        assert!(!foundation.can_accept_card(&card("3C")));
+        let empty = Foundation::new(Stack::new(StackId::Foundation3, &[]), &rules).expect("empty");
+        assert_eq!(None, empty.suit());
+        assert_eq!(Some(Rank::Ace), empty.next_needed_rank());
+        assert!(!empty.can_accept_card(&card("AS")));
+        assert!(Foundation::new(Stack::new(StackId::Tableau1, &[]), &rules).is_none());
+
+        let column = [card("-9C"), card("8H"), card("7S")];
+        let tableau =
+            Tableau::new(Stack::new(StackId::Tableau1, &column), &rules).expect("tableau");
+        assert_eq!(1, tableau.face_down_count());
+        assert_eq!(&column[1..], tableau.face_up_run());
+        assert!(tableau.can_accept_card(&card("6D")));
+        assert!(!tableau.can_accept_card(&card("6S")));
+    }
+}
@@ -0,0 +1,139 @@
+use super::{StackId, FOUNDATIONS, TABLEAUX};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// Where a card sits within its stack, counting up from the bottom card. Only
+// meaningful alongside the stack it was taken from; see CardLocation.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+#[serde(transparent)]
+pub struct CardIndex(usize);
+
+impl CardIndex {
+    pub const BOTTOM: CardIndex = CardIndex(0);
+
+    pub fn new(index: usize) -> Self {
+        Self(index)
+    }
+
+    pub fn get(self) -> usize {
+        self.0
+    }
+
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    pub fn previous(self) -> Option<Self> {
+        self.0.checked_sub(1).map(Self)
+    }
+}
+
+impl fmt::Display for CardIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// The position of a tableau from the left, which is not the same thing as an
+// index into the stack it names.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TableauIndex(usize);
+
+impl TableauIndex {
+    pub fn all(count: usize) -> impl Iterator<Item = TableauIndex> {
+        (0..count.min(TABLEAUX.len())).map(TableauIndex)
+    }
+
+    pub fn get(self) -> usize {
+        self.0
+    }
+
+    pub fn stack_id(self) -> StackId {
+        TABLEAUX[self.0]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FoundationIndex(usize);
+
+impl FoundationIndex {
+    pub fn all(count: usize) -> impl Iterator<Item = FoundationIndex> {
+        (0..count.min(FOUNDATIONS.len())).map(FoundationIndex)
+    }
+
+    pub fn get(self) -> usize {
+        self.0
+    }
+
+    pub fn stack_id(self) -> StackId {
+        FOUNDATIONS[self.0]
+    }
+}
+
+impl StackId {
+    pub fn tableau_index(self) -> Option<TableauIndex> {
+        TABLEAUX
+            .iter()
+            .position(|stack_id| *stack_id == self)
+            .map(TableauIndex)
+    }
+
+    pub fn foundation_index(self) -> Option<FoundationIndex> {
+        FOUNDATIONS
+            .iter()
+            .position(|stack_id| *stack_id == self)
+            .map(FoundationIndex)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Eq, Ord, PartialEq, PartialOrd, Hash, Serialize)]
+pub struct CardLocation {
+    pub stack: StackId,
+    pub index: CardIndex,
+}
+
+impl CardLocation {
+    pub fn new(stack: StackId, index: CardIndex) -> Self {
+        Self { stack, index }
+    }
+
+    pub fn stock() -> Self {
+        CardLocation {
+            stack: StackId::Stock,
+            index: CardIndex::BOTTOM,
+        }
+    }
+}
+
+impl fmt::Debug for CardLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_fmt(format_args!("{:?}[{}]", self.stack, self.index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stack_positions() {
+        assert_eq!(
+            Some(StackId::Tableau3),
+            StackId::Tableau3
+                .tableau_index()
+                .map(TableauIndex::stack_id)
+        );
+        assert_eq!(None, StackId::Waste.tableau_index());
+        assert_eq!(None, StackId::Tableau1.foundation_index());
+        assert!(FoundationIndex::all(2).map(FoundationIndex::stack_id).eq([
+            StackId::Foundation1,
+            StackId::Foundation2
+        ]
+        .iter()
+        .copied()));
+        assert_eq!(None, CardIndex::BOTTOM.previous());
+        assert_eq!(Some(CardIndex::new(2)), CardIndex::new(3).previous());
+    }
+}
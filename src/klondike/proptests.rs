@@ -0,0 +1,57 @@
+use super::*;
+use alloc::vec::Vec;
+use enum_iterator::IntoEnumIterator;
+use proptest::prelude::*;
+
+fn all_cards(table: &Table) -> Vec<Card> {
+    let mut cards: Vec<Card> = StackId::into_enum_iter()
+        .flat_map(|stack_id| table.get_stack(stack_id).cards().to_vec())
+        .map(|card| Card {
+            face_up: false,
+            ..card
+        })
+        .collect();
+    cards.sort();
+    cards
+}
+
+proptest! {
+    #[test]
+    fn test_random_play_keeps_invariants(
+        seed in any::<u64>(),
+        choices in proptest::collection::vec(any::<usize>(), 0..200)
+    ) {
+        let rules = RulesConfig {
+            allow_foundation_pick_up: false,
+            ..RulesConfig::default()
+        };
+        let mut table = Table::with_rules(seed, rules);
+        let mut deck = Card::ORDERED_DECK.to_vec();
+        deck.sort();
+        for choice in choices {
+            let plays = table.plays_with_stock();
+            if plays.is_empty() {
+                break;
+            }
+            let play = plays[choice % plays.len()];
+            let snapshot = table.clone();
+            let foundation_count = table.foundation_count();
+
+            replay_play(&mut table, play).expect("replay_play");
+            let mut undone = snapshot.clone();
+            let applied = undone.apply_move(play).expect("apply_move");
+            prop_assert_eq!(&table, &undone);
+            undone.unapply(applied);
+            prop_assert_eq!(&snapshot, &undone);
+            prop_assert_eq!(snapshot.position_hash(), undone.position_hash());
+
+            prop_assert_eq!(&deck, &all_cards(&table));
+            prop_assert!(table.foundation_count() >= foundation_count);
+            for stack_id in TABLEAUX {
+                let cards = table.get_stack(*stack_id).cards();
+                let face_down = cards.iter().take_while(|card| !card.face_up).count();
+                prop_assert!(cards[face_down..].iter().all(|card| card.face_up));
+            }
+        }
+    }
+}
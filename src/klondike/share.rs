@@ -0,0 +1,309 @@
+use super::*;
+use alloc::{string::String, vec::Vec};
+use enum_iterator::IntoEnumIterator;
+
+const FORMAT_VERSION: u32 = 1;
+const SPECIAL_STACK: u32 = 31;
+const GROUP_LEN: usize = 5;
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// A share code is the deal (variant, rules and seed) plus every play since,
+// bit packed, followed by a checksum byte and armored as Crockford base32.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShareCode {
+    pub seed: u64,
+    pub variant: VariantId,
+    pub rules: RulesConfig,
+    pub plays: Vec<Play>,
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, width: usize) {
+        for shift in (0..width).rev() {
+            let offset = self.bits % 8;
+            if offset == 0 {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> shift) & 1) as u8;
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= bit << (7 - offset);
+            self.bits += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, width: usize) -> Result<u64, KlondikeError> {
+        let mut value = 0;
+        for _ in 0..width {
+            let byte = self
+                .bytes
+                .get(self.bits / 8)
+                .ok_or(KlondikeError::InvalidShareCode)?;
+            value = (value << 1) | u64::from((byte >> (7 - self.bits % 8)) & 1);
+            self.bits += 1;
+        }
+        Ok(value)
+    }
+}
+
+fn position<T: IntoEnumIterator + PartialEq>(value: T) -> u64 {
+    T::into_enum_iter()
+        .position(|candidate| candidate == value)
+        .unwrap_or(0) as u64
+}
+
+fn nth<T: IntoEnumIterator>(index: u64) -> Result<T, KlondikeError> {
+    T::into_enum_iter()
+        .nth(index as usize)
+        .ok_or(KlondikeError::InvalidShareCode)
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.rotate_left(1) ^ byte)
+}
+
+fn to_base32(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            code.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        code.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    code
+}
+
+fn from_base32(code: &str) -> Result<Vec<u8>, KlondikeError> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in code.chars() {
+        let c = match c.to_ascii_uppercase() {
+            '-' | ' ' => continue,
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+        let value = ALPHABET
+            .iter()
+            .position(|letter| *letter as char == c)
+            .ok_or(KlondikeError::InvalidShareCode)?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+impl ShareCode {
+    pub fn new(table: &Table, seed: u64, plays: &[Play]) -> Self {
+        Self {
+            seed,
+            variant: table.variant,
+            rules: table.rules,
+            plays: plays
+                .iter()
+                .copied()
+                .filter(|play| *play != Play::Setup)
+                .collect(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut writer = BitWriter::default();
+        writer.write(u64::from(FORMAT_VERSION), 4);
+        writer.write(position(self.variant), 4);
+        for flag in &[
+            self.rules.allow_foundation_pick_up,
+            self.rules.any_card_on_empty_tableau,
+            self.rules.strict_waste_top,
+            self.rules.wrap_ranks,
+            self.rules.single_card_moves,
+        ] {
+            writer.write(*flag as u64, 1);
+        }
+        writer.write(
+            self.rules
+                .max_recycles
+                .map(|max| max.min(254))
+                .unwrap_or(255) as u64,
+            8,
+        );
+        writer.write(position(self.rules.foundation_base), 4);
+        let build = match self.rules.build {
+            BuildRule::AlternateColors => 0,
+            BuildRule::SameSuit => 1,
+            BuildRule::AnySuit => 2,
+        };
+        writer.write(build, 2);
+        writer.write(self.seed, 64);
+        writer.write(self.plays.len() as u64, 16);
+        for play in &self.plays {
+            match play {
+                Play::Setup => (),
+                Play::DrawFromStock => writer.write(u64::from(SPECIAL_STACK) << 1, 6),
+                Play::RecycleWaste => writer.write(u64::from(SPECIAL_STACK) << 1 | 1, 6),
+                Play::MoveCards(source, target) => {
+                    writer.write(position(source.stack), 5);
+                    writer.write(source.index.get() as u64, 7);
+                    writer.write(position(*target), 5);
+                }
+            }
+        }
+        let mut bytes = writer.bytes;
+        bytes.push(checksum(&bytes));
+        let armored = to_base32(&bytes);
+        let mut code = String::new();
+        for (index, c) in armored.chars().enumerate() {
+            if index > 0 && index % GROUP_LEN == 0 {
+                code.push('-');
+            }
+            code.push(c);
+        }
+        code
+    }
+
+    pub fn decode(code: &str) -> Result<Self, KlondikeError> {
+        let mut bytes = from_base32(code)?;
+        let expected = bytes.pop().ok_or(KlondikeError::InvalidShareCode)?;
+        if checksum(&bytes) != expected {
+            return Err(KlondikeError::InvalidShareCode);
+        }
+        let mut reader = BitReader {
+            bytes: &bytes,
+            bits: 0,
+        };
+        if reader.read(4)? != u64::from(FORMAT_VERSION) {
+            return Err(KlondikeError::InvalidShareCode);
+        }
+        let variant = nth(reader.read(4)?)?;
+        let mut rules = RulesConfig {
+            allow_foundation_pick_up: reader.read(1)? == 1,
+            any_card_on_empty_tableau: reader.read(1)? == 1,
+            strict_waste_top: reader.read(1)? == 1,
+            wrap_ranks: reader.read(1)? == 1,
+            single_card_moves: reader.read(1)? == 1,
+            ..RulesConfig::default()
+        };
+        rules.max_recycles = match reader.read(8)? {
+            255 => None,
+            max => Some(max as usize),
+        };
+        rules.foundation_base = nth(reader.read(4)?)?;
+        rules.build = match reader.read(2)? {
+            0 => BuildRule::AlternateColors,
+            1 => BuildRule::SameSuit,
+            2 => BuildRule::AnySuit,
+            _ => return Err(KlondikeError::InvalidShareCode),
+        };
+        let seed = reader.read(64)?;
+        let count = reader.read(16)?;
+        let mut plays = Vec::new();
+        for _ in 0..count {
+            let stack = reader.read(5)?;
+            if stack == u64::from(SPECIAL_STACK) {
+                plays.push(match reader.read(1)? {
+                    0 => Play::DrawFromStock,
+                    _ => Play::RecycleWaste,
+                });
+            } else {
+                let index = CardIndex::new(reader.read(7)? as usize);
+                let source = CardLocation::new(nth(stack)?, index);
+                plays.push(Play::MoveCards(source, nth(reader.read(5)?)?));
+            }
+        }
+        Ok(Self {
+            seed,
+            variant,
+            rules,
+            plays,
+        })
+    }
+
+    pub fn replay(&self) -> Result<Table, KlondikeError> {
+        let mut table = self.deal();
+        for play in &self.plays {
+            replay_play(&mut table, *play)?;
+        }
+        Ok(table)
+    }
+
+    // The table as it was before the first play.
+    pub fn deal(&self) -> Table {
+        Table::with_variant(self.seed, self.variant, self.rules)
+    }
+}
+
+pub fn replay_play(table: &mut Table, play: Play) -> Result<(), KlondikeError> {
+    match play {
+        Play::Setup => (),
+        Play::DrawFromStock | Play::RecycleWaste => table.deal_from_stock()?,
+        Play::MoveCards(source, target) => {
+            table.take_selected_cards_from_stack(source.stack, source.index)?;
+            table.put_hand_on_stack(target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_share_code_round_trip() {
+        let mut table = Table::new(322);
+        let mut plays = Vec::new();
+        for _ in 0..3 {
+            table.deal_from_stock().expect("deal");
+            plays.push(Play::DrawFromStock);
+            if let Some(Play::MoveCards(source, target)) = table.legal_moves().first().copied() {
+                table
+                    .take_selected_cards_from_stack(source.stack, source.index)
+                    .expect("take");
+                table.put_hand_on_stack(target).expect("put");
+                plays.push(Play::MoveCards(source, target));
+            }
+        }
+        let share = ShareCode::new(&table, 322, &plays);
+        let code = share.encode();
+        assert!(code
+            .chars()
+            .all(|c| c == '-' || ALPHABET.contains(&(c as u8))));
+        let decoded = ShareCode::decode(&code.to_ascii_lowercase()).expect("decode");
+        assert_eq!(share, decoded);
+        assert_eq!(table, decoded.replay().expect("replay"));
+
+        let mut typo: Vec<char> = code.chars().collect();
+        typo[2] = if typo[2] == 'A' { 'B' } else { 'A' };
+        let typo: String = typo.into_iter().collect();
+        assert_eq!(
+            Err(KlondikeError::InvalidShareCode),
+            ShareCode::decode(&typo)
+        );
+    }
+}
@@ -0,0 +1,606 @@
+use super::{
+    card::{card_code, NO_CARD},
+    *,
+};
+use alloc::fmt;
+use core::{
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+use enum_iterator::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    IntoEnumIterator,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub enum StackId {
+    Stock,
+    Waste,
+    Reserve1,
+    Reserve2,
+    Reserve3,
+    Reserve4,
+    Reserve5,
+    Reserve6,
+    Reserve7,
+    Foundation1,
+    Foundation2,
+    Foundation3,
+    Foundation4,
+    Foundation5,
+    Foundation6,
+    Foundation7,
+    Foundation8,
+    Tableau1,
+    Tableau2,
+    Tableau3,
+    Tableau4,
+    Tableau5,
+    Tableau6,
+    Tableau7,
+    Tableau8,
+    Tableau9,
+    Tableau10,
+    Hand,
+}
+
+impl StackId {
+    fn next_impl(&self, wrap: bool) -> Option<Self> {
+        match self {
+            StackId::Stock => Some(StackId::Waste),
+            StackId::Waste => Some(StackId::Reserve1),
+            StackId::Reserve1 => Some(StackId::Reserve2),
+            StackId::Reserve2 => Some(StackId::Reserve3),
+            StackId::Reserve3 => Some(StackId::Reserve4),
+            StackId::Reserve4 => Some(StackId::Reserve5),
+            StackId::Reserve5 => Some(StackId::Reserve6),
+            StackId::Reserve6 => Some(StackId::Reserve7),
+            StackId::Reserve7 => Some(StackId::Foundation1),
+            StackId::Foundation1 => Some(StackId::Foundation2),
+            StackId::Foundation2 => Some(StackId::Foundation3),
+            StackId::Foundation3 => Some(StackId::Foundation4),
+            StackId::Foundation4 => Some(StackId::Foundation5),
+            StackId::Foundation5 => Some(StackId::Foundation6),
+            StackId::Foundation6 => Some(StackId::Foundation7),
+            StackId::Foundation7 => Some(StackId::Foundation8),
+            StackId::Foundation8 => Some(StackId::Tableau1),
+            StackId::Tableau1 => Some(StackId::Tableau2),
+            StackId::Tableau2 => Some(StackId::Tableau3),
+            StackId::Tableau3 => Some(StackId::Tableau4),
+            StackId::Tableau4 => Some(StackId::Tableau5),
+            StackId::Tableau5 => Some(StackId::Tableau6),
+            StackId::Tableau6 => Some(StackId::Tableau7),
+            StackId::Tableau7 => Some(StackId::Tableau8),
+            StackId::Tableau8 => Some(StackId::Tableau9),
+            StackId::Tableau9 => Some(StackId::Tableau10),
+            StackId::Tableau10 => {
+                if wrap {
+                    Some(StackId::Stock)
+                } else {
+                    None
+                }
+            }
+            StackId::Hand => Some(StackId::Hand),
+        }
+    }
+
+    pub fn stack_type(self) -> StackType {
+        match self {
+            StackId::Stock => StackType::Stock,
+            StackId::Waste => StackType::Waste,
+            StackId::Reserve1
+            | StackId::Reserve2
+            | StackId::Reserve3
+            | StackId::Reserve4
+            | StackId::Reserve5
+            | StackId::Reserve6
+            | StackId::Reserve7 => StackType::Reserve,
+            StackId::Foundation1
+            | StackId::Foundation2
+            | StackId::Foundation3
+            | StackId::Foundation4
+            | StackId::Foundation5
+            | StackId::Foundation6
+            | StackId::Foundation7
+            | StackId::Foundation8 => StackType::Foundation,
+            StackId::Tableau1
+            | StackId::Tableau2
+            | StackId::Tableau3
+            | StackId::Tableau4
+            | StackId::Tableau5
+            | StackId::Tableau6
+            | StackId::Tableau7
+            | StackId::Tableau8
+            | StackId::Tableau9
+            | StackId::Tableau10 => StackType::Tableau,
+            StackId::Hand => StackType::Hand,
+        }
+    }
+
+    pub fn next_no_wrap(&self) -> Option<Self> {
+        self.next_impl(false)
+    }
+
+    pub fn next(&self) -> Self {
+        self.next_impl(true).expect("next")
+    }
+
+    pub fn previous(&self) -> Self {
+        match self {
+            StackId::Stock => StackId::Tableau10,
+            StackId::Waste => StackId::Stock,
+            StackId::Reserve1 => StackId::Waste,
+            StackId::Reserve2 => StackId::Reserve1,
+            StackId::Reserve3 => StackId::Reserve2,
+            StackId::Reserve4 => StackId::Reserve3,
+            StackId::Reserve5 => StackId::Reserve4,
+            StackId::Reserve6 => StackId::Reserve5,
+            StackId::Reserve7 => StackId::Reserve6,
+            StackId::Foundation1 => StackId::Reserve7,
+            StackId::Foundation2 => StackId::Foundation1,
+            StackId::Foundation3 => StackId::Foundation2,
+            StackId::Foundation4 => StackId::Foundation3,
+            StackId::Foundation5 => StackId::Foundation4,
+            StackId::Foundation6 => StackId::Foundation5,
+            StackId::Foundation7 => StackId::Foundation6,
+            StackId::Foundation8 => StackId::Foundation7,
+            StackId::Tableau1 => StackId::Foundation8,
+            StackId::Tableau2 => StackId::Tableau1,
+            StackId::Tableau3 => StackId::Tableau2,
+            StackId::Tableau4 => StackId::Tableau3,
+            StackId::Tableau5 => StackId::Tableau4,
+            StackId::Tableau6 => StackId::Tableau5,
+            StackId::Tableau7 => StackId::Tableau6,
+            StackId::Tableau8 => StackId::Tableau7,
+            StackId::Tableau9 => StackId::Tableau8,
+            StackId::Tableau10 => StackId::Tableau9,
+            StackId::Hand => StackId::Hand,
+        }
+    }
+}
+
+pub const FOUNDATIONS: &[StackId] = &[
+    StackId::Foundation1,
+    StackId::Foundation2,
+    StackId::Foundation3,
+    StackId::Foundation4,
+    StackId::Foundation5,
+    StackId::Foundation6,
+    StackId::Foundation7,
+    StackId::Foundation8,
+];
+
+pub const TABLEAUX: &[StackId] = &[
+    StackId::Tableau1,
+    StackId::Tableau2,
+    StackId::Tableau3,
+    StackId::Tableau4,
+    StackId::Tableau5,
+    StackId::Tableau6,
+    StackId::Tableau7,
+    StackId::Tableau8,
+    StackId::Tableau9,
+    StackId::Tableau10,
+];
+
+pub const RESERVES: &[StackId] = &[
+    StackId::Reserve1,
+    StackId::Reserve2,
+    StackId::Reserve3,
+    StackId::Reserve4,
+    StackId::Reserve5,
+    StackId::Reserve6,
+    StackId::Reserve7,
+];
+
+pub const WASTE_VISIBLE: usize = 3;
+pub const MAX_CARDS: usize = 104;
+pub(super) const STACK_COUNT: usize = 28;
+
+#[derive(Clone, Copy, Debug, Eq, IntoEnumIterator, Ord, PartialEq, PartialOrd, Hash)]
+pub enum StackType {
+    Stock,
+    Waste,
+    Reserve,
+    Foundation,
+    Tableau,
+    Hand,
+}
+
+// A Zobrist key for a card at a depth in a stack. Keys are mixed on demand
+// with splitmix64 rather than looked up, as a full table would run to hundreds
+// of thousands of entries.
+fn zobrist_key(stack_id: StackId, depth: usize, card: &Card) -> u64 {
+    let mut key = (stack_id as u64) << 16
+        | (depth as u64) << 8
+        | (card_code(card) as u64) << 1
+        | card.face_up as u64;
+    key = key.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    key ^ (key >> 31)
+}
+
+// Every stack, including the hand, is a range of one fixed array, so picking up,
+// dropping and dealing cards only rotate cards in place and never allocate.
+#[derive(Clone)]
+pub(super) struct CardArena {
+    pub(super) cards: [Card; MAX_CARDS],
+    pub(super) ends: [u8; STACK_COUNT],
+    pub(super) generations: [u32; STACK_COUNT],
+    // Kept up to date by every mutation, so only the cards that move are rehashed.
+    pub(super) hash: u64,
+}
+
+impl PartialEq for CardArena {
+    fn eq(&self, other: &Self) -> bool {
+        self.ends == other.ends && self.cards[..] == other.cards[..]
+    }
+}
+
+impl Eq for CardArena {}
+
+impl Hash for CardArena {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl CardArena {
+    pub(super) fn new(deck: &[Card]) -> Self {
+        let mut cards = [NO_CARD; MAX_CARDS];
+        cards[..deck.len()].clone_from_slice(deck);
+        let mut arena = Self {
+            cards,
+            ends: [deck.len() as u8; STACK_COUNT],
+            generations: [0; STACK_COUNT],
+            hash: 0,
+        };
+        arena.rehash();
+        arena
+    }
+
+    fn stack_hash(&self, stack_id: StackId, from: usize) -> u64 {
+        self.cards(stack_id)
+            .iter()
+            .enumerate()
+            .skip(from)
+            .fold(0, |hash, (depth, card)| {
+                hash ^ zobrist_key(stack_id, depth, card)
+            })
+    }
+
+    pub(super) fn rehash(&mut self) {
+        self.hash =
+            StackId::into_enum_iter().fold(0, |hash, stack_id| hash ^ self.stack_hash(stack_id, 0));
+    }
+
+    fn touch(&mut self, stack_id: StackId) {
+        let generation = &mut self.generations[stack_id as usize];
+        *generation = generation.wrapping_add(1);
+    }
+
+    pub(super) fn range(&self, stack_id: StackId) -> Range<usize> {
+        let index = stack_id as usize;
+        let start = if index == 0 {
+            0
+        } else {
+            self.ends[index - 1] as usize
+        };
+        start..self.ends[index] as usize
+    }
+
+    pub(super) fn cards(&self, stack_id: StackId) -> &[Card] {
+        &self.cards[self.range(stack_id)]
+    }
+
+    pub(super) fn update(&mut self, stack_id: StackId, update: impl FnOnce(&mut [Card])) {
+        self.touch(stack_id);
+        self.hash ^= self.stack_hash(stack_id, 0);
+        let range = self.range(stack_id);
+        update(&mut self.cards[range]);
+        self.hash ^= self.stack_hash(stack_id, 0);
+    }
+
+    pub(super) fn move_cards(
+        &mut self,
+        from: StackId,
+        start: usize,
+        count: usize,
+        to: StackId,
+        at: usize,
+    ) {
+        assert_ne!(from, to);
+        self.touch(from);
+        self.touch(to);
+        // Cards below the ones moved keep their depth, so their keys stand.
+        self.hash ^= self.stack_hash(from, start) ^ self.stack_hash(to, at);
+        let source = self.range(from).start + start;
+        let destination = self.range(to).start + at;
+        if from < to {
+            if source != destination {
+                self.cards[source..destination].rotate_left(count);
+            }
+            for end in &mut self.ends[from as usize..to as usize] {
+                *end -= count as u8;
+            }
+        } else {
+            if source != destination {
+                self.cards[destination..source + count].rotate_right(count);
+            }
+            for end in &mut self.ends[to as usize..from as usize] {
+                *end += count as u8;
+            }
+        }
+        self.hash ^= self.stack_hash(from, start) ^ self.stack_hash(to, at);
+    }
+
+    pub(super) fn replace(&mut self, stack_id: StackId, cards: &[Card]) {
+        let range = self.range(stack_id);
+        let total = self.ends[STACK_COUNT - 1] as usize;
+        let remaining = total - range.len();
+        assert!(remaining + cards.len() <= MAX_CARDS);
+        self.touch(stack_id);
+        self.hash ^= self.stack_hash(stack_id, 0);
+        self.cards[range.start..total].rotate_left(range.len());
+        self.cards[remaining..remaining + cards.len()].clone_from_slice(cards);
+        self.cards[range.start..remaining + cards.len()].rotate_right(cards.len());
+        for end in &mut self.ends[stack_id as usize..] {
+            *end = (*end as usize - range.len() + cards.len()) as u8;
+        }
+        for card in &mut self.cards[total - range.len() + cards.len()..] {
+            *card = NO_CARD;
+        }
+        self.hash ^= self.stack_hash(stack_id, 0);
+    }
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Stack<'a> {
+    pub stack_id: StackId,
+    pub stack_type: StackType,
+    cards: &'a [Card],
+}
+
+impl<'a> Stack<'a> {
+    pub fn new(stack_id: StackId, cards: &'a [Card]) -> Self {
+        Self {
+            stack_id,
+            stack_type: stack_id.stack_type(),
+            cards,
+        }
+    }
+
+    pub fn cards(&self) -> &'a [Card] {
+        self.cards
+    }
+
+    pub fn get_card(&self, index: CardIndex) -> Option<&'a Card> {
+        self.cards.get(index.get())
+    }
+
+    pub fn get_top_card(&self) -> Option<&'a Card> {
+        self.cards.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn find_card(&self, rank: Rank, suit: Suit) -> Option<CardIndex> {
+        self.cards
+            .iter()
+            .enumerate()
+            .filter(|(_index, card)| card.rank == rank && card.suit == suit)
+            .map(|(index, _card)| CardIndex::new(index))
+            .nth(0)
+    }
+
+    pub fn top_card_index(&self) -> CardIndex {
+        if self.cards.is_empty() {
+            CardIndex::BOTTOM
+        } else {
+            CardIndex::new(self.cards.len() - 1)
+        }
+    }
+
+    pub fn bottom_card(&self) -> Option<&'a Card> {
+        if self.cards.is_empty() {
+            None
+        } else {
+            Some(&self.cards[0])
+        }
+    }
+
+    pub fn top_card(&self) -> Option<&'a Card> {
+        if self.cards.is_empty() {
+            None
+        } else {
+            Some(&self.cards[self.cards.len() - 1])
+        }
+    }
+
+    pub fn previous_active_card(&self, start_index: Option<CardIndex>) -> Option<CardIndex> {
+        if self.cards.is_empty() {
+            return None;
+        }
+        let max_index = self.cards.len() - 1;
+        let index = if let Some(start_index) = start_index {
+            start_index.previous()?.get()
+        } else {
+            max_index
+        };
+        match self.stack_type {
+            StackType::Stock | StackType::Foundation | StackType::Waste | StackType::Reserve => {
+                if start_index.is_none() {
+                    Some(CardIndex::new(max_index))
+                } else {
+                    None
+                }
+            }
+            _ => {
+                for active_index in (0..=index).rev() {
+                    if self.cards[active_index].face_up {
+                        return Some(CardIndex::new(active_index));
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    pub fn next_active_card(&self, start_index: Option<CardIndex>) -> Option<CardIndex> {
+        if self.cards.is_empty() || self.stack_type == StackType::Stock {
+            return None;
+        }
+        let max_index = self.cards.len() - 1;
+        let index = if let Some(start_index) = start_index {
+            start_index.next().get()
+        } else {
+            0
+        };
+        if index <= max_index {
+            match self.stack_type {
+                StackType::Stock
+                | StackType::Foundation
+                | StackType::Waste
+                | StackType::Reserve => Some(CardIndex::new(max_index)),
+                _ => {
+                    for active_index in index..=max_index {
+                        if self.cards[active_index].face_up {
+                            return Some(CardIndex::new(active_index));
+                        }
+                    }
+                    None
+                }
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn is_top_face_up_card(&self, index: CardIndex) -> bool {
+        if self.cards.is_empty() {
+            return false;
+        }
+        let max_index = self.cards.len() - 1;
+        for search_index in 0..=max_index {
+            if self.cards[search_index].face_up {
+                return search_index == index.get();
+            }
+        }
+        return false;
+    }
+
+    pub fn is_run_from(&self, index: CardIndex) -> bool {
+        self.is_wrapping_run_from(index, false)
+    }
+
+    pub fn is_wrapping_run_from(&self, index: CardIndex, wrap_ranks: bool) -> bool {
+        let index = index.get();
+        if index >= self.cards.len() || !self.cards[index].face_up {
+            return false;
+        }
+        self.cards[index..].windows(2).all(|pair| {
+            pair[1].face_up
+                && !pair[1].is_same_color(&pair[0])
+                && pair[1].is_one_below_wrapping(&pair[0], wrap_ranks)
+        })
+    }
+
+    pub fn is_suited_run_from(&self, index: CardIndex) -> bool {
+        let index = index.get();
+        if index >= self.cards.len() || !self.cards[index].face_up {
+            return false;
+        }
+        self.cards[index..].windows(2).all(|pair| {
+            pair[1].face_up && pair[1].suit == pair[0].suit && pair[1].is_one_below(&pair[0])
+        })
+    }
+
+    pub fn foundation_can_accept_card(&self, card: &Card, rules: &RulesConfig) -> bool {
+        Foundation::new(*self, rules)
+            .map(|foundation| foundation.can_accept_card(card))
+            .unwrap_or(false)
+    }
+
+    pub fn foundation_can_accept_hand(&self, hand: &Stack, rules: &RulesConfig) -> bool {
+        if hand.cards.len() > 1 {
+            false
+        } else {
+            if let Some(card) = &hand.top_card() {
+                self.foundation_can_accept_card(card, rules)
+            } else {
+                false
+            }
+        }
+    }
+
+    pub fn tableau_can_accept_card(&self, card: &Card, rules: &RulesConfig) -> bool {
+        Tableau::new(*self, rules)
+            .map(|tableau| tableau.can_accept_card(card))
+            .unwrap_or(false)
+    }
+
+    pub fn tableau_can_accept_hand(&self, hand: &Stack, rules: &RulesConfig) -> bool {
+        if let Some(card) = &hand.bottom_card() {
+            self.tableau_can_accept_card(card, rules)
+        } else {
+            false
+        }
+    }
+
+    pub fn can_play(&self, hand: &Stack, rules: &RulesConfig) -> bool {
+        match self.stack_type {
+            StackType::Foundation => self.foundation_can_accept_hand(hand, rules),
+            StackType::Tableau => self.tableau_can_accept_hand(hand, rules),
+            _ => false,
+        }
+    }
+
+    pub fn can_play_card(
+        &self,
+        card: &Card,
+        moving_cards_count: usize,
+        rules: &RulesConfig,
+    ) -> bool {
+        match self.stack_type {
+            StackType::Foundation => {
+                moving_cards_count == 1 && self.foundation_can_accept_card(card, rules)
+            }
+            StackType::Tableau => self.tableau_can_accept_card(card, rules),
+            _ => false,
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Stack<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_fmt(format_args!("{:?}: [", self.stack_id))?;
+        if self.cards.len() > 0 {
+            let last_index = self.cards.len() - 1;
+            for (index, card) in self.cards.iter().enumerate() {
+                f.write_fmt(format_args!("{:?}", card))?;
+                if index < last_index {
+                    f.write_str(", ")?;
+                }
+            }
+        }
+        f.write_str("]")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,2107 @@
+use super::{
+    card::{card_code, card_from_code, make_decks, NO_CARD},
+    stack::{CardArena, MAX_CARDS, STACK_COUNT},
+    *,
+};
+use alloc::{fmt, vec::Vec};
+use core::convert::TryFrom;
+use enum_iterator::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+
+const CARD_BITS: usize = 6;
+const PACKED_BYTES: usize = MAX_CARDS * CARD_BITS / 8;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum BuildRule {
+    AlternateColors,
+    SameSuit,
+    AnySuit,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RulesConfig {
+    pub allow_foundation_pick_up: bool,
+    pub any_card_on_empty_tableau: bool,
+    pub max_recycles: Option<usize>,
+    pub strict_waste_top: bool,
+    pub foundation_base: Rank,
+    pub wrap_ranks: bool,
+    pub build: BuildRule,
+    pub single_card_moves: bool,
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self {
+            allow_foundation_pick_up: true,
+            any_card_on_empty_tableau: false,
+            max_recycles: None,
+            strict_waste_top: true,
+            foundation_base: Rank::Ace,
+            wrap_ranks: false,
+            build: BuildRule::AlternateColors,
+            single_card_moves: false,
+        }
+    }
+}
+
+pub trait Variant {
+    fn tableau_count(&self) -> usize;
+
+    fn draw_count(&self) -> usize;
+
+    fn has_waste(&self) -> bool {
+        true
+    }
+
+    fn reserve_count(&self) -> usize {
+        0
+    }
+
+    fn deck_count(&self) -> usize {
+        1
+    }
+
+    fn foundation_count(&self) -> usize {
+        4 * self.deck_count()
+    }
+
+    fn deal_from_stock(&self, table: &mut Table) -> Result<(), KlondikeError> {
+        table.deal_to_waste(self.draw_count())
+    }
+
+    fn is_movable_group(&self, stack: &Stack, index: CardIndex) -> bool {
+        stack.is_run_from(index)
+    }
+
+    fn foundation_accepts_drops(&self) -> bool {
+        true
+    }
+
+    fn after_move(&self, _table: &mut Table) {}
+
+    // Whether moves and deals can be taken back card by card. Variants that
+    // deal rows or tidy up after moves have their tables packed instead.
+    fn undoes_by_delta(&self) -> bool {
+        true
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        rules
+    }
+
+    fn deal(&self, table: &mut Table);
+}
+
+pub struct KlondikeVariant;
+
+impl Variant for KlondikeVariant {
+    fn tableau_count(&self) -> usize {
+        7
+    }
+
+    fn draw_count(&self) -> usize {
+        3
+    }
+
+    fn deal(&self, table: &mut Table) {
+        for tableau in TableauIndex::all(self.tableau_count()) {
+            table.deal_cards(tableau.stack_id(), tableau.get() + 1);
+            table.flip_top_card(tableau.stack_id());
+        }
+    }
+}
+
+pub struct WestcliffVariant;
+
+impl Variant for WestcliffVariant {
+    fn tableau_count(&self) -> usize {
+        10
+    }
+
+    fn draw_count(&self) -> usize {
+        1
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        RulesConfig {
+            any_card_on_empty_tableau: true,
+            max_recycles: Some(0),
+            ..rules
+        }
+    }
+
+    fn deal(&self, table: &mut Table) {
+        for stack_id in &TABLEAUX[..self.tableau_count()] {
+            table.deal_cards(*stack_id, 3);
+            table.flip_top_card(*stack_id);
+        }
+    }
+}
+
+pub struct EasthavenVariant;
+
+impl Variant for EasthavenVariant {
+    fn tableau_count(&self) -> usize {
+        7
+    }
+
+    fn draw_count(&self) -> usize {
+        0
+    }
+
+    fn has_waste(&self) -> bool {
+        false
+    }
+
+    fn deal_from_stock(&self, table: &mut Table) -> Result<(), KlondikeError> {
+        table.deal_row_to_tableaux()
+    }
+
+    fn undoes_by_delta(&self) -> bool {
+        false
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        RulesConfig {
+            any_card_on_empty_tableau: true,
+            max_recycles: Some(0),
+            ..rules
+        }
+    }
+
+    fn deal(&self, table: &mut Table) {
+        for stack_id in &TABLEAUX[..self.tableau_count()] {
+            table.deal_cards(*stack_id, 3);
+            table.flip_top_card(*stack_id);
+        }
+    }
+}
+
+pub struct YukonVariant;
+
+impl Variant for YukonVariant {
+    fn tableau_count(&self) -> usize {
+        7
+    }
+
+    fn draw_count(&self) -> usize {
+        0
+    }
+
+    fn has_waste(&self) -> bool {
+        false
+    }
+
+    fn deal_from_stock(&self, _table: &mut Table) -> Result<(), KlondikeError> {
+        Err(KlondikeError::NoCardsToDeal)
+    }
+
+    fn is_movable_group(&self, stack: &Stack, index: CardIndex) -> bool {
+        stack.cards()[index.get()..].iter().all(|card| card.face_up)
+    }
+
+    fn deal(&self, table: &mut Table) {
+        for tableau in TableauIndex::all(self.tableau_count()) {
+            let face_up = if tableau.get() == 0 { 1 } else { 5 };
+            table.deal_cards(tableau.stack_id(), tableau.get() + face_up);
+            table.update_cards(tableau.stack_id(), |cards| {
+                let face_down = cards.len() - face_up;
+                for card in &mut cards[face_down..] {
+                    card.face_up = true;
+                }
+            });
+        }
+    }
+}
+
+pub struct CanfieldVariant;
+
+impl Variant for CanfieldVariant {
+    fn tableau_count(&self) -> usize {
+        4
+    }
+
+    fn draw_count(&self) -> usize {
+        3
+    }
+
+    fn reserve_count(&self) -> usize {
+        1
+    }
+
+    fn is_movable_group(&self, stack: &Stack, index: CardIndex) -> bool {
+        stack.is_wrapping_run_from(index, true)
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        RulesConfig {
+            any_card_on_empty_tableau: true,
+            wrap_ranks: true,
+            ..rules
+        }
+    }
+
+    fn deal(&self, table: &mut Table) {
+        table.deal_cards(StackId::Reserve1, 13);
+        table.flip_top_card(StackId::Reserve1);
+
+        table.deal_base_card();
+
+        for stack_id in &TABLEAUX[..self.tableau_count()] {
+            table.deal_cards(*stack_id, 1);
+            table.flip_top_card(*stack_id);
+        }
+    }
+}
+
+pub struct AgnesVariant;
+
+impl Variant for AgnesVariant {
+    fn tableau_count(&self) -> usize {
+        7
+    }
+
+    fn draw_count(&self) -> usize {
+        0
+    }
+
+    fn has_waste(&self) -> bool {
+        false
+    }
+
+    fn reserve_count(&self) -> usize {
+        7
+    }
+
+    fn deal_from_stock(&self, table: &mut Table) -> Result<(), KlondikeError> {
+        table.deal_row_to_reserves()
+    }
+
+    fn undoes_by_delta(&self) -> bool {
+        false
+    }
+
+    fn is_movable_group(&self, stack: &Stack, index: CardIndex) -> bool {
+        stack.is_wrapping_run_from(index, true)
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        RulesConfig {
+            any_card_on_empty_tableau: false,
+            max_recycles: Some(0),
+            wrap_ranks: true,
+            ..rules
+        }
+    }
+
+    fn deal(&self, table: &mut Table) {
+        KlondikeVariant.deal(table);
+        table.deal_base_card();
+        table
+            .deal_row_to_reserves()
+            .expect("stock has cards for the reserves");
+    }
+}
+
+pub struct ThoughtfulVariant;
+
+impl Variant for ThoughtfulVariant {
+    fn tableau_count(&self) -> usize {
+        7
+    }
+
+    fn draw_count(&self) -> usize {
+        3
+    }
+
+    fn deal(&self, table: &mut Table) {
+        KlondikeVariant.deal(table);
+        for stack_id in &TABLEAUX[..self.tableau_count()] {
+            table.update_cards(*stack_id, |cards| {
+                for card in cards {
+                    card.face_up = true;
+                }
+            });
+        }
+    }
+}
+
+pub struct FortyThievesVariant;
+
+impl Variant for FortyThievesVariant {
+    fn tableau_count(&self) -> usize {
+        10
+    }
+
+    fn draw_count(&self) -> usize {
+        1
+    }
+
+    fn deck_count(&self) -> usize {
+        2
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        RulesConfig {
+            any_card_on_empty_tableau: true,
+            max_recycles: Some(0),
+            build: BuildRule::SameSuit,
+            single_card_moves: true,
+            ..rules
+        }
+    }
+
+    fn deal(&self, table: &mut Table) {
+        for stack_id in &TABLEAUX[..self.tableau_count()] {
+            table.deal_cards(*stack_id, 4);
+            table.update_cards(*stack_id, |cards| {
+                for card in cards {
+                    card.face_up = true;
+                }
+            });
+        }
+    }
+}
+
+pub struct SpideretteVariant;
+
+impl Variant for SpideretteVariant {
+    fn tableau_count(&self) -> usize {
+        7
+    }
+
+    fn draw_count(&self) -> usize {
+        0
+    }
+
+    fn has_waste(&self) -> bool {
+        false
+    }
+
+    fn deal_from_stock(&self, table: &mut Table) -> Result<(), KlondikeError> {
+        table.deal_row_to_tableaux()
+    }
+
+    fn is_movable_group(&self, stack: &Stack, index: CardIndex) -> bool {
+        stack.is_suited_run_from(index)
+    }
+
+    fn foundation_accepts_drops(&self) -> bool {
+        false
+    }
+
+    fn after_move(&self, table: &mut Table) {
+        table.clear_completed_runs();
+    }
+
+    fn undoes_by_delta(&self) -> bool {
+        false
+    }
+
+    fn adjust_rules(&self, rules: RulesConfig) -> RulesConfig {
+        RulesConfig {
+            allow_foundation_pick_up: false,
+            any_card_on_empty_tableau: true,
+            max_recycles: Some(0),
+            build: BuildRule::AnySuit,
+            ..rules
+        }
+    }
+
+    fn deal(&self, table: &mut Table) {
+        KlondikeVariant.deal(table);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, IntoEnumIterator, PartialEq, Serialize)]
+pub enum VariantId {
+    Klondike,
+    Westcliff,
+    Easthaven,
+    Yukon,
+    Canfield,
+    Agnes,
+    FortyThieves,
+    Spiderette,
+    Thoughtful,
+}
+
+impl VariantId {
+    pub fn variant(self) -> &'static dyn Variant {
+        match self {
+            VariantId::Klondike => &KlondikeVariant,
+            VariantId::Westcliff => &WestcliffVariant,
+            VariantId::Easthaven => &EasthavenVariant,
+            VariantId::Yukon => &YukonVariant,
+            VariantId::Canfield => &CanfieldVariant,
+            VariantId::Agnes => &AgnesVariant,
+            VariantId::FortyThieves => &FortyThievesVariant,
+            VariantId::Spiderette => &SpideretteVariant,
+            VariantId::Thoughtful => &ThoughtfulVariant,
+        }
+    }
+
+    pub fn tableau_count(self) -> usize {
+        self.variant().tableau_count()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct HandOrigin {
+    pub stack: StackId,
+    pub index: CardIndex,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KlondikeError {
+    EmptyStack(StackId),
+    InvalidIndex(CardLocation),
+    HandEmpty,
+    HandNotEmpty,
+    NoCardsToDeal,
+    NoRedealsLeft,
+    StockNotEmpty,
+    CannotPickUp(StackId),
+    NotFaceUp(CardLocation),
+    NotTopCard(CardLocation),
+    NotARun(CardLocation),
+    CannotDropOn(StackId),
+    IllegalDrop(StackId),
+    WrongColor(StackId),
+    WrongSuit(StackId),
+    WrongRank(StackId),
+    FoundationSuitMismatch(StackId),
+    TooManyCards(StackId),
+    CannotStartColumn(StackId),
+    InvalidTable,
+    InvalidShareCode,
+    InvalidNotation,
+}
+
+impl fmt::Display for KlondikeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            KlondikeError::EmptyStack(stack_id) => {
+                f.write_fmt(format_args!("{:?} has no cards", stack_id))
+            }
+            KlondikeError::InvalidIndex(source) => {
+                f.write_fmt(format_args!("no card at {:?}", source))
+            }
+            KlondikeError::HandEmpty => f.write_str("no cards in hand"),
+            KlondikeError::HandNotEmpty => f.write_str("already holding cards"),
+            KlondikeError::NoCardsToDeal => f.write_str("stock and waste are empty"),
+            KlondikeError::NoRedealsLeft => f.write_str("no redeals left"),
+            KlondikeError::StockNotEmpty => f.write_str("stock still has cards"),
+            KlondikeError::CannotPickUp(stack_id) => {
+                f.write_fmt(format_args!("can't pick up from {:?}", stack_id))
+            }
+            KlondikeError::NotFaceUp(source) => {
+                f.write_fmt(format_args!("{:?} is face down", source))
+            }
+            KlondikeError::NotTopCard(source) => {
+                f.write_fmt(format_args!("{:?} is not the top card", source))
+            }
+            KlondikeError::NotARun(source) => {
+                f.write_fmt(format_args!("cards from {:?} are not a run", source))
+            }
+            KlondikeError::CannotDropOn(stack_id) => {
+                f.write_fmt(format_args!("can't drop cards on {:?}", stack_id))
+            }
+            KlondikeError::IllegalDrop(stack_id) => {
+                f.write_fmt(format_args!("{:?} can't accept the hand", stack_id))
+            }
+            KlondikeError::WrongColor(stack_id) => {
+                f.write_fmt(format_args!("hand is the same color as {:?}", stack_id))
+            }
+            KlondikeError::WrongSuit(stack_id) => {
+                f.write_fmt(format_args!("hand doesn't follow suit on {:?}", stack_id))
+            }
+            KlondikeError::WrongRank(stack_id) => {
+                f.write_fmt(format_args!("hand is the wrong rank for {:?}", stack_id))
+            }
+            KlondikeError::FoundationSuitMismatch(stack_id) => {
+                f.write_fmt(format_args!("hand is the wrong suit for {:?}", stack_id))
+            }
+            KlondikeError::TooManyCards(stack_id) => {
+                f.write_fmt(format_args!("{:?} takes one card at a time", stack_id))
+            }
+            KlondikeError::CannotStartColumn(stack_id) => {
+                f.write_fmt(format_args!("hand can't start a column on {:?}", stack_id))
+            }
+            KlondikeError::InvalidTable => f.write_str("saved table is not a complete deck"),
+            KlondikeError::InvalidShareCode => f.write_str("share code is not valid"),
+            KlondikeError::InvalidNotation => f.write_str("card notation is not valid"),
+        }
+    }
+}
+
+impl KlondikeError {
+    // A line for the player on why the action was refused.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            KlondikeError::EmptyStack(_) => "There are no cards there",
+            KlondikeError::InvalidIndex(_) => "There's no card there",
+            KlondikeError::HandEmpty => "You aren't holding any cards",
+            KlondikeError::HandNotEmpty => "Put down the cards you're holding first",
+            KlondikeError::NoCardsToDeal => "The stock and waste are empty",
+            KlondikeError::NoRedealsLeft => "No redeals left",
+            KlondikeError::StockNotEmpty => "The stock still has cards",
+            KlondikeError::CannotPickUp(_) => "Cards can't be picked up from there",
+            KlondikeError::NotFaceUp(_) => "That card is face down",
+            KlondikeError::NotTopCard(_) => "Only the top card can be moved",
+            KlondikeError::NotARun(_) => "Those cards aren't a run",
+            KlondikeError::CannotDropOn(_) | KlondikeError::IllegalDrop(_) => {
+                "Those cards can't go there"
+            }
+            KlondikeError::WrongColor(_) => "Build down in alternating colors",
+            KlondikeError::WrongSuit(_) => "Build down in the same suit",
+            KlondikeError::WrongRank(stack_id) => match stack_id.stack_type() {
+                StackType::Foundation => "Foundations build up one rank at a time",
+                _ => "Build down one rank at a time",
+            },
+            KlondikeError::FoundationSuitMismatch(_) => "That foundation is for another suit",
+            KlondikeError::TooManyCards(_) => "Foundations take one card at a time",
+            KlondikeError::CannotStartColumn(_) => "That card can't start an empty column",
+            KlondikeError::InvalidTable => "The saved game is damaged",
+            KlondikeError::InvalidShareCode => "That share code isn't valid",
+            KlondikeError::InvalidNotation => "That card notation isn't valid",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct SavedTable {
+    stacks: Vec<(StackId, Vec<Card>)>,
+    hand_origin: Option<HandOrigin>,
+    stock_cycles: usize,
+    rules: RulesConfig,
+    variant: VariantId,
+}
+
+impl From<Table> for SavedTable {
+    fn from(table: Table) -> Self {
+        Self {
+            stacks: StackId::into_enum_iter()
+                .map(|stack_id| (stack_id, table.get_stack(stack_id).cards().to_vec()))
+                .filter(|(_, cards)| !cards.is_empty())
+                .collect(),
+            hand_origin: table.hand_origin,
+            stock_cycles: table.stock_cycles,
+            rules: table.rules,
+            variant: table.variant,
+        }
+    }
+}
+
+impl TryFrom<SavedTable> for Table {
+    type Error = KlondikeError;
+
+    fn try_from(saved: SavedTable) -> Result<Self, Self::Error> {
+        let deck_count = saved.variant.variant().deck_count();
+        let card_count: usize = saved.stacks.iter().map(|(_, cards)| cards.len()).sum();
+        if card_count != 52 * deck_count {
+            return Err(KlondikeError::InvalidTable);
+        }
+        let mut table = Table {
+            cards: CardArena::new(&[]),
+            hand_origin: saved.hand_origin,
+            stock_cycles: saved.stock_cycles,
+            rules: saved.rules,
+            variant: saved.variant,
+        };
+        for (stack_id, cards) in &saved.stacks {
+            table.set_cards(*stack_id, cards);
+        }
+        let mut cards: Vec<Card> = StackId::into_enum_iter()
+            .flat_map(|stack_id| table.get_stack(stack_id).cards().to_vec())
+            .map(|card| Card {
+                face_up: false,
+                ..card
+            })
+            .collect();
+        cards.sort();
+        let mut deck: Vec<Card> = (0..deck_count)
+            .flat_map(|_| Card::ORDERED_DECK.iter().cloned())
+            .collect();
+        deck.sort();
+        if cards != deck {
+            return Err(KlondikeError::InvalidTable);
+        }
+        Ok(table)
+    }
+}
+
+#[derive(Clone, Deserialize, Hash, PartialEq, Eq, Serialize)]
+#[serde(into = "SavedTable", try_from = "SavedTable")]
+pub struct Table {
+    cards: CardArena,
+    pub hand_origin: Option<HandOrigin>,
+    pub stock_cycles: usize,
+    pub rules: RulesConfig,
+    pub variant: VariantId,
+}
+
+impl fmt::Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.debug_struct("Table")
+            .field(
+                "stacks",
+                &StackId::into_enum_iter()
+                    .map(|stack_id| self.get_stack(stack_id))
+                    .filter(|stack| !stack.is_empty())
+                    .collect::<Vec<_>>(),
+            )
+            .field("hand_origin", &self.hand_origin)
+            .field("stock_cycles", &self.stock_cycles)
+            .finish()
+    }
+}
+
+impl Table {
+    pub fn new(seed: u64) -> Self {
+        Self::with_rules(seed, RulesConfig::default())
+    }
+
+    pub fn with_rules(seed: u64, rules: RulesConfig) -> Self {
+        Self::with_variant(seed, VariantId::Klondike, rules)
+    }
+
+    pub fn with_variant(seed: u64, variant: VariantId, rules: RulesConfig) -> Self {
+        let deck = make_decks(seed, variant.variant().deck_count());
+        let mut table = Self {
+            cards: CardArena::new(&deck),
+            hand_origin: None,
+            stock_cycles: 0,
+            rules: variant.variant().adjust_rules(rules),
+            variant,
+        };
+        variant.variant().deal(&mut table);
+        table
+    }
+
+    // Builds a position from card notation per stack; cards left out go face
+    // down under anything listed for the stock.
+    pub fn from_layout(
+        variant: VariantId,
+        rules: RulesConfig,
+        layout: &[(StackId, &str)],
+    ) -> Result<Self, KlondikeError> {
+        let mut remaining: Vec<Card> = (0..variant.variant().deck_count())
+            .flat_map(|_| Card::ORDERED_DECK.iter().cloned())
+            .collect();
+        let mut table = Self {
+            cards: CardArena::new(&[]),
+            hand_origin: None,
+            stock_cycles: 0,
+            rules: variant.variant().adjust_rules(rules),
+            variant,
+        };
+        for (stack_id, notation) in layout {
+            let cards = notation
+                .split_whitespace()
+                .map(Card::from_notation)
+                .collect::<Result<Vec<Card>, KlondikeError>>()?;
+            for card in &cards {
+                let index = remaining
+                    .iter()
+                    .position(|other| other.rank == card.rank && other.suit == card.suit)
+                    .ok_or(KlondikeError::InvalidTable)?;
+                remaining.remove(index);
+            }
+            table.set_cards(*stack_id, &cards);
+        }
+        remaining.extend_from_slice(table.get_stack(StackId::Stock).cards());
+        table.set_cards(StackId::Stock, &remaining);
+        Ok(table)
+    }
+
+    pub fn tableau_ids(&self) -> &'static [StackId] {
+        &TABLEAUX[..self.variant.tableau_count()]
+    }
+
+    pub fn foundation_ids(&self) -> &'static [StackId] {
+        &FOUNDATIONS[..self.variant.variant().foundation_count()]
+    }
+
+    pub fn reserve_ids(&self) -> &'static [StackId] {
+        &RESERVES[..self.variant.variant().reserve_count()]
+    }
+
+    pub fn has_stack(&self, stack_id: StackId) -> bool {
+        match self.get_stack(stack_id).stack_type {
+            StackType::Tableau => self.tableau_ids().contains(&stack_id),
+            StackType::Waste => self.variant.variant().has_waste(),
+            StackType::Reserve => self.reserve_ids().contains(&stack_id),
+            StackType::Foundation => self.foundation_ids().contains(&stack_id),
+            StackType::Stock | StackType::Hand => true,
+        }
+    }
+
+    pub fn get_stack(&self, stack_id: StackId) -> Stack<'_> {
+        Stack::new(stack_id, self.cards.cards(stack_id))
+    }
+
+    // A copy of the table with the play made, for looking ahead without
+    // touching the game in progress.
+    pub fn preview(&self, play: Play) -> Result<Table, KlondikeError> {
+        let mut next = self.clone();
+        next.play(play)?;
+        Ok(next)
+    }
+
+    fn play(&mut self, play: Play) -> Result<(), KlondikeError> {
+        match play {
+            Play::MoveCards(source, target) => {
+                self.take_selected_cards_from_stack(source.stack, source.index)?;
+                if let Err(err) = self.put_hand_on_stack(target) {
+                    self.return_hand()?;
+                    return Err(err);
+                }
+            }
+            Play::DrawFromStock | Play::RecycleWaste => self.deal_from_stock()?,
+            Play::Setup => (),
+        }
+        Ok(())
+    }
+
+    // Makes the play and returns just enough to take it back with unapply.
+    pub fn apply_move(&mut self, play: Play) -> Result<AppliedMove, KlondikeError> {
+        if self.cards_in_hand() {
+            return Err(KlondikeError::HandNotEmpty);
+        }
+        let before = if self.variant.variant().undoes_by_delta() {
+            None
+        } else {
+            Some(PackedTable::pack(self))
+        };
+        let delta = match play {
+            Play::MoveCards(source, target) => {
+                self.take_selected_cards_from_stack(source.stack, source.index)?;
+                let count = self.get_stack(StackId::Hand).len();
+                let flipped = matches!(
+                    self.get_stack(source.stack).top_card(),
+                    Some(card) if !card.face_up
+                );
+                if let Err(err) = self.put_hand_on_stack(target) {
+                    self.return_hand()?;
+                    return Err(err);
+                }
+                MoveDelta::Moved {
+                    source,
+                    target,
+                    count,
+                    flipped,
+                }
+            }
+            Play::DrawFromStock | Play::RecycleWaste => {
+                let stock = self.get_stack(StackId::Stock).len();
+                let waste = self.get_stack(StackId::Waste).len();
+                self.deal_from_stock()?;
+                if stock == 0 {
+                    MoveDelta::Recycled(waste)
+                } else {
+                    MoveDelta::Dealt(stock - self.get_stack(StackId::Stock).len())
+                }
+            }
+            Play::Setup => MoveDelta::Unchanged,
+        };
+        let revealed = matches!(delta, MoveDelta::Moved { flipped: true, .. });
+        Ok(AppliedMove {
+            play,
+            revealed,
+            delta: before.map(MoveDelta::Packed).unwrap_or(delta),
+        })
+    }
+
+    // Takes back the most recent applied move. Moves have to come off in the
+    // reverse of the order they went on.
+    pub fn unapply(&mut self, applied: AppliedMove) {
+        if self.cards_in_hand() {
+            let _ = self.return_hand();
+        }
+        match applied.delta {
+            MoveDelta::Moved {
+                source,
+                target,
+                count,
+                flipped,
+            } => {
+                if flipped {
+                    self.flip_top_card(source.stack);
+                }
+                let start = self.get_stack(target).len() - count;
+                self.cards
+                    .move_cards(target, start, count, source.stack, source.index.get());
+            }
+            MoveDelta::Dealt(count) => {
+                let start = self.get_stack(StackId::Waste).len() - count;
+                self.update_cards(StackId::Waste, |waste| {
+                    for card in &mut waste[start..] {
+                        card.face_up = false;
+                    }
+                    waste[start..].reverse();
+                });
+                let at = self.get_stack(StackId::Stock).len();
+                self.cards
+                    .move_cards(StackId::Waste, start, count, StackId::Stock, at);
+            }
+            MoveDelta::Recycled(count) => {
+                self.update_cards(StackId::Stock, |stock| {
+                    stock.reverse();
+                    for card in stock.iter_mut() {
+                        card.face_up = true;
+                    }
+                });
+                self.cards
+                    .move_cards(StackId::Stock, 0, count, StackId::Waste, 0);
+                self.stock_cycles -= 1;
+            }
+            MoveDelta::Packed(before) => self.restore(before.unpack()),
+            MoveDelta::Unchanged => (),
+        }
+    }
+
+    // Zobrist hash of where every card lies and which way up, so equal layouts
+    // hash alike however they were reached.
+    pub fn position_hash(&self) -> u64 {
+        self.cards.hash
+    }
+
+    pub fn generation(&self, stack_id: StackId) -> u32 {
+        self.cards.generations[stack_id as usize]
+    }
+
+    pub fn generations(&self) -> &[u32] {
+        &self.cards.generations
+    }
+
+    pub fn restore(&mut self, snapshot: Table) {
+        let mut generations = self.cards.generations;
+        for stack_id in StackId::into_enum_iter() {
+            if self.cards.cards(stack_id) != snapshot.cards.cards(stack_id) {
+                generations[stack_id as usize] = generations[stack_id as usize].wrapping_add(1);
+            }
+        }
+        *self = snapshot;
+        self.cards.generations = generations;
+    }
+
+    pub fn set_cards(&mut self, stack_id: StackId, cards: &[Card]) {
+        self.cards.replace(stack_id, cards);
+    }
+
+    fn update_cards(&mut self, stack_id: StackId, update: impl FnOnce(&mut [Card])) {
+        self.cards.update(stack_id, update);
+    }
+
+    fn deal_cards(&mut self, stack_id: StackId, count: usize) {
+        let stock_len = self.get_stack(StackId::Stock).len();
+        let at = self.get_stack(stack_id).len();
+        self.cards
+            .move_cards(StackId::Stock, stock_len - count, count, stack_id, at);
+    }
+
+    fn flip_top_card(&mut self, stack_id: StackId) {
+        self.update_cards(stack_id, |cards| {
+            if let Some(card) = cards.last_mut() {
+                card.face_up = !card.face_up;
+            }
+        });
+    }
+
+    pub fn find_card(&self, rank: Rank, suit: Suit) -> Option<CardLocation> {
+        for stack_id in StackId::into_enum_iter() {
+            let stack = self.get_stack(stack_id);
+            if let Some(index) = stack.find_card(rank, suit) {
+                return Some(CardLocation {
+                    stack: stack_id,
+                    index,
+                });
+            }
+        }
+        None
+    }
+
+    pub fn card_count(&self) -> usize {
+        StackId::into_enum_iter()
+            .map(|stack_id| self.get_stack(stack_id).len())
+            .sum::<usize>()
+    }
+
+    pub fn cards_in_hand(&self) -> bool {
+        !self.get_stack(StackId::Hand).is_empty()
+    }
+
+    pub fn has_cards_in_stock(&self) -> bool {
+        !self.get_stack(StackId::Stock).is_empty()
+    }
+
+    pub fn has_cards_in_waste(&self) -> bool {
+        !self.get_stack(StackId::Waste).is_empty()
+    }
+
+    pub fn foundation_count(&self) -> usize {
+        FOUNDATIONS
+            .iter()
+            .map(|stack_id| self.get_stack(*stack_id).len())
+            .sum::<usize>()
+    }
+
+    pub fn face_down_remaining(&self) -> usize {
+        self.tableaux()
+            .map(|tableau| tableau.face_down_count())
+            .sum::<usize>()
+    }
+
+    pub fn stock_cycles_used(&self) -> usize {
+        self.stock_cycles
+    }
+
+    pub fn redeals_remaining(&self) -> Option<usize> {
+        self.rules
+            .max_recycles
+            .map(|max_recycles| max_recycles.saturating_sub(self.stock_cycles))
+    }
+
+    pub fn can_recycle(&self) -> bool {
+        self.redeals_remaining() != Some(0)
+    }
+
+    pub fn stock_is_dead(&self) -> bool {
+        !self.has_cards_in_stock() && (!self.has_cards_in_waste() || !self.can_recycle())
+    }
+
+    pub fn empty_tableaux(&self) -> usize {
+        self.tableau_ids()
+            .iter()
+            .filter(|stack_id| self.get_stack(**stack_id).is_empty())
+            .count()
+    }
+
+    pub fn deck_size(&self) -> usize {
+        52 * self.variant.variant().deck_count()
+    }
+
+    pub fn winner(&self) -> bool {
+        self.foundation_count() == self.deck_size()
+    }
+
+    pub fn deal_from_stock(&mut self) -> Result<(), KlondikeError> {
+        self.variant.variant().deal_from_stock(self)?;
+        self.variant.variant().after_move(self);
+        Ok(())
+    }
+
+    pub fn deal_to_waste(&mut self, draw_count: usize) -> Result<(), KlondikeError> {
+        let amount_to_deal = draw_count.min(self.get_stack(StackId::Stock).len());
+        if amount_to_deal == 0 {
+            let waste_len = self.get_stack(StackId::Waste).len();
+            if waste_len == 0 {
+                return Err(KlondikeError::NoCardsToDeal);
+            }
+            if !self.can_recycle() {
+                return Err(KlondikeError::NoRedealsLeft);
+            }
+            self.cards
+                .move_cards(StackId::Waste, 0, waste_len, StackId::Stock, 0);
+            self.update_cards(StackId::Stock, |stock| {
+                for card in stock.iter_mut() {
+                    card.face_up = false;
+                }
+                stock.reverse();
+            });
+            self.stock_cycles += 1;
+        } else {
+            self.deal_cards(StackId::Waste, amount_to_deal);
+            self.update_cards(StackId::Waste, |waste| {
+                let dealt = waste.len() - amount_to_deal;
+                waste[dealt..].reverse();
+                for card in &mut waste[dealt..] {
+                    card.face_up = true;
+                }
+            });
+        }
+        Ok(())
+    }
+
+    pub fn deal_row_to_tableaux(&mut self) -> Result<(), KlondikeError> {
+        if !self.has_cards_in_stock() {
+            return Err(KlondikeError::NoCardsToDeal);
+        }
+        for stack_id in self.tableau_ids() {
+            if self.has_cards_in_stock() {
+                self.deal_cards(*stack_id, 1);
+                self.expose_top_card_of_stack(*stack_id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn deal_row_to_reserves(&mut self) -> Result<(), KlondikeError> {
+        if !self.has_cards_in_stock() {
+            return Err(KlondikeError::NoCardsToDeal);
+        }
+        for stack_id in self.reserve_ids() {
+            if self.has_cards_in_stock() {
+                self.deal_cards(*stack_id, 1);
+                self.expose_top_card_of_stack(*stack_id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear_completed_runs(&mut self) {
+        for stack_id in self.tableau_ids() {
+            let stack = self.get_stack(*stack_id);
+            let start = match stack.len().checked_sub(13) {
+                Some(start) => start,
+                None => continue,
+            };
+            if stack.cards()[start].rank != Rank::King
+                || !stack.is_suited_run_from(CardIndex::new(start))
+            {
+                continue;
+            }
+            let foundation_id = self
+                .foundation_ids()
+                .iter()
+                .copied()
+                .find(|foundation_id| self.get_stack(*foundation_id).is_empty());
+            if let Some(foundation_id) = foundation_id {
+                self.cards
+                    .move_cards(*stack_id, start, 13, foundation_id, 0);
+                self.update_cards(foundation_id, |cards| cards.reverse());
+                self.expose_top_card_of_stack(*stack_id);
+            }
+        }
+    }
+
+    pub fn deal_base_card(&mut self) {
+        let base_card = self
+            .get_stack(StackId::Stock)
+            .top_card()
+            .expect("card")
+            .clone();
+        self.rules.foundation_base = base_card.rank;
+        if let Some(foundation_id) = FOUNDATIONS.iter().copied().find(|foundation_id| {
+            self.get_stack(*foundation_id)
+                .foundation_can_accept_card(&base_card, &self.rules)
+        }) {
+            self.deal_cards(foundation_id, 1);
+            self.expose_top_card_of_stack(foundation_id);
+        }
+    }
+
+    pub fn recycle_waste(&mut self) -> Result<(), KlondikeError> {
+        if self.has_cards_in_stock() {
+            return Err(KlondikeError::StockNotEmpty);
+        }
+        self.deal_from_stock()
+    }
+
+    pub fn expose_top_card_of_stack(&mut self, stack_id: StackId) {
+        self.update_cards(stack_id, |cards| {
+            if let Some(card) = cards.last_mut() {
+                card.face_up = true;
+            }
+        });
+    }
+
+    pub fn next_pick_up_index(
+        &self,
+        stack_id: StackId,
+        start: Option<CardIndex>,
+    ) -> Option<CardIndex> {
+        let stack = self.get_stack(stack_id);
+        if stack.stack_type == StackType::Waste && !self.rules.strict_waste_top {
+            let first_visible = CardIndex::new(stack.len().saturating_sub(WASTE_VISIBLE));
+            let index = start.map_or(first_visible, |start| start.next());
+            if index.get() < stack.len() {
+                Some(index)
+            } else {
+                None
+            }
+        } else {
+            stack.next_active_card(start)
+        }
+    }
+
+    pub fn is_pick_up_source(&self, stack_id: StackId) -> bool {
+        match self.get_stack(stack_id).stack_type {
+            StackType::Foundation => self.rules.allow_foundation_pick_up,
+            StackType::Stock | StackType::Hand => false,
+            StackType::Waste | StackType::Reserve | StackType::Tableau => true,
+        }
+    }
+
+    pub fn check_pick_up(&self, stack_id: StackId, index: CardIndex) -> Result<(), KlondikeError> {
+        if self.cards_in_hand() {
+            return Err(KlondikeError::HandNotEmpty);
+        }
+        let stack = self.get_stack(stack_id);
+        let source = CardLocation::new(stack_id, index);
+        let card = stack
+            .get_card(index)
+            .ok_or(KlondikeError::InvalidIndex(source))?;
+        match stack.stack_type {
+            StackType::Foundation if !self.rules.allow_foundation_pick_up => {
+                Err(KlondikeError::CannotPickUp(stack_id))
+            }
+            StackType::Waste if !self.rules.strict_waste_top => {
+                if index.get() < stack.len().saturating_sub(WASTE_VISIBLE) {
+                    Err(KlondikeError::NotTopCard(source))
+                } else {
+                    Ok(())
+                }
+            }
+            StackType::Waste | StackType::Reserve | StackType::Foundation => {
+                if index != stack.top_card_index() {
+                    Err(KlondikeError::NotTopCard(source))
+                } else {
+                    Ok(())
+                }
+            }
+            StackType::Tableau => {
+                if !card.face_up {
+                    Err(KlondikeError::NotFaceUp(source))
+                } else if self.rules.single_card_moves && index != stack.top_card_index() {
+                    Err(KlondikeError::NotTopCard(source))
+                } else if !self.variant.variant().is_movable_group(&stack, index) {
+                    Err(KlondikeError::NotARun(source))
+                } else {
+                    Ok(())
+                }
+            }
+            StackType::Stock | StackType::Hand => Err(KlondikeError::CannotPickUp(stack_id)),
+        }
+    }
+
+    pub fn take_top_card_from_stack(&mut self, stack_id: StackId) -> Result<(), KlondikeError> {
+        let top_card_index = self.get_stack(stack_id).top_card_index();
+        if self.get_stack(stack_id).is_empty() {
+            return Err(KlondikeError::EmptyStack(stack_id));
+        }
+        self.check_pick_up(stack_id, top_card_index)?;
+        self.cards
+            .move_cards(stack_id, top_card_index.get(), 1, StackId::Hand, 0);
+        self.hand_taken(HandOrigin {
+            stack: stack_id,
+            index: top_card_index,
+        });
+        Ok(())
+    }
+
+    pub fn take_selected_cards_from_stack(
+        &mut self,
+        stack_id: StackId,
+        index: CardIndex,
+    ) -> Result<(), KlondikeError> {
+        self.check_pick_up(stack_id, index)?;
+        let stack = self.get_stack(stack_id);
+        let count = if stack.stack_type == StackType::Waste {
+            1
+        } else {
+            stack.len() - index.get()
+        };
+        self.cards
+            .move_cards(stack_id, index.get(), count, StackId::Hand, 0);
+        self.hand_taken(HandOrigin {
+            stack: stack_id,
+            index,
+        });
+        Ok(())
+    }
+
+    fn hand_taken(&mut self, origin: HandOrigin) {
+        self.hand_origin = Some(origin);
+    }
+
+    pub fn best_target(&self) -> Option<StackId> {
+        self.foundation_ids()
+            .iter()
+            .chain(self.tableau_ids().iter())
+            .copied()
+            .find(|stack_id| self.stack_can_accept_hand(*stack_id))
+    }
+
+    pub fn check_drop(&self, stack_id: StackId) -> Result<(), KlondikeError> {
+        match self.get_stack(stack_id).stack_type {
+            StackType::Stock | StackType::Waste | StackType::Reserve | StackType::Hand => {
+                Err(KlondikeError::CannotDropOn(stack_id))
+            }
+            StackType::Foundation | StackType::Tableau => {
+                if self.stack_can_accept_hand(stack_id) {
+                    Ok(())
+                } else {
+                    Err(self.drop_error(stack_id))
+                }
+            }
+        }
+    }
+
+    // Why the hand can't go on a stack, as closely as the rules can say.
+    fn drop_error(&self, stack_id: StackId) -> KlondikeError {
+        let target = self.get_stack(stack_id);
+        let hand = self.get_stack(StackId::Hand);
+        let card = match hand.bottom_card() {
+            Some(card) => card,
+            None => return KlondikeError::HandEmpty,
+        };
+        if !self.has_stack(stack_id) {
+            return KlondikeError::IllegalDrop(stack_id);
+        }
+        match target.stack_type {
+            StackType::Foundation if !self.variant.variant().foundation_accepts_drops() => {
+                KlondikeError::CannotDropOn(stack_id)
+            }
+            StackType::Foundation if hand.len() > 1 => KlondikeError::TooManyCards(stack_id),
+            StackType::Foundation => match Foundation::new(target, &self.rules) {
+                Some(foundation)
+                    if foundation.suit().is_some() && foundation.suit() != Some(card.suit) =>
+                {
+                    KlondikeError::FoundationSuitMismatch(stack_id)
+                }
+                Some(foundation) if foundation.next_needed_rank() != Some(card.rank) => {
+                    KlondikeError::WrongRank(stack_id)
+                }
+                Some(_) | None => KlondikeError::FoundationSuitMismatch(stack_id),
+            },
+            StackType::Tableau => match target.top_card() {
+                Some(top_card) => match self.rules.build {
+                    BuildRule::AlternateColors if top_card.is_same_color(card) => {
+                        KlondikeError::WrongColor(stack_id)
+                    }
+                    BuildRule::SameSuit if top_card.suit != card.suit => {
+                        KlondikeError::WrongSuit(stack_id)
+                    }
+                    BuildRule::AlternateColors | BuildRule::SameSuit | BuildRule::AnySuit => {
+                        KlondikeError::WrongRank(stack_id)
+                    }
+                },
+                None => KlondikeError::CannotStartColumn(stack_id),
+            },
+            StackType::Stock | StackType::Waste | StackType::Reserve | StackType::Hand => {
+                KlondikeError::CannotDropOn(stack_id)
+            }
+        }
+    }
+
+    pub fn put_hand_on_stack(&mut self, stack_id: StackId) -> Result<usize, KlondikeError> {
+        let origin = self.hand_origin.ok_or(KlondikeError::HandEmpty)?;
+        self.check_drop(stack_id)?;
+        let count = self.get_stack(StackId::Hand).len();
+        let index = self.get_stack(stack_id).len();
+        self.cards
+            .move_cards(StackId::Hand, 0, count, stack_id, index);
+        self.hand_origin = None;
+        self.expose_top_card_of_stack(origin.stack);
+        self.variant.variant().after_move(self);
+        Ok(index)
+    }
+
+    pub fn return_hand(&mut self) -> Result<(), KlondikeError> {
+        let origin = self.hand_origin.ok_or(KlondikeError::HandEmpty)?;
+        let count = self.get_stack(StackId::Hand).len();
+        self.cards
+            .move_cards(StackId::Hand, 0, count, origin.stack, origin.index.get());
+        self.hand_origin = None;
+        Ok(())
+    }
+
+    pub fn stack_can_accept_hand(&self, stack_id: StackId) -> bool {
+        let target = self.get_stack(stack_id);
+        let accepts_drops = target.stack_type != StackType::Foundation
+            || self.variant.variant().foundation_accepts_drops();
+        self.has_stack(stack_id)
+            && accepts_drops
+            && target.can_play(&self.get_stack(StackId::Hand), &self.rules)
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct PackedTable {
+    cards: [u8; PACKED_BYTES],
+    ends: [u8; STACK_COUNT],
+    face_down: [u8; STACK_COUNT],
+    hand_origin: Option<HandOrigin>,
+    stock_cycles: u8,
+    rules: RulesConfig,
+    variant: VariantId,
+}
+
+impl PackedTable {
+    pub fn pack(table: &Table) -> Self {
+        let mut packed = Self {
+            cards: [0; PACKED_BYTES],
+            ends: table.cards.ends,
+            face_down: [0; STACK_COUNT],
+            hand_origin: table.hand_origin,
+            stock_cycles: table.stock_cycles as u8,
+            rules: table.rules,
+            variant: table.variant,
+        };
+        for stack_id in StackId::into_enum_iter() {
+            let cards = table.cards.cards(stack_id);
+            let face_down = cards.iter().take_while(|card| !card.face_up).count();
+            debug_assert!(cards[face_down..].iter().all(|card| card.face_up));
+            packed.face_down[stack_id as usize] = face_down as u8;
+        }
+        let total = table.cards.ends[STACK_COUNT - 1] as usize;
+        for (index, card) in table.cards.cards[..total].iter().enumerate() {
+            packed.set_code(index, card_code(card));
+        }
+        packed
+    }
+
+    pub fn unpack(&self) -> Table {
+        let mut arena = CardArena {
+            cards: [NO_CARD; MAX_CARDS],
+            ends: self.ends,
+            generations: [0; STACK_COUNT],
+            hash: 0,
+        };
+        for stack_id in StackId::into_enum_iter() {
+            let face_down = self.face_down[stack_id as usize] as usize;
+            for (offset, index) in arena.range(stack_id).enumerate() {
+                arena.cards[index] = card_from_code(self.code(index), offset >= face_down);
+            }
+        }
+        arena.rehash();
+        Table {
+            cards: arena,
+            hand_origin: self.hand_origin,
+            stock_cycles: self.stock_cycles as usize,
+            rules: self.rules,
+            variant: self.variant,
+        }
+    }
+
+    pub fn foundation_count(&self) -> usize {
+        let first = StackId::Foundation1 as usize;
+        let last = StackId::Foundation8 as usize;
+        (self.ends[last] - self.ends[first - 1]) as usize
+    }
+
+    pub fn winner(&self) -> bool {
+        self.foundation_count() == 52 * self.variant.variant().deck_count()
+    }
+
+    fn code(&self, index: usize) -> u8 {
+        let bit = index * CARD_BITS;
+        let byte = bit / 8;
+        let low = self.cards[byte] as u16;
+        let high = self.cards.get(byte + 1).copied().unwrap_or(0) as u16;
+        ((low | high << 8) >> (bit % 8)) as u8 & 0x3f
+    }
+
+    fn set_code(&mut self, index: usize, code: u8) {
+        let bit = index * CARD_BITS;
+        let byte = bit / 8;
+        let window = (code as u16) << (bit % 8);
+        self.cards[byte] |= window as u8;
+        if let Some(high) = self.cards.get_mut(byte + 1) {
+            *high |= (window >> 8) as u8;
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum MoveDelta {
+    // Cards that went from the source to the top of the target, and whether
+    // the card they uncovered was turned over.
+    Moved {
+        source: CardLocation,
+        target: StackId,
+        count: usize,
+        flipped: bool,
+    },
+    Dealt(usize),
+    Recycled(usize),
+    Packed(PackedTable),
+    Unchanged,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppliedMove {
+    play: Play,
+    revealed: bool,
+    delta: MoveDelta,
+}
+
+impl AppliedMove {
+    pub fn play(&self) -> Play {
+        self.play
+    }
+
+    // Whether the move turned over the card it uncovered.
+    pub fn revealed(&self) -> bool {
+        self.revealed
+    }
+}
+
+impl fmt::Debug for PackedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        self.unpack().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::card::make_deck;
+    use alloc::vec;
+
+    const TEST_SEED: u64 = 324;
+
+    fn empty_table() -> Table {
+        let mut table = Table::new(TEST_SEED);
+        for stack_id in StackId::into_enum_iter() {
+            table.set_cards(stack_id, &[]);
+        }
+        table
+    }
+
+    #[test]
+    fn test_table_from_layout() {
+        let table = Table::from_layout(
+            VariantId::Klondike,
+            RulesConfig::default(),
+            &[
+                (StackId::Foundation1, "AD 2D"),
+                (StackId::Tableau1, "-KS 7H"),
+                (StackId::Waste, "3C"),
+            ],
+        )
+        .expect("layout");
+        assert_eq!(2, table.foundation_count());
+        assert_eq!(47, table.get_stack(StackId::Stock).len());
+        assert_eq!(
+            Some(CardLocation::new(StackId::Tableau1, CardIndex::new(1))),
+            table.find_card(Rank::Seven, Suit::Heart)
+        );
+        let json = serde_json::to_string(&table).expect("save");
+        assert_eq!(table, serde_json::from_str::<Table>(&json).expect("load"));
+        assert_eq!(
+            Err(KlondikeError::InvalidTable),
+            Table::from_layout(
+                VariantId::Klondike,
+                RulesConfig::default(),
+                &[(StackId::Tableau1, "7H"), (StackId::Tableau2, "7H")],
+            )
+        );
+    }
+
+    #[test]
+    fn test_table_save_round_trip() {
+        let mut table =
+            Table::with_variant(TEST_SEED, VariantId::FortyThieves, RulesConfig::default());
+        table.deal_from_stock().expect("deal");
+        let json = serde_json::to_string(&table).expect("save");
+        assert_eq!(table, serde_json::from_str::<Table>(&json).expect("load"));
+
+        let mut missing = SavedTable::from(table);
+        missing.stacks[0].1.pop();
+        assert_eq!(Err(KlondikeError::InvalidTable), Table::try_from(missing));
+    }
+
+    #[test]
+    fn test_deal_to_waste_turns_cards_in_order() {
+        let mut table = Table::new(TEST_SEED);
+        let stock = table.get_stack(StackId::Stock).cards().to_vec();
+        table.deal_to_waste(3).expect("deal");
+        let dealt: Vec<Card> = stock[stock.len() - 3..]
+            .iter()
+            .rev()
+            .map(|card| Card {
+                face_up: true,
+                ..card.clone()
+            })
+            .collect();
+        assert_eq!(&dealt[..], table.get_stack(StackId::Waste).cards());
+
+        while table.has_cards_in_stock() {
+            table.deal_to_waste(3).expect("deal");
+        }
+        table.deal_to_waste(3).expect("recycle");
+        assert_eq!(&stock[..], table.get_stack(StackId::Stock).cards());
+        assert!(table.get_stack(StackId::Waste).is_empty());
+    }
+
+    #[test]
+    fn test_preview_leaves_table_alone() {
+        let table = Table::new(TEST_SEED);
+        let start = table.clone();
+        for play in table.legal_moves() {
+            let next = table.preview(play).expect("preview");
+            assert_ne!(start, next);
+        }
+        let dealt = table.preview(Play::DrawFromStock).expect("deal");
+        assert_ne!(start, dealt);
+        assert_eq!(start, table);
+
+        let mut played = table.clone();
+        played.deal_from_stock().expect("deal");
+        assert_eq!(played, dealt);
+    }
+
+    #[test]
+    fn test_unapply_moves() {
+        for variant in VariantId::into_enum_iter() {
+            let mut table = Table::with_variant(TEST_SEED, variant, RulesConfig::default());
+            let start = table.clone();
+            let mut applied = Vec::new();
+            for step in 0..60 {
+                let moves = table.legal_moves();
+                let play = if moves.is_empty() || step % 3 == 0 {
+                    Play::DrawFromStock
+                } else {
+                    moves[step % moves.len()]
+                };
+                if let Ok(played) = table.apply_move(play) {
+                    applied.push((table.clone(), played));
+                }
+            }
+            assert!(!applied.is_empty());
+            while let Some((after, played)) = applied.pop() {
+                assert_eq!(after, table);
+                table.unapply(played);
+            }
+            assert_eq!(start, table);
+            assert_eq!(start.position_hash(), table.position_hash());
+        }
+    }
+
+    #[test]
+    fn test_position_hash_follows_moves() {
+        let rehashed = |table: &Table| PackedTable::pack(table).unpack().position_hash();
+        let mut table = Table::new(TEST_SEED);
+        let start = table.position_hash();
+        assert_eq!(start, rehashed(&table));
+        for _ in 0..40 {
+            match table.legal_moves().first() {
+                Some(play) => table = table.preview(*play).expect("play"),
+                None => table.deal_from_stock().expect("deal"),
+            }
+            assert_eq!(rehashed(&table), table.position_hash());
+        }
+        assert_ne!(start, table.position_hash());
+
+        let mut cycled = Table::new(TEST_SEED);
+        while cycled.has_cards_in_stock() {
+            cycled.deal_from_stock().expect("deal");
+        }
+        cycled.deal_from_stock().expect("recycle");
+        assert_eq!(start, cycled.position_hash());
+    }
+
+    #[test]
+    fn test_generations_track_changed_stacks() {
+        let mut table = Table::new(TEST_SEED);
+        let snapshot = table.clone();
+        let tableau = table.generation(StackId::Tableau1);
+        let stock = table.generation(StackId::Stock);
+        table.deal_from_stock().expect("deal");
+        assert_ne!(stock, table.generation(StackId::Stock));
+        assert_ne!(0, table.generation(StackId::Waste));
+        assert_eq!(tableau, table.generation(StackId::Tableau1));
+
+        let dealt = table.generation(StackId::Stock);
+        table.restore(snapshot.clone());
+        assert_eq!(snapshot, table);
+        assert_ne!(dealt, table.generation(StackId::Stock));
+        assert_eq!(tableau, table.generation(StackId::Tableau1));
+    }
+
+    #[test]
+    fn test_packed_table_round_trip() {
+        let mut table =
+            Table::with_variant(TEST_SEED, VariantId::FortyThieves, RulesConfig::default());
+        table.deal_from_stock().expect("deal");
+        table
+            .take_top_card_from_stack(StackId::Waste)
+            .expect("take");
+        let packed = PackedTable::pack(&table);
+        assert_eq!(table, packed.unpack());
+        assert_eq!(table.foundation_count(), packed.foundation_count());
+        assert!(core::mem::size_of::<PackedTable>() < core::mem::size_of::<Table>());
+
+        let mut klondike = Table::new(TEST_SEED);
+        klondike.set_cards(StackId::Tableau7, &[]);
+        assert_eq!(klondike, PackedTable::pack(&klondike).unpack());
+    }
+
+    #[test]
+    fn test_thoughtful_deal_is_open() {
+        let klondike = Table::new(TEST_SEED);
+        let table = Table::with_variant(TEST_SEED, VariantId::Thoughtful, RulesConfig::default());
+        for stack_id in table.tableau_ids() {
+            let stack = table.get_stack(*stack_id);
+            assert_eq!(klondike.get_stack(*stack_id).len(), stack.len());
+            assert!((0..stack.len())
+                .all(|index| stack.get_card(CardIndex::new(index)).expect("card").face_up));
+        }
+        assert_eq!(
+            klondike.get_stack(StackId::Stock).len(),
+            table.get_stack(StackId::Stock).len()
+        );
+    }
+
+    #[test]
+    fn test_westcliff_deal() {
+        let mut table =
+            Table::with_variant(TEST_SEED, VariantId::Westcliff, RulesConfig::default());
+        assert_eq!(10, table.tableau_ids().len());
+        for stack_id in table.tableau_ids() {
+            let stack = table.get_stack(*stack_id);
+            assert_eq!(3, stack.len());
+            assert!(stack.top_card().expect("top card").face_up);
+        }
+        assert_eq!(22, table.get_stack(StackId::Stock).len());
+        assert_eq!(52, table.card_count());
+        assert!(table.rules.any_card_on_empty_tableau);
+
+        table.deal_from_stock().expect("deal");
+        assert_eq!(1, table.get_stack(StackId::Waste).len());
+        while table.has_cards_in_stock() {
+            table.deal_from_stock().expect("deal");
+        }
+        assert_eq!(Err(KlondikeError::NoRedealsLeft), table.deal_from_stock());
+
+        let klondike = Table::new(TEST_SEED);
+        assert!(!klondike.has_stack(StackId::Tableau8));
+        assert_eq!(0, klondike.empty_tableaux());
+    }
+
+    #[test]
+    fn test_easthaven_deals_rows() {
+        let mut table =
+            Table::with_variant(TEST_SEED, VariantId::Easthaven, RulesConfig::default());
+        assert_eq!(31, table.get_stack(StackId::Stock).len());
+        assert!(!table.has_stack(StackId::Waste));
+        for _ in 0..4 {
+            table.deal_from_stock().expect("deal");
+        }
+        assert_eq!(3, table.get_stack(StackId::Stock).len());
+        assert_eq!(7, table.get_stack(StackId::Tableau1).len());
+        table.deal_from_stock().expect("deal");
+        assert_eq!(8, table.get_stack(StackId::Tableau3).len());
+        assert_eq!(7, table.get_stack(StackId::Tableau4).len());
+        assert!(table.get_stack(StackId::Waste).is_empty());
+        assert!(table.stock_is_dead());
+        assert_eq!(Err(KlondikeError::NoCardsToDeal), table.deal_from_stock());
+        assert_eq!(52, table.card_count());
+    }
+
+    #[test]
+    fn test_yukon_moves_any_face_up_group() {
+        let mut table = Table::with_variant(TEST_SEED, VariantId::Yukon, RulesConfig::default());
+        assert!(table.get_stack(StackId::Stock).is_empty());
+        assert_eq!(52, table.card_count());
+        assert_eq!(21, table.face_down_remaining());
+        assert_eq!(11, table.get_stack(StackId::Tableau7).len());
+        assert_eq!(Err(KlondikeError::NoCardsToDeal), table.deal_from_stock());
+
+        let stack = table.get_stack(StackId::Tableau7);
+        let deepest_face_up = stack.len() - 5;
+        assert!(
+            !stack
+                .get_card(CardIndex::new(deepest_face_up - 1))
+                .expect("card")
+                .face_up
+        );
+        assert_eq!(
+            Ok(()),
+            table.check_pick_up(StackId::Tableau7, CardIndex::new(deepest_face_up))
+        );
+        assert_eq!(
+            Err(KlondikeError::NotFaceUp(CardLocation::new(
+                StackId::Tableau7,
+                CardIndex::new(deepest_face_up - 1)
+            ))),
+            table.check_pick_up(StackId::Tableau7, CardIndex::new(deepest_face_up - 1))
+        );
+        table
+            .take_selected_cards_from_stack(StackId::Tableau7, CardIndex::new(deepest_face_up))
+            .expect("take");
+        assert_eq!(5, table.get_stack(StackId::Hand).len());
+    }
+
+    #[test]
+    fn test_agnes_deals_reserve_rows() {
+        let mut table = Table::with_variant(TEST_SEED, VariantId::Agnes, RulesConfig::default());
+        assert_eq!(7, table.reserve_ids().len());
+        assert!(!table.has_stack(StackId::Waste));
+        assert!(table.has_stack(StackId::Reserve7));
+        assert_eq!(1, table.foundation_count());
+        assert_eq!(16, table.get_stack(StackId::Stock).len());
+        assert!(table
+            .reserve_ids()
+            .iter()
+            .all(|reserve| table.get_stack(*reserve).len() == 1));
+
+        table.deal_from_stock().expect("deal");
+        table.deal_from_stock().expect("deal");
+        table.deal_from_stock().expect("deal");
+        assert_eq!(0, table.get_stack(StackId::Stock).len());
+        assert_eq!(4, table.get_stack(StackId::Reserve1).len());
+        assert_eq!(3, table.get_stack(StackId::Reserve7).len());
+        assert_eq!(Err(KlondikeError::NoCardsToDeal), table.deal_from_stock());
+        assert_eq!(52, table.card_count());
+
+        let empty = Stack::new(StackId::Tableau1, &[]);
+        let top_rank = Card {
+            suit: Suit::Club,
+            rank: table.rules.foundation_base.previous_wrapping(),
+            face_up: true,
+        };
+        assert!(empty.tableau_can_accept_card(&top_rank, &table.rules));
+        assert_eq!(
+            Err(KlondikeError::CannotDropOn(StackId::Reserve1)),
+            table.check_drop(StackId::Reserve1)
+        );
+    }
+
+    #[test]
+    fn test_drop_errors() {
+        let mut table = empty_table();
+        let cards = |notation: &[&str]| -> Vec<Card> {
+            notation
+                .iter()
+                .map(|text| Card::from_notation(text).expect("card"))
+                .collect()
+        };
+        table.set_cards(StackId::Tableau1, &cards(&["8H"]));
+        table.set_cards(StackId::Foundation1, &cards(&["AS", "2S"]));
+        let mut drop = |hand: &[&str], target: StackId| {
+            table.set_cards(StackId::Tableau2, &cards(hand));
+            table
+                .take_selected_cards_from_stack(StackId::Tableau2, CardIndex::BOTTOM)
+                .expect("pick up");
+            let result = table.check_drop(target);
+            table.return_hand().expect("return");
+            result
+        };
+
+        assert_eq!(Ok(()), drop(&["7S"], StackId::Tableau1));
+        assert_eq!(
+            Err(KlondikeError::WrongColor(StackId::Tableau1)),
+            drop(&["7D"], StackId::Tableau1)
+        );
+        assert_eq!(
+            Err(KlondikeError::WrongRank(StackId::Tableau1)),
+            drop(&["6S"], StackId::Tableau1)
+        );
+        assert_eq!(
+            Err(KlondikeError::CannotStartColumn(StackId::Tableau3)),
+            drop(&["QS"], StackId::Tableau3)
+        );
+        assert_eq!(
+            Err(KlondikeError::FoundationSuitMismatch(StackId::Foundation1)),
+            drop(&["3H"], StackId::Foundation1)
+        );
+        assert_eq!(
+            Err(KlondikeError::WrongRank(StackId::Foundation1)),
+            drop(&["4S"], StackId::Foundation1)
+        );
+        assert_eq!(
+            Err(KlondikeError::TooManyCards(StackId::Foundation1)),
+            drop(&["3S", "2H"], StackId::Foundation1)
+        );
+        assert_eq!(
+            Err(KlondikeError::FoundationSuitMismatch(StackId::Foundation2)),
+            drop(&["AH"], StackId::Foundation2)
+        );
+    }
+
+    #[test]
+    fn test_forty_thieves_two_decks() {
+        let mut table =
+            Table::with_variant(TEST_SEED, VariantId::FortyThieves, RulesConfig::default());
+        assert_eq!(104, table.deck_size());
+        assert_eq!(104, table.card_count());
+        assert_eq!(64, table.get_stack(StackId::Stock).len());
+        assert_eq!(8, table.foundation_ids().len());
+        assert!(table.has_stack(StackId::Foundation8));
+        assert!(!Table::new(TEST_SEED).has_stack(StackId::Foundation5));
+
+        let column = table.get_stack(StackId::Tableau1);
+        assert!(column.cards().iter().all(|card| card.face_up));
+        assert_eq!(
+            Err(KlondikeError::NotTopCard(CardLocation::new(
+                StackId::Tableau1,
+                CardIndex::new(2)
+            ))),
+            table.check_pick_up(StackId::Tableau1, CardIndex::new(2))
+        );
+        assert_eq!(
+            Ok(()),
+            table.check_pick_up(StackId::Tableau1, CardIndex::new(3))
+        );
+
+        let seven = Card {
+            suit: Suit::Heart,
+            rank: Rank::Seven,
+            face_up: true,
+        };
+        let red_six = Card {
+            suit: Suit::Diamond,
+            rank: Rank::Six,
+            face_up: true,
+        };
+        let six = Card {
+            suit: Suit::Heart,
+            rank: Rank::Six,
+            face_up: true,
+        };
+        table.set_cards(StackId::Tableau1, &[seven]);
+        assert!(!table
+            .get_stack(StackId::Tableau1)
+            .tableau_can_accept_card(&red_six, &table.rules));
+        assert!(table
+            .get_stack(StackId::Tableau1)
+            .tableau_can_accept_card(&six, &table.rules));
+    }
+
+    #[test]
+    fn test_spiderette_clears_completed_runs() {
+        let mut table =
+            Table::with_variant(TEST_SEED, VariantId::Spiderette, RulesConfig::default());
+        assert_eq!(24, table.get_stack(StackId::Stock).len());
+        assert!(!table.has_stack(StackId::Waste));
+
+        let mut run: Vec<Card> = Rank::into_enum_iter()
+            .map(|rank| Card {
+                suit: Suit::Spade,
+                rank,
+                face_up: true,
+            })
+            .collect();
+        run.reverse();
+        let ace = run.pop().expect("ace");
+        let mut mixed = run.clone();
+        mixed[11].suit = Suit::Heart;
+        table.set_cards(StackId::Tableau1, &mixed);
+        assert!(!table
+            .variant
+            .variant()
+            .is_movable_group(&table.get_stack(StackId::Tableau1), CardIndex::new(10)));
+        assert!(table
+            .variant
+            .variant()
+            .is_movable_group(&table.get_stack(StackId::Tableau1), CardIndex::new(11)));
+
+        table.set_cards(StackId::Tableau1, &run);
+        table.set_cards(StackId::Tableau2, &[ace]);
+        table
+            .take_top_card_from_stack(StackId::Tableau2)
+            .expect("take ace");
+        assert!(!table.stack_can_accept_hand(StackId::Foundation1));
+        table
+            .put_hand_on_stack(StackId::Tableau1)
+            .expect("drop ace");
+        assert!(table.get_stack(StackId::Tableau1).is_empty());
+        assert_eq!(13, table.get_stack(StackId::Foundation1).len());
+        assert_eq!(
+            Some(Rank::King),
+            table
+                .get_stack(StackId::Foundation1)
+                .top_card()
+                .map(|card| card.rank)
+        );
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        let mut table = empty_table();
+        table.set_cards(
+            StackId::Tableau1,
+            &[Card {
+                suit: Suit::Heart,
+                rank: Rank::Ace,
+                face_up: true,
+            }],
+        );
+        table.set_cards(
+            StackId::Tableau2,
+            &[Card {
+                suit: Suit::Club,
+                rank: Rank::Three,
+                face_up: true,
+            }],
+        );
+        let moves = table.legal_moves();
+        assert!(moves.contains(&Play::MoveCards(
+            CardLocation::new(StackId::Tableau1, CardIndex::new(0)),
+            StackId::Foundation3
+        )));
+        assert!(moves.iter().all(|play| matches!(
+            play,
+            Play::MoveCards(source, _) if source.stack == StackId::Tableau1
+        )));
+    }
+
+    #[test]
+    fn test_hint_prefers_foundation_moves() {
+        let mut table = empty_table();
+        table.set_cards(
+            StackId::Tableau1,
+            &[
+                Card {
+                    suit: Suit::Club,
+                    rank: Rank::Five,
+                    face_up: false,
+                },
+                Card {
+                    suit: Suit::Heart,
+                    rank: Rank::Ace,
+                    face_up: true,
+                },
+            ],
+        );
+        table.set_cards(
+            StackId::Tableau2,
+            &[Card {
+                suit: Suit::Spade,
+                rank: Rank::King,
+                face_up: true,
+            }],
+        );
+        assert_eq!(
+            Some(Play::MoveCards(
+                CardLocation::new(StackId::Tableau1, CardIndex::new(1)),
+                StackId::Foundation3
+            )),
+            table.hint()
+        );
+
+        let mut five = table.get_stack(StackId::Tableau1).cards()[0].clone();
+        five.face_up = true;
+        table.set_cards(StackId::Tableau1, &[five]);
+        assert_eq!(None, table.hint());
+
+        table.set_cards(StackId::Stock, &make_deck(TEST_SEED)[50..]);
+        assert_eq!(Some(Play::DrawFromStock), table.hint());
+    }
+
+    #[test]
+    fn test_canfield_deal_and_wrapping() {
+        let table = Table::with_variant(TEST_SEED, VariantId::Canfield, RulesConfig::default());
+        assert_eq!(13, table.get_stack(StackId::Reserve1).len());
+        assert!(
+            table
+                .get_stack(StackId::Reserve1)
+                .top_card()
+                .expect("top card")
+                .face_up
+        );
+        assert_eq!(1, table.foundation_count());
+        assert_eq!(34, table.get_stack(StackId::Stock).len());
+        assert_eq!(52, table.card_count());
+        let base = table.rules.foundation_base;
+        let base_card = table
+            .foundation_ids()
+            .iter()
+            .find_map(|foundation| table.get_stack(*foundation).top_card())
+            .expect("base card");
+        assert_eq!(base, base_card.rank);
+
+        let king = Card {
+            suit: Suit::Spade,
+            rank: Rank::King,
+            face_up: true,
+        };
+        let ace = Card {
+            suit: Suit::Heart,
+            rank: Rank::Ace,
+            face_up: true,
+        };
+        assert!(king.is_one_below_wrapping(&ace, true));
+        assert!(!king.is_one_below_wrapping(&ace, false));
+        let cards = [ace.clone()];
+        let tableau = Stack::new(StackId::Tableau1, &cards);
+        assert!(tableau.tableau_can_accept_card(&king, &table.rules));
+        let cards = [ace, king];
+        let tableau = Stack::new(StackId::Tableau1, &cards);
+        assert!(tableau.is_wrapping_run_from(CardIndex::new(0), true));
+        assert!(!tableau.is_run_from(CardIndex::new(0)));
+    }
+
+    #[test]
+    fn test_relaxed_waste_pick_up() {
+        let mut table = empty_table();
+        table.set_cards(StackId::Waste, &make_deck(TEST_SEED)[47..]);
+        table.update_cards(StackId::Waste, |cards| {
+            for card in cards {
+                card.face_up = true;
+            }
+        });
+        assert_eq!(
+            Err(KlondikeError::NotTopCard(CardLocation::new(
+                StackId::Waste,
+                CardIndex::new(3)
+            ))),
+            table.check_pick_up(StackId::Waste, CardIndex::new(3))
+        );
+
+        table.rules.strict_waste_top = false;
+        let waste_sources: Vec<CardLocation> = ActiveCardIterator::new(&table)
+            .filter(|source| source.stack == StackId::Waste)
+            .collect();
+        assert_eq!(
+            vec![
+                CardLocation::new(StackId::Waste, CardIndex::new(2)),
+                CardLocation::new(StackId::Waste, CardIndex::new(3)),
+                CardLocation::new(StackId::Waste, CardIndex::new(4))
+            ],
+            waste_sources
+        );
+        assert_eq!(
+            Err(KlondikeError::NotTopCard(CardLocation::new(
+                StackId::Waste,
+                CardIndex::new(1)
+            ))),
+            table.check_pick_up(StackId::Waste, CardIndex::new(1))
+        );
+
+        let before = table.get_stack(StackId::Waste).cards().to_vec();
+        table
+            .take_selected_cards_from_stack(StackId::Waste, CardIndex::new(3))
+            .expect("take");
+        assert_eq!(1, table.get_stack(StackId::Hand).len());
+        assert_eq!(4, table.get_stack(StackId::Waste).len());
+        table.return_hand().expect("return");
+        assert!(before == table.get_stack(StackId::Waste).cards());
+    }
+
+    #[test]
+    fn test_navigation_on_empty_table() {
+        let table = empty_table();
+        for stack_id in StackId::into_enum_iter() {
+            let source = CardLocation::new(stack_id, CardIndex::new(0));
+            assert_eq!(None, table.next_active_card(source));
+            assert_eq!(None, table.previous_active_card(source));
+        }
+    }
+
+    #[test]
+    fn test_navigation_with_only_stock() {
+        let mut table = empty_table();
+        table.set_cards(StackId::Stock, &make_deck(TEST_SEED));
+        assert_eq!(None, table.next_active_card(CardLocation::stock()));
+        assert_eq!(
+            Some(CardLocation::new(StackId::Stock, CardIndex::new(51))),
+            table.previous_active_card(CardLocation::stock())
+        );
+    }
+
+    #[test]
+    fn test_navigation_wraps_to_single_card() {
+        let mut table = empty_table();
+        table.set_cards(StackId::Tableau4, &make_deck(TEST_SEED)[51..]);
+        table.expose_top_card_of_stack(StackId::Tableau4);
+        let only_card = CardLocation::new(StackId::Tableau4, CardIndex::new(0));
+        assert_eq!(Some(only_card), table.next_active_card(only_card));
+        assert_eq!(Some(only_card), table.previous_active_card(only_card));
+    }
+}
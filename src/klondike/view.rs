@@ -0,0 +1,56 @@
+use super::{RulesConfig, Stack, StackId, Table};
+
+// A read-only borrow of the table. Drawing code gets one of these instead of
+// the table so it has no way to change the game.
+#[derive(Clone, Copy)]
+pub struct TableView<'a> {
+    table: &'a Table,
+}
+
+impl<'a> TableView<'a> {
+    pub fn get_stack(self, stack_id: StackId) -> Stack<'a> {
+        self.table.get_stack(stack_id)
+    }
+
+    pub fn has_stack(self, stack_id: StackId) -> bool {
+        self.table.has_stack(stack_id)
+    }
+
+    pub fn rules(self) -> &'a RulesConfig {
+        &self.table.rules
+    }
+
+    pub fn cards_in_hand(self) -> bool {
+        self.table.cards_in_hand()
+    }
+
+    pub fn reserve_ids(self) -> &'static [StackId] {
+        self.table.reserve_ids()
+    }
+
+    pub fn foundation_ids(self) -> &'static [StackId] {
+        self.table.foundation_ids()
+    }
+
+    pub fn tableau_ids(self) -> &'static [StackId] {
+        self.table.tableau_ids()
+    }
+
+    pub fn stock_cycles_used(self) -> usize {
+        self.table.stock_cycles_used()
+    }
+
+    pub fn redeals_remaining(self) -> Option<usize> {
+        self.table.redeals_remaining()
+    }
+
+    pub fn stock_is_dead(self) -> bool {
+        self.table.stock_is_dead()
+    }
+}
+
+impl Table {
+    pub fn view(&self) -> TableView<'_> {
+        TableView { table: self }
+    }
+}
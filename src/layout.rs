@@ -0,0 +1,381 @@
+use crate::{
+    klondike::{CardIndex, Play, StackId, Table, WASTE_VISIBLE},
+    options::Preferences,
+    FanDirection, StackDrawMode, StackView, CARD_HEIGHT, CARD_WIDTH, GUTTER, MARGIN, SCREEN_WIDTH,
+};
+use core::iter;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize, ScreenVector},
+    graphics::LCD_ROWS,
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+pub const HUD_HEIGHT: i32 = 16;
+const TILT_DEAD_ZONE: f32 = 0.1;
+const TILT_PAN_SPEED: f32 = 24.0;
+
+pub fn hud_top() -> i32 {
+    LCD_ROWS as i32 - HUD_HEIGHT
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CardMetrics {
+    pub width: i32,
+    pub height: i32,
+    pub fan: i32,
+}
+
+impl CardMetrics {
+    pub fn size(&self) -> ScreenSize {
+        ScreenSize::new(self.width, self.height)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.width == CARD_WIDTH && self.height == CARD_HEIGHT
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.width as f32 / CARD_WIDTH as f32
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CardScale {
+    Small,
+    Medium,
+    Large,
+}
+
+impl CardScale {
+    pub fn metrics(self) -> CardMetrics {
+        match self {
+            CardScale::Small => CardMetrics {
+                width: 40,
+                height: 56,
+                fan: 8,
+            },
+            CardScale::Medium => CardMetrics {
+                width: CARD_WIDTH,
+                height: CARD_HEIGHT,
+                fan: MARGIN,
+            },
+            CardScale::Large => CardMetrics {
+                width: 56,
+                height: 78,
+                fan: 12,
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CardScale::Small => "Small",
+            CardScale::Medium => "Medium",
+            CardScale::Large => "Large",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            CardScale::Small => CardScale::Medium,
+            CardScale::Medium => CardScale::Large,
+            CardScale::Large => CardScale::Small,
+        }
+    }
+}
+
+pub fn playfield_bottom(show_hud: bool) -> i32 {
+    if show_hud {
+        hud_top()
+    } else {
+        LCD_ROWS as i32
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    top: i32,
+    focus: Option<ScreenRect>,
+}
+
+impl Viewport {
+    pub fn follow(&mut self, focus: ScreenRect, bottom: i32) {
+        if self.focus == Some(focus) {
+            return;
+        }
+        self.focus = Some(focus);
+        if focus.max_y() + MARGIN - self.top > bottom {
+            self.top = focus.max_y() + MARGIN - bottom;
+        }
+        if focus.min_y() - MARGIN < self.top {
+            self.top = (focus.min_y() - MARGIN).max(0);
+        }
+    }
+
+    pub fn pan(&mut self, delta: i32, content_bottom: i32, bottom: i32) {
+        self.top = (self.top + delta).min(content_bottom - bottom).max(0);
+    }
+
+    pub fn offset(&self) -> ScreenVector {
+        ScreenVector::new(0, -self.top)
+    }
+}
+
+pub fn tilt_pan_delta(tilt: f32, neutral: f32) -> i32 {
+    let lean = tilt - neutral;
+    if lean > TILT_DEAD_ZONE {
+        ((lean - TILT_DEAD_ZONE) * TILT_PAN_SPEED) as i32
+    } else if lean < -TILT_DEAD_ZONE {
+        ((lean + TILT_DEAD_ZONE) * TILT_PAN_SPEED) as i32
+    } else {
+        0
+    }
+}
+
+pub fn content_bottom(views: &HashMap<StackId, StackView>, table: &Table) -> i32 {
+    views
+        .values()
+        .filter(|view| view.stack_id != StackId::Hand)
+        .map(|view| {
+            view.run_rect(&table.get_stack(view.stack_id), CardIndex::BOTTOM)
+                .max_y()
+                + MARGIN
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn hud_rect() -> ScreenRect {
+    ScreenRect::new(
+        ScreenPoint::new(0, hud_top()),
+        ScreenSize::new(SCREEN_WIDTH, HUD_HEIGHT),
+    )
+}
+
+fn card_center(
+    views: &HashMap<StackId, StackView>,
+    stack_id: StackId,
+    index: CardIndex,
+) -> Option<ScreenPoint> {
+    views
+        .get(&stack_id)
+        .map(|view| view.card_rect(index).center())
+}
+
+pub fn hint_endpoints(
+    views: &HashMap<StackId, StackView>,
+    table: &Table,
+    play: Play,
+) -> Option<(ScreenPoint, ScreenPoint)> {
+    match play {
+        Play::MoveCards(source, target) => {
+            let from = card_center(views, source.stack, source.index)?;
+            let target_index = CardIndex::new(table.get_stack(target).len());
+            let to = card_center(views, target, target_index)?;
+            Some((from, to))
+        }
+        Play::DrawFromStock | Play::RecycleWaste => {
+            let stock_view = views.get(&StackId::Stock)?;
+            let to = stock_view.card_rect(CardIndex::BOTTOM).center();
+            Some((to + ScreenVector::new(0, stock_view.metrics.height), to))
+        }
+        Play::Setup => None,
+    }
+}
+
+pub fn build_views(preferences: &Preferences, table: &Table) -> HashMap<StackId, StackView> {
+    let metrics = preferences.card_scale.metrics();
+    let card_width = metrics.width;
+    let card_height = metrics.height;
+    let tableau_ids = table.tableau_ids();
+    let foundation_ids = table.foundation_ids();
+    let suit_count = 4;
+    let foundations_left =
+        SCREEN_WIDTH - suit_count * card_width - (suit_count - 1) * GUTTER - MARGIN;
+    let mut position = ScreenPoint::new(foundations_left, MARGIN);
+    let foundation_spacing = match foundation_ids.len() {
+        0 | 1 => card_width + GUTTER,
+        count => ((SCREEN_WIDTH - MARGIN - card_width - foundations_left) / (count as i32 - 1))
+            .min(card_width + GUTTER),
+    };
+
+    let foundations = foundation_ids.iter().map(|foundation| {
+        let stack = StackView {
+            stack_id: *foundation,
+            position,
+            metrics,
+            mode: StackDrawMode::Squared,
+        };
+        position.x += foundation_spacing;
+        stack
+    });
+
+    // Without a waste, reserves get rows of their own under the stock, as
+    // many across as fit without overlapping.
+    let reserve_ids = table.reserve_ids();
+    let reserve_rows = !table.has_stack(StackId::Waste) && !reserve_ids.is_empty();
+    let reserves_top = MARGIN + card_height + GUTTER;
+    let reserves_across = ((SCREEN_WIDTH - MARGIN * 2 + GUTTER) / (card_width + GUTTER)).max(1);
+    let reserve_row_count = if reserve_rows {
+        (reserve_ids.len() as i32 - 1) / reserves_across + 1
+    } else {
+        0
+    };
+    let tableau_top = reserves_top + reserve_row_count * (card_height + GUTTER);
+    let tableau_visible = if preferences.show_hud && !preferences.scroll_tableaux {
+        ((hud_top() - tableau_top - card_height) / metrics.fan + 1) as usize
+    } else {
+        52
+    };
+    let mut position = ScreenPoint::new(MARGIN, tableau_top);
+    let mut stack_count = 1;
+    let tableau_spacing = match tableau_ids.len() {
+        0 | 1 => card_width + GUTTER,
+        count => {
+            ((SCREEN_WIDTH - MARGIN * 2 - card_width) / (count as i32 - 1)).min(card_width + GUTTER)
+        }
+    };
+    let tableaux = tableau_ids.iter().map(|tableau| {
+        let stack = StackView {
+            stack_id: *tableau,
+            position,
+            metrics,
+            mode: StackDrawMode::Fanned(FanDirection::Down, tableau_visible),
+        };
+        stack_count += 1;
+        position.x += tableau_spacing;
+        stack
+    });
+
+    let stock = StackView {
+        stack_id: StackId::Stock,
+        position: ScreenPoint::new(MARGIN, MARGIN),
+        metrics,
+        mode: StackDrawMode::Squared,
+    };
+    let waste = StackView {
+        stack_id: StackId::Waste,
+        position: ScreenPoint::new(MARGIN + GUTTER + card_width, MARGIN),
+        metrics,
+        mode: StackDrawMode::Fanned(FanDirection::Right, WASTE_VISIBLE),
+    };
+    let reserves = reserve_ids.iter().enumerate().map(move |(index, reserve)| {
+        let index = index as i32;
+        let position = if reserve_rows {
+            ScreenPoint::new(
+                MARGIN + (index % reserves_across) * (card_width + GUTTER),
+                reserves_top + (index / reserves_across) * (card_height + GUTTER),
+            )
+        } else {
+            ScreenPoint::new(
+                SCREEN_WIDTH - MARGIN - card_width + index * (card_width + GUTTER),
+                tableau_top,
+            )
+        };
+        StackView {
+            stack_id: *reserve,
+            position,
+            metrics,
+            mode: StackDrawMode::Squared,
+        }
+    });
+    let in_hand = StackView {
+        stack_id: StackId::Hand,
+        position: ScreenPoint::zero(),
+        metrics,
+        mode: StackDrawMode::Squared,
+    };
+
+    foundations
+        .chain(tableaux)
+        .chain(iter::once(stock))
+        .chain(iter::once(waste).chain(iter::once(in_hand)))
+        .chain(reserves)
+        .filter(|stack_view| table.has_stack(stack_view.stack_id))
+        .map(|stack_view| (stack_view.stack_id, stack_view))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::{RulesConfig, VariantId};
+    use alloc::vec::Vec;
+
+    fn card_rect(y: i32) -> ScreenRect {
+        ScreenRect::new(
+            ScreenPoint::new(MARGIN, y),
+            ScreenSize::new(CARD_WIDTH, CARD_HEIGHT),
+        )
+    }
+
+    #[test]
+    fn test_viewport_follows_focus() {
+        let mut viewport = Viewport::default();
+        viewport.follow(card_rect(100), 240);
+        assert_eq!(ScreenVector::zero(), viewport.offset());
+        viewport.follow(card_rect(300), 240);
+        assert_eq!(
+            ScreenVector::new(0, -(300 + CARD_HEIGHT + MARGIN - 240)),
+            viewport.offset()
+        );
+        viewport.follow(card_rect(MARGIN), 240);
+        assert_eq!(ScreenVector::zero(), viewport.offset());
+    }
+
+    #[test]
+    fn test_card_scales_fit_the_screen() {
+        let table = Table::new(322);
+        for card_scale in &[CardScale::Small, CardScale::Medium, CardScale::Large] {
+            let preferences = Preferences {
+                card_scale: *card_scale,
+                ..Preferences::default()
+            };
+            let views = build_views(&preferences, &table);
+            for view in views.values().filter(|view| view.stack_id != StackId::Hand) {
+                assert_eq!(card_scale.metrics(), view.metrics);
+                assert!(view.card_rect(CardIndex::BOTTOM).max_x() <= SCREEN_WIDTH);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserves_do_not_overlap() {
+        let table = Table::with_variant(322, VariantId::Agnes, RulesConfig::default());
+        for card_scale in &[CardScale::Small, CardScale::Medium, CardScale::Large] {
+            let preferences = Preferences {
+                card_scale: *card_scale,
+                ..Preferences::default()
+            };
+            let views = build_views(&preferences, &table);
+            let rects: Vec<ScreenRect> = table
+                .reserve_ids()
+                .iter()
+                .map(|reserve| views[reserve].card_rect(CardIndex::BOTTOM))
+                .collect();
+            assert_eq!(7, rects.len());
+            for (index, rect) in rects.iter().enumerate() {
+                assert!(rect.max_x() <= SCREEN_WIDTH);
+                assert!(rects[index + 1..]
+                    .iter()
+                    .all(|other| !rect.intersects(other)));
+                for tableau in table.tableau_ids() {
+                    assert!(!rect.intersects(&views[tableau].card_rect(CardIndex::BOTTOM)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tilt_pans_within_content() {
+        let mut viewport = Viewport::default();
+        assert_eq!(0, tilt_pan_delta(0.55, 0.5));
+        let delta = tilt_pan_delta(1.0, 0.5);
+        assert!(delta > 0);
+        viewport.pan(delta, 260, 240);
+        assert_eq!(ScreenVector::new(0, -delta.min(20)), viewport.offset());
+        viewport.pan(tilt_pan_delta(-1.0, 0.5) * 10, 260, 240);
+        assert_eq!(ScreenVector::zero(), viewport.offset());
+    }
+}
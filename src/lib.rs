@@ -3,16 +3,98 @@
 
 extern crate alloc;
 
+mod animation;
+mod attract;
+mod audio;
+mod autosave;
+mod bookmarks;
+mod calendar;
+mod card_back;
+mod cursor;
+mod effects;
+mod export;
+mod highlight;
+mod history;
+mod hud;
+mod input;
 #[allow(dead_code)]
 mod klondike;
+mod layout;
+mod move_cache;
+mod options;
+mod panel;
+mod par;
+mod phase;
+mod puzzles;
+mod qr;
+mod race;
+// Waiting on a crankstart binding for the scoreboards API; nothing posts
+// or reads scores until there is one.
+#[cfg(feature = "scoreboards")]
+#[allow(dead_code)]
+mod scoreboard;
+mod scoring;
+mod selection;
+mod serial;
+mod session;
+mod settings;
+mod share_view;
+#[cfg(any(test, feature = "simulator"))]
+mod simulator;
+mod stats;
+mod storage;
+mod suit_pattern;
+mod summary;
+mod telemetry;
+mod thumbnail;
+mod undo;
+mod widgets;
+mod wildcard;
 
-use crate::klondike::*;
-use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
+use crate::{
+    animation::{AnimationEvent, HintArrow, RecycleAnimation, WinWaterfall},
+    attract::AttractMode,
+    audio::{Audio, Cue, PendingCues},
+    autosave::{Journal, JournalEntry, SavedGame, SlotPicker, Snapshot},
+    bookmarks::BookmarkBrowser,
+    calendar::{local_day, CalendarView},
+    card_back::{CardBack, CardBackPicker},
+    cursor::Cursor,
+    effects::{Effects, TableEvent},
+    export::{HISTORY_EXPORT_PATH, STATS_EXPORT_PATH},
+    highlight::{draw_ghost, draw_marquee, draw_playable_tabs, draw_pulse},
+    history::{History, HistoryBrowser, HISTORY_PATH},
+    hud::{draw_hud, draw_redeals_badge},
+    input::{Command, InputMapper, RawInput},
+    klondike::*,
+    layout::{CardMetrics, Viewport},
+    move_cache::LegalMoveCache,
+    options::{OptionsAction, OptionsMenu, Preferences},
+    panel::draw_panel,
+    par::{move_budget, par_for, Grade},
+    phase::{GamePhase, PhaseMachine},
+    puzzles::{puzzle, PUZZLES},
+    race::{Race, TurnResult},
+    scoring::ScoringMode,
+    selection::Selection,
+    serial::SerialCommand,
+    session::{format_time, Session},
+    settings::{Settings, SettingsChange},
+    share_view::ShareCodeView,
+    stats::{GameMode, GameResult, PersonalBests, Stats, STATS_PATH},
+    suit_pattern::draw_suit_marker,
+    summary::{GameSummary, Verdict},
+    telemetry::{FileTelemetry, NoTelemetry, Telemetry},
+    thumbnail::Thumbnail,
+    undo::{UndoError, UndoHistory, UndoLimit},
+    widgets::{CrankEntry, EntryEvent, CODE_CHARACTERS, DIGITS, NAME_CHARACTERS},
+};
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec, vec::Vec};
 use anyhow::Error;
 use core::{iter, mem};
 use crankstart::{
     crankstart_game,
-    geometry::{ScreenPoint, ScreenVector},
+    geometry::{ScreenPoint, ScreenRect, ScreenSize, ScreenVector},
     graphics::{
         Bitmap, BitmapTable, Font, Graphics, LCDBitmapDrawMode, LCDBitmapFlip, LCDColor, LCDRect,
         LCDSolidColor, LCD_COLUMNS, LCD_ROWS,
@@ -21,29 +103,12 @@ use crankstart::{
     system::{PDButtons, System},
     Game, Playdate,
 };
+use crankstart_sys::PDPeripherals;
 use enum_iterator::IntoEnumIterator;
 use euclid::{vec2, Point2D, Vector2D};
 use hashbrown::HashMap;
 use rand::{prelude::*, seq::SliceRandom, SeedableRng};
 
-const WINABLE_SEEDS: &[u64] = &[
-    322, 331, 341, 1004, 1006, 1013, 1016, 1018, 1021, 1023, 1026, 1032, 1038, 1040, 1041, 1042,
-    1044, 1055, 1056, 1058, 1061, 1064, 1079, 1082, 1088, 1093, 1095, 1104, 1113, 1118, 1119, 1120,
-    1125, 1132, 1138, 1145, 1146, 1165, 1172, 1176, 1177, 1178, 1180, 1181, 1191, 1193, 1195, 1203,
-    1207, 1208, 1211, 1215, 1219, 1222, 1225, 1227, 1229, 1231, 1239, 1240, 1244, 1245, 1247, 1248,
-    1249, 1252, 1256, 1265, 1272, 1273, 1274, 1275, 1277, 1278, 1291, 1293, 1295, 1306, 1307, 1308,
-    1312, 1318, 1320, 1329, 1330, 1336, 1341, 1354, 1357, 1360, 1362, 1366, 1367, 1369, 1373, 1378,
-    1379, 1380, 1382, 1385, 1386, 1397, 1409, 1415, 1418, 1428, 1434, 1435, 1441, 1447, 1448, 1451,
-    1455, 1458, 1460, 1463, 1466, 1476, 1477, 1478, 1481, 1497, 1499, 1512, 1515, 1518, 1520, 1527,
-    1532, 1536, 1541, 1542, 1545, 1556, 1557, 1561, 1562, 1573, 1581, 1585, 1592, 1599, 1600, 1602,
-    1616, 1621, 1622, 1623, 1624, 1625, 1627, 1628, 1631, 1632, 1639, 1642, 1653, 1657, 1659, 1660,
-    1668, 1678, 1679, 1682, 1683, 1684, 1694, 1712, 1714, 1731, 1748, 1750, 1753, 1754, 1758, 1762,
-    1764, 1777, 1778, 1791, 1808, 1812, 1813, 1816, 1825, 1846, 1851, 1860, 1864, 1866, 1867, 1869,
-    1872, 1876, 1882, 1884, 1886, 1889, 1891, 1893, 1896, 1901, 1902, 1904, 1906, 1916, 1920, 1921,
-    1922, 1927, 1929, 1934, 1935, 1943, 1944, 1946, 1954, 1955, 1956, 1959, 1968, 1972, 1978, 1987,
-    1990, 1993,
-];
-
 const SCREEN_CLIP: LCDRect = LCDRect {
     left: 0,
     right: LCD_COLUMNS as i32,
@@ -57,11 +122,14 @@ const MARGIN: i32 = 10;
 //const INDEX_MARGIN_X: i32 = 4;
 //const INDEX_MARGIN_Y: i32 = 1;
 const GUTTER: i32 = 5;
+const AUTO_COMPLETE_STEP_MS: usize = 150;
+const ATTRACT_IDLE_MS: usize = 30_000;
+const SEED_DIGITS: usize = 20;
+const SHARE_CODE_LIMIT: usize = 400;
+const SLOT_NAME_LENGTH: usize = 12;
 const CARD_WIDTH: i32 = 50;
 const CARD_HEIGHT: i32 = 70;
 
-const CRANK_THRESHHOLD: i32 = 10;
-
 #[derive(Debug)]
 enum FanDirection {
     Down,
@@ -79,40 +147,82 @@ struct StackView {
     stack_id: StackId,
     position: ScreenPoint,
     mode: StackDrawMode,
+    metrics: CardMetrics,
 }
 
 impl StackView {
-    pub fn get_card_position(&self, index: usize) -> ScreenPoint {
+    pub fn get_card_position(&self, index: CardIndex) -> ScreenPoint {
+        let fan = self.metrics.fan;
         let (vector, count) = match &self.mode {
             StackDrawMode::Squared => (ScreenVector::zero(), 0),
             StackDrawMode::Fanned(direction, visible) => match direction {
-                FanDirection::Down => (ScreenVector::new(0, MARGIN), *visible),
-                FanDirection::Right => (ScreenVector::new(MARGIN, 0), *visible),
+                FanDirection::Down => (ScreenVector::new(0, fan), *visible),
+                FanDirection::Right => (ScreenVector::new(fan, 0), *visible),
             },
         };
-        let number = index.min(count.saturating_sub(1));
+        let number = index.get().min(count.saturating_sub(1));
         self.position + vector * number as i32
     }
 
     #[allow(unused)]
     pub fn get_top_card_position(&self, stack: &Stack) -> ScreenPoint {
-        let index = if stack.is_empty() { 0 } else { stack.len() - 1 };
-        self.get_card_position(index)
+        self.get_card_position(stack.top_card_index())
     }
 
-    fn draw_empty(&self, resources: &Resources) -> Result<(), Error> {
-        resources.empty.draw(
-            None,
-            None,
-            self.position,
-            LCDBitmapDrawMode::kDrawModeCopy,
-            LCDBitmapFlip::kBitmapUnflipped,
-            SCREEN_CLIP,
-        )?;
+    pub fn run_rect(&self, stack: &Stack, index: CardIndex) -> ScreenRect {
+        let top_index = stack.top_card_index().max(index);
+        let mut start = self.get_card_position(index);
+        let end = self.get_card_position(top_index);
+        let peeked = index < top_index
+            && stack
+                .get_card(index)
+                .map(|card| card.face_up)
+                .unwrap_or(false);
+        if peeked {
+            start.y -= self.metrics.height / 4;
+        }
+        ScreenRect::new(
+            start,
+            ScreenSize::new(
+                end.x - start.x + self.metrics.width,
+                end.y - start.y + self.metrics.height,
+            ),
+        )
+    }
+
+    pub fn card_rect(&self, index: CardIndex) -> ScreenRect {
+        ScreenRect::new(self.get_card_position(index), self.metrics.size())
+    }
+
+    pub fn landing_rects(&self, target: &Stack, count: usize) -> Vec<ScreenRect> {
+        (target.len()..target.len() + count)
+            .map(|index| self.card_rect(CardIndex::new(index)))
+            .collect()
+    }
+
+    fn draw_bitmap(&self, bitmap: &Bitmap, position: ScreenPoint) -> Result<(), Error> {
+        if self.metrics.is_native() {
+            bitmap.draw(
+                None,
+                None,
+                position,
+                LCDBitmapDrawMode::kDrawModeCopy,
+                LCDBitmapFlip::kBitmapUnflipped,
+                SCREEN_CLIP,
+            )?;
+        } else {
+            let scale = self.metrics.scale();
+            bitmap.draw_scaled(None, None, position, vec2(scale, scale), SCREEN_CLIP)?;
+        }
         Ok(())
     }
 
+    fn draw_empty(&self, resources: &Resources) -> Result<(), Error> {
+        self.draw_bitmap(&resources.empty, self.position)
+    }
+
     fn draw_card_at(
+        &self,
         card: &Card,
         position: &ScreenPoint,
         resources: &Resources,
@@ -124,37 +234,18 @@ impl StackView {
                 &resources.empty
             }
         } else {
-            &resources.back
+            resources.back_bitmap()
         };
-        bitmap.draw(
-            None,
-            None,
-            *position,
-            LCDBitmapDrawMode::kDrawModeCopy,
-            LCDBitmapFlip::kBitmapUnflipped,
-            SCREEN_CLIP,
-        )?;
+        self.draw_bitmap(bitmap, *position)?;
+        if card.face_up && resources.suit_patterns {
+            draw_suit_marker(*position, card.suit, self.metrics.width)?;
+        }
         Ok(())
     }
 
     fn draw_squared(&self, stack: &Stack, resources: &Resources) -> Result<(), Error> {
         if let Some(card) = stack.get_top_card() {
-            let bitmap = if card.face_up {
-                resources
-                    .card_bitmaps
-                    .get(&(card.suit, card.rank))
-                    .unwrap_or(&resources.empty)
-            } else {
-                &resources.back
-            };
-            bitmap.draw(
-                None,
-                None,
-                self.position,
-                LCDBitmapDrawMode::kDrawModeCopy,
-                LCDBitmapFlip::kBitmapUnflipped,
-                SCREEN_CLIP,
-            )?;
+            self.draw_card_at(card, &self.position, resources)?;
         }
         Ok(())
     }
@@ -163,7 +254,7 @@ impl StackView {
         &self,
         stack: &Stack,
         resources: &Resources,
-        source: &Source,
+        source: &CardLocation,
         direction: &FanDirection,
         visible: usize,
     ) -> Result<(), Error> {
@@ -172,23 +263,23 @@ impl StackView {
         let mut card_pos = self.position;
 
         let fan_vector = match direction {
-            FanDirection::Down => ScreenVector::new(0, MARGIN),
-            FanDirection::Right => ScreenVector::new(MARGIN, 0),
+            FanDirection::Down => ScreenVector::new(0, self.metrics.fan),
+            FanDirection::Right => ScreenVector::new(self.metrics.fan, 0),
         };
 
         let start = cards_in_stack - cards_to_draw;
-        let max_index = cards_in_stack - 1;
-        for index in start..cards_in_stack {
+        let max_index = stack.top_card_index();
+        for index in (start..cards_in_stack).map(CardIndex::new) {
             if let Some(card) = stack.get_card(index) {
                 if card.face_up
                     && index < max_index
                     && index == source.index
                     && stack.stack_id == source.stack
                 {
-                    let peeked = card_pos - Vector2D::new(0, CARD_HEIGHT / 4);
-                    Self::draw_card_at(card, &peeked, resources)?;
+                    let peeked = card_pos - Vector2D::new(0, self.metrics.height / 4);
+                    self.draw_card_at(card, &peeked, resources)?;
                 } else {
-                    Self::draw_card_at(card, &card_pos, resources)?;
+                    self.draw_card_at(card, &card_pos, resources)?;
                 }
             }
             card_pos += fan_vector;
@@ -197,7 +288,32 @@ impl StackView {
         Ok(())
     }
 
-    fn draw(&self, source: &Source, stack: &Stack, resources: &Resources) -> Result<(), Error> {
+    fn draw_dead(&self) -> Result<(), Error> {
+        let graphics = Graphics::get();
+        let inset = vec2(GUTTER, GUTTER);
+        let top_left = self.position + inset;
+        let bottom_right = self.position + self.metrics.size().to_vector() - inset;
+        graphics.draw_line(
+            top_left,
+            bottom_right,
+            3,
+            LCDColor::Solid(LCDSolidColor::kColorBlack),
+        )?;
+        graphics.draw_line(
+            ScreenPoint::new(bottom_right.x, top_left.y),
+            ScreenPoint::new(top_left.x, bottom_right.y),
+            3,
+            LCDColor::Solid(LCDSolidColor::kColorBlack),
+        )?;
+        Ok(())
+    }
+
+    fn draw(
+        &self,
+        source: &CardLocation,
+        stack: &Stack,
+        resources: &Resources,
+    ) -> Result<(), Error> {
         if stack.is_empty() {
             self.draw_empty(resources)?;
         } else {
@@ -214,30 +330,123 @@ impl StackView {
 
 struct Resources {
     card_bitmaps: HashMap<(Suit, Rank), Bitmap>,
-    back: Bitmap,
+    backs: HashMap<CardBack, Bitmap>,
+    back: CardBack,
+    suit_patterns: bool,
     empty: Bitmap,
     #[allow(unused)]
     graphics: Graphics,
-    point: Bitmap,
+}
+
+impl Resources {
+    fn back_bitmap(&self) -> &Bitmap {
+        self.backs.get(&self.back).unwrap_or(&self.empty)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ResumeChoice {
+    Resume,
+    NewGame,
+}
+
+impl ResumeChoice {
+    fn toggle(self) -> Self {
+        match self {
+            ResumeChoice::Resume => ResumeChoice::NewGame,
+            ResumeChoice::NewGame => ResumeChoice::Resume,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StatsPage {
+    Summary,
+    Progress,
+}
+
+impl StatsPage {
+    fn toggle(self) -> Self {
+        match self {
+            StatsPage::Summary => StatsPage::Progress,
+            StatsPage::Progress => StatsPage::Summary,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EntryPurpose {
+    Seed,
+    ShareCode,
+    SlotName(GameMode),
+}
+
+enum Overlay {
+    Options(OptionsMenu),
+    Entry(CrankEntry, EntryPurpose),
+    Stats(StatsPage),
+    Bankroll,
+    History(HistoryBrowser),
+    Bookmarks(BookmarkBrowser),
+    Calendar(CalendarView),
+    SaveSlots(SlotPicker),
+    Notice(Vec<String>),
+    ShareCode(ShareCodeView),
+    CardBacks(CardBackPicker),
+    NewGame,
+    Attract,
+    ResumeGame(ResumeChoice),
+    ConfirmRecycle,
+    Won(GameSummary),
+    TimeUp(GameSummary, Option<usize>),
+    OutOfMoves(GameSummary),
+    RaceHandoff,
+    RaceResults,
 }
 
 struct KlondikeGame {
     table: Table,
-    active_cards: Vec<Source>,
-    source_index: usize,
-    targets: Vec<StackId>,
-    target_index: usize,
+    cursor: Cursor,
+    selection: Selection,
+    legal_moves: LegalMoveCache,
+    playable: Vec<CardLocation>,
     views: HashMap<StackId, StackView>,
     #[allow(unused)]
     cards_table: BitmapTable,
     resources: Resources,
-    crank_threshhold: i32,
+    input: InputMapper,
+    settings: Settings,
+    overlay: Option<Overlay>,
+    stats: Stats,
+    history: History,
+    session: Session,
+    undo: UndoHistory,
+    recycle_animation: Option<RecycleAnimation>,
+    hint_arrow: Option<HintArrow>,
+    viewport: Viewport,
+    tilt_neutral: Option<f32>,
+    waterfall: Option<WinWaterfall>,
+    effects: Effects,
+    audio: Option<Audio>,
+    pending_cues: PendingCues,
+    attract: Option<AttractMode>,
+    idle_ms: usize,
+    journal: Journal,
+    replaying: bool,
+    phase: PhaseMachine,
+    auto_complete_ms: usize,
+    // The table as it stood after the last trip through the stock.
+    recycle_hash: Option<u64>,
+    last_frame_ms: usize,
+    telemetry: Box<dyn Telemetry>,
 }
 
 impl KlondikeGame {
     pub fn load_resources(
         cards_table: &BitmapTable,
         graphics: Graphics,
+        back: CardBack,
+        suit_patterns: bool,
     ) -> Result<Resources, Error> {
         let mut card_bitmaps = HashMap::new();
         for suit in Suit::into_enum_iter() {
@@ -255,273 +464,1853 @@ impl KlondikeGame {
                 col += 1;
             }
         }
-        let back = cards_table.get_bitmap(4)?;
+        let mut backs = HashMap::new();
+        for card_back in CardBack::into_enum_iter() {
+            backs.insert(card_back, cards_table.get_bitmap(card_back.bitmap_index())?);
+        }
         let empty = cards_table.get_bitmap(0)?;
-        let point = graphics.load_bitmap("assets/point")?;
         Ok(Resources {
             card_bitmaps,
+            backs,
             back,
+            suit_patterns,
             empty,
             graphics,
-            point,
         })
     }
 
-    fn update_active_cards(&mut self) {
-        self.active_cards = iter::once(Source::stock())
-            .chain(ActiveCardIterator::new(&self.table))
+    fn playable_sources(legal_moves: &[Play]) -> Vec<CardLocation> {
+        let mut sources: Vec<CardLocation> = legal_moves
+            .iter()
+            .filter_map(|play| match play {
+                Play::MoveCards(source, _) => Some(*source),
+                Play::Setup | Play::DrawFromStock | Play::RecycleWaste => None,
+            })
             .collect();
+        sources.dedup();
+        sources
     }
 
-    fn update_targets(&mut self) {
-        let source = self.table.source;
-
-        self.targets = StackId::into_enum_iter()
-            .filter(|stack_id| {
-                *stack_id == source.stack || self.table.stack_can_accept_hand(*stack_id)
-            })
-            .collect();
-        self.target_index = self
-            .targets
-            .iter()
-            .position(|stack_id| *stack_id == source.stack)
-            .unwrap_or(0);
+    fn update_active_cards(&mut self) {
+        self.playable = Self::playable_sources(self.legal_moves_cached());
+        self.selection
+            .update_active_cards(&self.table, &mut self.cursor);
     }
 
-    fn go_previous(&mut self) {
-        if self.table.cards_in_hand() {
-            if self.target_index == 0 {
-                self.target_index = self.targets.len().saturating_sub(1);
-            } else {
-                self.target_index -= 1;
-            }
-            self.table.target = self.targets[self.target_index];
+    // Playtest builds log to a file; everyone else gets no telemetry at all.
+    fn telemetry() -> Box<dyn Telemetry> {
+        if cfg!(feature = "telemetry") {
+            Box::new(FileTelemetry::default())
         } else {
-            if self.source_index == 0 {
-                self.source_index = self.active_cards.len().saturating_sub(1);
-            } else {
-                self.source_index -= 1;
-            }
-            self.table.source = self.active_cards[self.source_index];
+            Box::new(NoTelemetry)
         }
     }
 
-    fn go_next(&mut self) {
-        if self.table.cards_in_hand() {
-            let max_index = self.targets.len().saturating_sub(1);
-            if self.target_index == max_index {
-                self.target_index = 0;
-            } else {
-                self.target_index += 1;
-            }
-            self.table.target = self.targets[self.target_index];
-        } else {
-            if self.active_cards.len() > 0 {
-                let max_index = self.active_cards.len().saturating_sub(1);
-                if self.source_index >= max_index {
-                    self.source_index = 0;
-                } else {
-                    self.source_index += 1;
-                }
-                self.table.source = self.active_cards[self.source_index];
+    fn random_seed(mode: GameMode) -> Result<u64, Error> {
+        let (secs, _) = System::get().get_seconds_since_epoch()?;
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(secs as u64);
+        match mode {
+            GameMode::Klondike
+            | GameMode::Practice
+            | GameMode::TimeAttack
+            | GameMode::MoveChallenge
+            | GameMode::Wildcard
+            | GameMode::Race => Ok(*WINABLE_SEEDS.choose(&mut rng).expect("seed")),
+            GameMode::Westcliff
+            | GameMode::Easthaven
+            | GameMode::Yukon
+            | GameMode::Canfield
+            | GameMode::Agnes
+            | GameMode::FortyThieves
+            | GameMode::Spiderette => Ok(rng.gen::<u32>() as u64),
+            GameMode::Puzzle => Ok((rng.gen::<u32>() as usize % PUZZLES.len()) as u64),
+        }
+    }
+
+    fn today() -> Result<u32, Error> {
+        let system = System::get();
+        let (secs, _) = system.get_seconds_since_epoch()?;
+        Ok(local_day(secs, system.get_timezone_offset()?))
+    }
+
+    fn daily_seed(mode: GameMode, day: u32) -> u64 {
+        match mode {
+            GameMode::Klondike
+            | GameMode::Practice
+            | GameMode::TimeAttack
+            | GameMode::MoveChallenge
+            | GameMode::Wildcard
+            | GameMode::Race => daily_klondike_seed(day),
+            GameMode::Westcliff
+            | GameMode::Easthaven
+            | GameMode::Yukon
+            | GameMode::Canfield
+            | GameMode::Agnes
+            | GameMode::FortyThieves
+            | GameMode::Spiderette => u64::from(daily_hash(day)),
+            GameMode::Puzzle => (daily_hash(day) as usize % PUZZLES.len()) as u64,
+        }
+    }
+
+    fn deal_daily(&mut self, day: u32) {
+        let seed = Self::daily_seed(self.settings.preferences.game_mode, day);
+        self.deal(seed, Some(day));
+    }
+
+    fn save_stats(&self) {
+        if let Err(err) = storage::save(STATS_PATH, &self.stats) {
+            log_to_console!("saving stats failed: {}", err);
+        }
+    }
+
+    fn play_cue(&self, cue: Cue) {
+        if let (true, Some(audio)) = (self.settings.preferences.sounds, &self.audio) {
+            if let Err(err) = audio.play(cue) {
+                log_to_console!("playing sound failed: {}", err);
             }
         }
     }
 
-    pub fn new(_playdate: &Playdate) -> Result<Box<Self>, Error> {
-        let (secs, _) = System::get().get_seconds_since_epoch()?;
-        let mut rng = rand_pcg::Pcg32::seed_from_u64(secs as u64);
-        let seed = WINABLE_SEEDS.choose(&mut rng).expect("seed");
-        let table = Table::new(*seed);
-        let graphics = Graphics::get();
-        let cards_table = graphics.load_bitmap_table("assets/cards")?;
+    fn play_cue_after(&mut self, event: AnimationEvent, cue: Cue) {
+        if let Some(cut_short) = self.pending_cues.wait_for(event, cue) {
+            self.play_cue(cut_short);
+        }
+    }
 
-        let foundation_gutter_count = (FOUNDATIONS.len() - 1) as i32;
-        let mut position = ScreenPoint::new(
-            SCREEN_WIDTH
-                - FOUNDATIONS.len() as i32 * 50
-                - foundation_gutter_count * GUTTER
-                - MARGIN,
-            MARGIN,
-        );
+    fn animation_reached(&mut self, event: AnimationEvent) {
+        if let Some(cue) = self.pending_cues.reached(event) {
+            self.play_cue(cue);
+        }
+    }
 
-        let foundations = FOUNDATIONS.iter().map(|foundation| {
-            let stack = StackView {
-                stack_id: *foundation,
-                position,
-                mode: StackDrawMode::Squared,
-            };
-            position.x += CARD_WIDTH + GUTTER;
-            stack
-        });
+    fn settings_changed(&mut self, previous: Settings) {
+        let changes = self.settings.changes(&previous);
+        if changes.is_empty() {
+            return;
+        }
+        for change in changes {
+            match change {
+                SettingsChange::Controls => self.input = InputMapper::default(),
+                SettingsChange::Layout => {
+                    self.views = layout::build_views(&self.settings.preferences, &self.table)
+                }
+                SettingsChange::Theme => {
+                    self.resources.back = self.settings.preferences.card_back;
+                    self.resources.suit_patterns = self.settings.preferences.suit_patterns;
+                }
+                SettingsChange::GameMode
+                | SettingsChange::Assists
+                | SettingsChange::Sounds
+                | SettingsChange::Scoring
+                | SettingsChange::Rules => (),
+            }
+        }
+        if let Err(err) = self.settings.save() {
+            log_to_console!("saving settings failed: {}", err);
+        }
+    }
 
-        let mut position = ScreenPoint::new(MARGIN, MARGIN + CARD_HEIGHT + GUTTER);
-        let mut stack_count = 1;
-        let tableaux = TABLEAUX.iter().map(|tableau| {
-            let stack = StackView {
-                stack_id: *tableau,
-                position,
-                mode: StackDrawMode::Fanned(FanDirection::Down, 52),
-            };
-            stack_count += 1;
-            position.x += 55;
-            stack
-        });
+    fn save_history(&self) {
+        if let Err(err) = storage::save(HISTORY_PATH, &self.history) {
+            log_to_console!("saving history failed: {}", err);
+        }
+    }
 
-        let stock = StackView {
-            stack_id: StackId::Stock,
-            position: ScreenPoint::new(MARGIN, MARGIN),
-            mode: StackDrawMode::Squared,
-        };
-        let waste = StackView {
-            stack_id: StackId::Waste,
-            position: ScreenPoint::new(MARGIN + GUTTER + CARD_WIDTH, MARGIN),
-            mode: StackDrawMode::Fanned(FanDirection::Right, 3),
-        };
-        let in_hand = StackView {
-            stack_id: StackId::Hand,
-            position: ScreenPoint::zero(),
-            mode: StackDrawMode::Squared,
-        };
+    fn verdict(&self) -> Verdict {
+        let standard =
+            self.session.modifiers.is_empty() && self.settings.rules == RulesConfig::default();
+        match self.session.mode {
+            GameMode::Puzzle => Verdict::Winnable,
+            GameMode::Klondike
+            | GameMode::Practice
+            | GameMode::TimeAttack
+            | GameMode::MoveChallenge
+            | GameMode::Wildcard
+            | GameMode::Race
+                if standard && WINABLE_SEEDS.contains(&self.session.seed) =>
+            {
+                Verdict::Winnable
+            }
+            _ => Verdict::Unverified,
+        }
+    }
 
-        let views: HashMap<StackId, StackView> = foundations
-            .chain(tableaux)
-            .chain(iter::once(stock))
-            .chain(iter::once(waste).chain(iter::once(in_hand)))
-            .map(|stack_view| (stack_view.stack_id, stack_view))
-            .collect();
-        let resources = Self::load_resources(&cards_table, Graphics::get())?;
-        let active_cards = iter::once(Source::stock())
-            .chain(ActiveCardIterator::new(&table))
-            .collect();
-        Ok(Box::new(Self {
-            table,
-            active_cards,
-            source_index: 0,
-            targets: Vec::new(),
-            target_index: 0,
-            views,
-            cards_table,
-            resources,
-            crank_threshhold: 0,
-        }))
+    fn summary(&self, result: GameResult, records: PersonalBests) -> GameSummary {
+        GameSummary::new(&self.session, &self.table, result, self.verdict(), records)
     }
 
-    fn check_crank(&mut self, _playdate: &mut Playdate) -> Result<(), Error> {
-        let change = System::get().get_crank_change()? as i32;
-        self.crank_threshhold += change;
+    fn enter_phase(&mut self, phase: GamePhase) {
+        if let Err(err) = self.phase.enter(phase) {
+            log_to_console!("{}", err);
+        }
+    }
+
+    fn can_auto_complete(&self) -> bool {
+        !self.table.has_cards_in_stock()
+            && !self.table.has_cards_in_waste()
+            && self.table.face_down_remaining() == 0
+            && self
+                .table
+                .reserve_ids()
+                .iter()
+                .all(|stack_id| self.table.get_stack(*stack_id).is_empty())
+    }
 
-        if self.crank_threshhold > CRANK_THRESHHOLD {
-            self.go_next();
-            self.crank_threshhold = -CRANK_THRESHHOLD;
-        } else if self.crank_threshhold < -CRANK_THRESHHOLD {
-            self.go_previous();
-            self.crank_threshhold = CRANK_THRESHHOLD;
+    fn check_phase(&mut self, delta_ms: usize) {
+        match self.phase.current() {
+            GamePhase::Playing if !self.table.cards_in_hand() => {
+                if self.can_auto_complete() {
+                    self.auto_complete_ms = 0;
+                    self.enter_phase(GamePhase::AutoCompleting);
+                } else if self.table.stock_is_dead() && self.table.moves_iter().next().is_none() {
+                    self.enter_phase(GamePhase::Stuck);
+                    self.telemetry.stuck(&self.session, &self.table);
+                    self.play_cue(Cue::Rejected);
+                    self.overlay = Some(Overlay::Notice(vec![
+                        String::from("No moves left"),
+                        String::from("Undo or start a new game"),
+                    ]));
+                }
+            }
+            GamePhase::AutoCompleting => {
+                self.auto_complete_ms += delta_ms;
+                if self.auto_complete_ms >= AUTO_COMPLETE_STEP_MS {
+                    self.auto_complete_ms = 0;
+                    self.auto_complete_step();
+                }
+            }
+            GamePhase::Dealing
+            | GamePhase::Playing
+            | GamePhase::Won
+            | GamePhase::Stuck
+            | GamePhase::Paused => (),
         }
-        Ok(())
     }
 
-    fn check_buttons(&mut self, _playdate: &mut Playdate) -> Result<(), Error> {
-        let (_, pushed, _) = System::get().get_button_state()?;
-        if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA
-            || (pushed & PDButtons::kButtonB) == PDButtons::kButtonB
-        {
-            if self.table.cards_in_hand() {
-                self.table.put_hand_on_target();
-                self.update_active_cards();
-            } else {
-                match self.table.source.stack {
-                    StackId::Stock => {
-                        self.table.deal_from_stock();
+    // Plays one card home; falls back to normal play if nothing can go.
+    fn auto_complete_step(&mut self) {
+        let table = &self.table;
+        let play = table.moves_iter().find(|play| match play {
+            Play::MoveCards(source, target) => {
+                table.get_stack(source.stack).stack_type != StackType::Foundation
+                    && table.get_stack(*target).stack_type == StackType::Foundation
+            }
+            Play::Setup | Play::DrawFromStock | Play::RecycleWaste => false,
+        });
+        let moved = match play {
+            Some(Play::MoveCards(source, target)) => {
+                match self.execute(TableCommand::new(Play::MoveCards(source, target)), false) {
+                    Ok(()) => {
                         self.update_active_cards();
+                        Some(target)
                     }
-                    StackId::Waste
-                    | StackId::Foundation1
-                    | StackId::Foundation2
-                    | StackId::Foundation3
-                    | StackId::Foundation4 => {
-                        self.table.take_top_card_from_stack(self.table.source.stack)
+                    Err(err) => {
+                        log_to_console!("{}", err);
+                        None
                     }
-                    StackId::Tableau1
-                    | StackId::Tableau2
-                    | StackId::Tableau3
-                    | StackId::Tableau4
-                    | StackId::Tableau5
-                    | StackId::Tableau6
-                    | StackId::Tableau7 => self.table.take_selected_cards_from_stack(
-                        self.table.source.stack,
-                        self.table.source.index,
-                    ),
-                    StackId::Hand => (),
-                }
-                self.table.target = self.table.source.stack;
-                self.update_targets();
-            }
-        } else if pushed & PDButtons::kButtonLeft == PDButtons::kButtonLeft {
-            self.go_previous();
-        } else if pushed & PDButtons::kButtonRight == PDButtons::kButtonRight {
-            self.go_next();
+                }
+            }
+            _ => None,
+        };
+        match moved {
+            Some(target) => {
+                self.play_cue_after(
+                    AnimationEvent::CardsLanded(target),
+                    Cue::FoundationGrew(self.table.get_stack(target).len()),
+                );
+                self.effects.trigger(
+                    TableEvent::CardsLanded(target),
+                    &self.settings.preferences.effects,
+                );
+                if self.goal_met() {
+                    self.record_result(GameResult::Won);
+                }
+            }
+            None => self.enter_phase(GamePhase::Playing),
         }
-        Ok(())
     }
-}
 
-impl Game for KlondikeGame {
-    fn update(
-        &mut self,
-        playdate: &mut crankstart::Playdate,
-    ) -> core::result::Result<(), anyhow::Error> {
-        self.check_crank(playdate)?;
-        self.check_buttons(playdate)?;
+    fn record_result(&mut self, result: GameResult) {
+        if !self.session.result_recorded {
+            match result {
+                GameResult::Won => self.enter_phase(GamePhase::Won),
+                GameResult::Lost => self.enter_phase(GamePhase::Stuck),
+                GameResult::Abandoned => (),
+            }
+            self.telemetry
+                .game_ended(&self.session, &self.table, result);
+            let practice = self.session.mode == GameMode::Practice;
+            if result == GameResult::Won {
+                self.session.record_win();
+            }
+            self.stats.record(self.session.mode, result);
+            if result != GameResult::Won {
+                self.stats.record_progress(
+                    self.session.mode,
+                    self.table.foundation_count(),
+                    self.table.deck_size(),
+                );
+            }
+            if self.session.scoring == ScoringMode::Vegas && !practice {
+                self.stats.vegas.settle(self.session.score);
+            }
+            self.history.record(&self.session, result);
+            if let Some(day) = self.session.daily {
+                self.stats.record_daily(self.session.mode, day, result);
+            }
+            let turn = TurnResult::new(&self.session, &self.table);
+            let race_over = match self.session.race.as_mut() {
+                Some(race) if result != GameResult::Abandoned => {
+                    race.finish_turn(turn);
+                    Some(race.is_over())
+                }
+                _ => None,
+            };
+            if let Some(race_over) = race_over {
+                self.overlay = Some(if race_over {
+                    Overlay::RaceResults
+                } else {
+                    Overlay::RaceHandoff
+                });
+                if result == GameResult::Won {
+                    self.waterfall = Some(WinWaterfall::new(self.waterfall_launches()));
+                }
+            } else if self.session.mode.time_limit_ms().is_some() {
+                if result != GameResult::Abandoned {
+                    let rank = self
+                        .stats
+                        .record_time_attack(&self.session, self.table.foundation_count());
+                    let summary = self.summary(result, PersonalBests::default());
+                    self.overlay = Some(Overlay::TimeUp(summary, rank));
+                }
+                if result == GameResult::Won {
+                    self.waterfall = Some(WinWaterfall::new(self.waterfall_launches()));
+                }
+            } else if result == GameResult::Lost && self.session.out_of_moves() {
+                let summary = self.summary(result, PersonalBests::default());
+                self.overlay = Some(Overlay::OutOfMoves(summary));
+            } else if result == GameResult::Won {
+                let bests = if practice {
+                    PersonalBests::default()
+                } else {
+                    if self.session.mode == GameMode::Puzzle {
+                        self.stats
+                            .record_puzzle(self.session.seed, self.session.moves);
+                    }
+                    if let Some(par) = par_for(self.session.mode, self.session.seed) {
+                        let grade = Grade::from_moves(self.session.moves, par);
+                        self.stats.record_grade(&self.session, grade);
+                    }
+                    self.stats.record_win(&self.session)
+                };
+                self.overlay = Some(Overlay::Won(self.summary(result, bests)));
+                self.waterfall = Some(WinWaterfall::new(self.waterfall_launches()));
+            }
+            self.session.result_recorded = true;
+            if let Err(err) = self.journal.clear() {
+                log_to_console!("clearing autosave failed: {}", err);
+            }
+            self.save_stats();
+            self.save_history();
+        }
+    }
 
-        let cards_in_hand = self.table.cards_in_hand();
-        if cards_in_hand {
-            let top_card_index = self.table.get_stack(self.table.target).top_card_index();
-            let position = self
-                .views
-                .get(&self.table.target)
-                .and_then(|view| {
-                    Some(view.get_card_position(top_card_index) + Vector2D::new(10, 10))
+    fn completed_foundations(&self) -> Vec<StackId> {
+        self.table
+            .foundations()
+            .filter(Foundation::is_complete)
+            .map(|foundation| foundation.stack().stack_id)
+            .collect()
+    }
+
+    fn waterfall_launches(&self) -> Vec<(Card, ScreenPoint)> {
+        let foundations: Vec<Stack> = self
+            .table
+            .foundation_ids()
+            .iter()
+            .map(|stack_id| self.table.get_stack(*stack_id))
+            .collect();
+        let deepest = foundations
+            .iter()
+            .map(|stack| stack.len())
+            .max()
+            .unwrap_or(0);
+        (0..deepest)
+            .flat_map(|depth| {
+                foundations.iter().filter_map(move |stack| {
+                    let card = stack
+                        .get_card(CardIndex::new(stack.len().checked_sub(depth + 1)?))?
+                        .clone();
+                    let position = self.views.get(&stack.stack_id)?.position;
+                    Some((card, position + self.viewport.offset()))
                 })
-                .unwrap_or_else(|| ScreenPoint::zero());
-            if let Some(in_hand) = self.views.get_mut(&StackId::Hand) {
-                in_hand.position = position;
-            }
+            })
+            .collect()
+    }
+
+    fn deal(&mut self, seed: u64, daily: Option<u32>) {
+        let mode = self.settings.preferences.game_mode;
+        if self.session.mode != mode {
+            self.leave_slot();
+            self.journal = Journal::new(mode);
+        } else if self.session.started {
+            self.record_result(GameResult::Abandoned);
         }
+        self.session = Self::new_session(seed, &self.settings.preferences);
+        self.session.daily = daily;
+        self.session.live_daily = daily.is_some() && daily == Self::today().ok();
+        self.session.relaxed = self.settings.rules.any_card_on_empty_tableau;
+        self.table = Self::deal_table(&self.session, self.settings.rules);
+        self.telemetry.game_started(&self.session);
+        self.reset_table_state();
+        self.start_autosave();
+        self.overlay = Some(Overlay::NewGame);
+        self.practice_hint();
+    }
 
-        Graphics::get().clear(LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+    fn reset_table_state(&mut self) {
+        self.undo = UndoHistory::new(Self::undo_limit(&self.session, &self.settings.preferences));
+        self.recycle_animation = None;
+        self.hint_arrow = None;
+        self.viewport = Viewport::default();
+        self.waterfall = None;
+        self.effects = Effects::default();
+        self.pending_cues.clear();
+        self.recycle_hash = None;
+        self.legal_moves.invalidate();
+        self.cursor = Cursor::new(&self.table);
+        self.views = layout::build_views(&self.settings.preferences, &self.table);
+        self.selection = Selection::default();
+        self.enter_phase(GamePhase::Dealing);
+        self.update_active_cards();
+    }
 
-        for (stack_id, view) in &self.views {
-            if *stack_id != StackId::Hand || cards_in_hand {
-                let stack = self.table.get_stack(*stack_id);
-                view.draw(&self.table.source, stack, &self.resources)?;
-            }
+    fn undo_limit(session: &Session, preferences: &Preferences) -> UndoLimit {
+        match session.mode {
+            GameMode::Practice => UndoLimit::Unlimited,
+            _ => wildcard::undo_limit(&session.modifiers, preferences.undo_limit),
         }
+    }
 
-        let position = if cards_in_hand {
-            let target = self.table.get_stack(self.table.target);
-            let target_view = self.views.get(&target.stack_id).expect("target_view");
-            let position =
-                target_view.get_card_position(target.top_card_index()) + Vector2D::new(10, 10);
-            position
+    fn new_session(seed: u64, preferences: &Preferences) -> Session {
+        let mode = preferences.game_mode;
+        let modifiers = match mode {
+            GameMode::Wildcard => wildcard::modifiers_for(seed),
+            _ => Vec::new(),
+        };
+        let scoring = wildcard::scoring(&modifiers, mode.scoring(preferences.scoring));
+        let mut session = Session::new(seed, mode, scoring);
+        session.modifiers = modifiers;
+        if mode == GameMode::Race {
+            session.race = Some(Race::default());
+        }
+        session
+    }
+
+    fn next_race_turn(&mut self) {
+        let race = self.session.race.take();
+        self.deal(self.session.seed, self.session.daily);
+        self.session.race = race;
+        self.start_autosave();
+    }
+
+    fn deal_table(session: &Session, rules: RulesConfig) -> Table {
+        match session.mode {
+            GameMode::Puzzle => puzzle(session.seed).table(rules).expect("puzzle layout"),
+            mode => Table::with_variant(
+                session.seed,
+                wildcard::variant(&session.modifiers, mode.variant()),
+                wildcard::adjust_rules(&session.modifiers, rules),
+            ),
+        }
+    }
+
+    fn next_puzzle(&self) -> u64 {
+        let count = PUZZLES.len() as u64;
+        let start = if self.session.mode == GameMode::Puzzle {
+            self.session.seed + 1
         } else {
-            let source = self.table.get_stack(self.table.source.stack);
-            let source_view = self.views.get(&source.stack_id).expect("source_view");
-            source_view.get_card_position(self.table.source.index)
+            0
         };
+        (0..count)
+            .map(|offset| (start + offset) % count)
+            .find(|index| !self.stats.puzzle_solved(*index))
+            .unwrap_or(start % count)
+    }
 
-        self.resources.point.draw(
-            None,
-            None,
-            position + vec2(CARD_WIDTH, CARD_HEIGHT) / 2,
-            LCDBitmapDrawMode::kDrawModeCopy,
-            LCDBitmapFlip::kBitmapUnflipped,
-            SCREEN_CLIP,
-        )?;
+    fn goal_met(&self) -> bool {
+        match self.session.mode {
+            GameMode::Puzzle => puzzle(self.session.seed).goal_met(&self.table),
+            _ => self.table.winner(),
+        }
+    }
 
+    fn deal_new_game(&mut self) -> Result<(), Error> {
+        let seed = match self.settings.preferences.game_mode {
+            GameMode::Puzzle => self.next_puzzle(),
+            mode => Self::random_seed(mode)?,
+        };
+        self.deal(seed, None);
         Ok(())
     }
+
+    fn new_game_lines(&self) -> Vec<String> {
+        if self.session.mode == GameMode::Puzzle {
+            let puzzle = puzzle(self.session.seed);
+            return vec![
+                format!("Puzzle {}: {}", self.session.seed + 1, puzzle.name),
+                String::from(puzzle.description),
+                format!("in {} moves", puzzle.move_limit),
+                self.stats.puzzle_progress(),
+                String::from("Press A to play"),
+            ];
+        }
+        if let Some(race) = &self.session.race {
+            let mut lines = vec![
+                format!("Race deal #{}", self.session.seed),
+                format!("Player {}'s turn", race.current_player()),
+            ];
+            lines.extend(race.turn_lines());
+            lines.push(String::from("Press A to play"));
+            return lines;
+        }
+        if !self.session.modifiers.is_empty() {
+            return vec![
+                format!("Wildcard deal #{}", self.session.seed),
+                String::from("This deal's modifiers:"),
+                wildcard::describe(&self.session.modifiers),
+                String::from("Press A to play"),
+            ];
+        }
+        let title = if self.session.daily.is_some() {
+            format!(
+                "{} daily deal #{}",
+                self.session.mode.name(),
+                self.session.seed
+            )
+        } else {
+            format!("{} deal #{}", self.session.mode.name(), self.session.seed)
+        };
+        let mut lines = vec![title];
+        match self.stats.record_for(self.session.mode, self.session.seed) {
+            Some(record) => lines.extend(record.summary_lines()),
+            None => lines.push(String::from("No record for this deal yet")),
+        }
+        lines.push(String::from("Press A to play"));
+        lines
+    }
+
+    fn won_lines(&self, summary: &GameSummary) -> Vec<String> {
+        let mut lines = summary.won_lines();
+        lines.push(
+            if self
+                .stats
+                .is_bookmarked(self.session.mode, self.session.seed)
+            {
+                String::from("Press A for a new game")
+            } else {
+                String::from("A: new game  B: bookmark deal")
+            },
+        );
+        lines
+    }
+
+    fn time_up_lines(summary: &GameSummary, rank: Option<usize>) -> Vec<String> {
+        let mut lines = summary.time_up_lines();
+        lines.push(match rank {
+            Some(rank) => format!("Leaderboard rank: {}", rank + 1),
+            None => String::from("Not on the leaderboard"),
+        });
+        lines.push(String::from("Press A for a new game"));
+        lines
+    }
+
+    fn race_handoff_lines(&self) -> Vec<String> {
+        let race = self.session.race.clone().unwrap_or_default();
+        let mut lines = vec![String::from("Turn over")];
+        lines.extend(race.turn_lines());
+        lines.push(format!("Pass to Player {}", race.current_player()));
+        lines.push(String::from("Press A when ready"));
+        lines
+    }
+
+    fn out_of_moves_lines(summary: &GameSummary) -> Vec<String> {
+        let mut lines = summary.out_of_moves_lines();
+        lines.push(String::from("A: new game  B: retry deal"));
+        lines
+    }
+
+    fn resume_lines(&self) -> Vec<String> {
+        vec![
+            String::from("Game in progress"),
+            format!("{} #{}", self.session.mode.name(), self.session.seed),
+            format!(
+                "Time: {}  Home: {}/{}",
+                format_time(self.session.elapsed_ms),
+                self.table.foundation_count(),
+                self.table.deck_size()
+            ),
+            String::from("Resume"),
+            String::from("New game"),
+        ]
+    }
+
+    fn bookmark_deal(&mut self, note: Option<String>) -> bool {
+        let added = self
+            .stats
+            .bookmark(self.session.mode, self.session.seed, note);
+        if added {
+            self.save_stats();
+        }
+        added
+    }
+
+    pub fn new(_playdate: &Playdate) -> Result<Box<Self>, Error> {
+        let settings = Settings::load().unwrap_or_else(|err| {
+            log_to_console!("loading settings failed: {}", err);
+            Settings::default()
+        });
+        let Settings { preferences, rules } = settings;
+        let seed = Self::random_seed(preferences.game_mode)?;
+        let mut session = Self::new_session(seed, &preferences);
+        session.relaxed = rules.any_card_on_empty_tableau;
+        let table = Self::deal_table(&session, rules);
+        let stats = storage::load(STATS_PATH)
+            .unwrap_or_else(|err| {
+                log_to_console!("loading stats failed: {}", err);
+                None
+            })
+            .unwrap_or_default();
+        let history = storage::load(HISTORY_PATH)
+            .unwrap_or_else(|err| {
+                log_to_console!("loading history failed: {}", err);
+                None
+            })
+            .unwrap_or_default();
+        let audio = Audio::load()
+            .map_err(|err| log_to_console!("loading sounds failed: {}", err))
+            .ok();
+        let graphics = Graphics::get();
+        let cards_table = graphics.load_bitmap_table("assets/cards")?;
+
+        let views = layout::build_views(&preferences, &table);
+        let resources = Self::load_resources(
+            &cards_table,
+            Graphics::get(),
+            preferences.card_back,
+            preferences.suit_patterns,
+        )?;
+        let mut legal_moves = LegalMoveCache::default();
+        let playable = Self::playable_sources(legal_moves.get(&table));
+        let mut cursor = Cursor::new(&table);
+        let selection = Selection::new(&table, &mut cursor);
+        let mut game = Box::new(Self {
+            table,
+            cursor,
+            selection,
+            legal_moves,
+            playable,
+            views,
+            cards_table,
+            resources,
+            input: InputMapper::default(),
+            settings,
+            overlay: Some(Overlay::NewGame),
+            stats,
+            history,
+            undo: UndoHistory::new(Self::undo_limit(&session, &preferences)),
+            session,
+            recycle_animation: None,
+            hint_arrow: None,
+            viewport: Viewport::default(),
+            tilt_neutral: None,
+            waterfall: None,
+            effects: Effects::default(),
+            audio,
+            pending_cues: PendingCues::default(),
+            attract: None,
+            idle_ms: 0,
+            journal: Journal::new(preferences.game_mode),
+            replaying: false,
+            phase: PhaseMachine::default(),
+            auto_complete_ms: 0,
+            recycle_hash: None,
+            last_frame_ms: System::get().get_current_time_milliseconds()?,
+            telemetry: Self::telemetry(),
+        });
+        #[cfg(not(test))]
+        serial::listen().unwrap_or_else(|err| {
+            log_to_console!("listening on serial failed: {}", err);
+        });
+        match autosave::load(preferences.game_mode) {
+            Ok(Some(saved)) => {
+                game.resume(saved);
+                if game.session.started {
+                    game.overlay = Some(Overlay::ResumeGame(ResumeChoice::Resume));
+                }
+            }
+            Ok(None) => game.start_autosave(),
+            Err(err) => {
+                log_to_console!("loading autosave failed: {}", err);
+                game.start_autosave();
+            }
+        }
+        Ok(game)
+    }
+
+    fn update_tilt(&mut self) -> Result<(), Error> {
+        let system = System::get();
+        let wanted =
+            self.settings.preferences.tilt_to_pan && self.settings.preferences.scroll_tableaux;
+        match (wanted, self.tilt_neutral) {
+            (true, None) => {
+                system.set_peripherals_enabled(PDPeripherals::kAccelerometer)?;
+                let (_, tilt, _) = system.get_accelerometer()?;
+                self.tilt_neutral = Some(tilt);
+            }
+            (true, Some(neutral)) => {
+                let (_, tilt, _) = system.get_accelerometer()?;
+                let delta = layout::tilt_pan_delta(tilt, neutral);
+                if delta != 0 {
+                    self.viewport.pan(
+                        delta,
+                        layout::content_bottom(&self.views, &self.table),
+                        layout::playfield_bottom(self.settings.preferences.show_hud),
+                    );
+                }
+            }
+            (false, Some(_)) => {
+                system.set_peripherals_enabled(PDPeripherals::kNone)?;
+                self.tilt_neutral = None;
+            }
+            (false, None) => (),
+        }
+        Ok(())
+    }
+
+    fn start_recycle_animation(&mut self, redeals_before: Option<usize>) {
+        if let (Some(waste), Some(stock)) = (
+            self.views.get(&StackId::Waste),
+            self.views.get(&StackId::Stock),
+        ) {
+            self.recycle_animation = Some(RecycleAnimation::new(
+                waste.position,
+                stock.position,
+                stock.metrics.size(),
+                redeals_before,
+                self.table.redeals_remaining(),
+            ));
+        }
+    }
+
+    fn show_hint(&mut self) {
+        self.hint_arrow = self
+            .table
+            .hint()
+            .and_then(|play| layout::hint_endpoints(&self.views, &self.table, play))
+            .map(|(from, to)| HintArrow::new(from, to));
+        if self.hint_arrow.is_none() {
+            log_to_console!("no moves left");
+        }
+    }
+
+    fn practice_hint(&mut self) {
+        if self.session.mode == GameMode::Practice && !self.table.cards_in_hand() {
+            self.show_hint();
+        }
+    }
+
+    fn drop_hand(&mut self) -> Result<(), KlondikeError> {
+        if let Some(applied) = self.cursor.put_hand_on_target(&mut self.table)? {
+            self.command_applied(applied, false);
+        }
+        self.update_active_cards();
+        Ok(())
+    }
+
+    fn deal_stock(&mut self) -> Result<(), KlondikeError> {
+        let recycled = self.table.get_stack(StackId::Stock).is_empty();
+        let redeals_before = self.table.redeals_remaining();
+        let play = if recycled {
+            Play::RecycleWaste
+        } else {
+            Play::DrawFromStock
+        };
+        self.execute(TableCommand::new(play), false)?;
+        if recycled {
+            self.start_recycle_animation(redeals_before);
+            let hash = self.table.position_hash();
+            if self.recycle_hash == Some(hash) {
+                self.overlay = Some(Overlay::Notice(vec![
+                    String::from("Nothing has changed"),
+                    String::from("since the last pass"),
+                ]));
+            }
+            self.recycle_hash = Some(hash);
+        }
+        self.update_active_cards();
+        Ok(())
+    }
+
+    fn execute(&mut self, command: TableCommand, redoing: bool) -> Result<(), KlondikeError> {
+        let applied = command.apply(&mut self.table)?;
+        self.command_applied(applied, redoing);
+        Ok(())
+    }
+
+    // Every change to the table lands here, whether played, redone or
+    // replayed from the journal.
+    fn command_applied(&mut self, applied: AppliedCommand, redoing: bool) {
+        let play = applied.command().play();
+        let score = self.session.score;
+        match play {
+            Play::MoveCards(source, target) => {
+                self.session.record_move(
+                    self.table.get_stack(source.stack).stack_type,
+                    self.table.get_stack(target).stack_type,
+                    applied.revealed(),
+                );
+            }
+            Play::DrawFromStock | Play::RecycleWaste => {
+                self.session.record_deal(play == Play::RecycleWaste)
+            }
+            Play::Setup => (),
+        }
+        if redoing {
+            self.undo.push_redone(applied, score);
+        } else {
+            self.undo.push(applied, score);
+        }
+        self.session.plays.push(play);
+        self.autosave(if redoing {
+            JournalEntry::Redo
+        } else {
+            JournalEntry::Play(play)
+        });
+    }
+
+    fn autosave(&mut self, entry: JournalEntry) {
+        if self.replaying {
+            return;
+        }
+        if let Err(err) = self
+            .journal
+            .append(entry, &self.session, &self.table, &self.undo)
+        {
+            log_to_console!("autosave failed: {}", err);
+        }
+    }
+
+    fn start_autosave(&mut self) {
+        if let Err(err) = self.journal.start(&self.session, &self.table, &self.undo) {
+            log_to_console!("autosave failed: {}", err);
+        }
+    }
+
+    fn leave_slot(&mut self) {
+        if self.session.started && !self.session.result_recorded {
+            self.start_autosave();
+        }
+    }
+
+    fn switch_slot(&mut self, mode: GameMode) -> Result<(), Error> {
+        if mode == self.session.mode {
+            self.overlay = None;
+            return Ok(());
+        }
+        self.leave_slot();
+        let previous = self.settings;
+        self.settings.preferences.game_mode = mode;
+        self.settings_changed(previous);
+        match autosave::load(mode) {
+            Ok(Some(saved)) => self.resume(saved),
+            Ok(None) => self.deal_new_game()?,
+            Err(err) => {
+                log_to_console!("loading autosave failed: {}", err);
+                self.deal_new_game()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn replay(&mut self, entry: JournalEntry) -> Result<(), Error> {
+        match entry {
+            JournalEntry::Play(Play::Setup) => Ok(()),
+            JournalEntry::Play(play) => self
+                .execute(TableCommand::new(play), false)
+                .map_err(Error::msg),
+            JournalEntry::Undo => self.try_undo().map_err(Error::msg),
+            JournalEntry::Redo => {
+                let command = self.undo.redo().map_err(Error::msg)?;
+                self.execute(command, true).map_err(Error::msg)
+            }
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &Snapshot) {
+        self.session = snapshot.session.clone();
+        self.table = snapshot.table.clone();
+        self.reset_table_state();
+        if let Some(saved) = snapshot.undo.clone() {
+            let limit = Self::undo_limit(&self.session, &self.settings.preferences);
+            match UndoHistory::load(limit, saved, &self.table) {
+                Ok(undo) => self.undo = undo,
+                Err(err) => log_to_console!("loading undo history failed: {}", err),
+            }
+        }
+    }
+
+    fn resume(&mut self, saved: SavedGame) {
+        self.journal = Journal::resume(&saved);
+        self.restore_snapshot(&saved.snapshot);
+        self.replaying = true;
+        let replayed = saved
+            .entries
+            .iter()
+            .try_for_each(|entry| self.replay(*entry));
+        self.replaying = false;
+        // A journal that doesn't replay cleanly would leave a game the player
+        // never saw, so go back to the snapshot and start the journal over.
+        if let Err(err) = replayed {
+            log_to_console!("replaying autosave failed: {}", err);
+            self.restore_snapshot(&saved.snapshot);
+            self.start_autosave();
+        } else if let Err(err) = self.journal.rewrite(&saved) {
+            log_to_console!("autosave failed: {}", err);
+            self.start_autosave();
+        }
+        self.recycle_animation = None;
+        self.update_active_cards();
+        self.overlay = None;
+        self.practice_hint();
+    }
+
+    fn pick_up_or_drop(&mut self) -> Result<(), KlondikeError> {
+        self.hint_arrow = None;
+        if self.table.cards_in_hand() {
+            self.drop_hand()?;
+        } else if self.cursor.source.stack == StackId::Stock {
+            self.deal_stock()?;
+        } else {
+            self.selection.pick_up(&mut self.table, &mut self.cursor)?;
+        }
+        Ok(())
+    }
+
+    fn run_serial(&mut self, message: &str) {
+        let reply = match SerialCommand::parse(message) {
+            Ok(SerialCommand::Table) => Ok(serial::table_notation(&self.table)),
+            Ok(SerialCommand::Share) => Ok(vec![ShareCode::new(
+                &self.table,
+                self.session.seed,
+                &self.session.plays,
+            )
+            .encode()]),
+            Ok(SerialCommand::Play(_)) if self.table.cards_in_hand() => {
+                Err(Error::msg("cards in hand"))
+            }
+            Ok(SerialCommand::Play(play)) => self
+                .execute(TableCommand::new(play), false)
+                .map(|_| {
+                    self.update_active_cards();
+                    vec![String::from("ok")]
+                })
+                .map_err(Error::msg),
+            Ok(SerialCommand::Deal(seed)) => seed
+                .map(Ok)
+                .unwrap_or_else(|| Self::random_seed(self.settings.preferences.game_mode))
+                .map(|seed| {
+                    self.deal(seed, None);
+                    vec![format!("dealt {}", seed)]
+                }),
+            Err(err) => Err(err),
+        };
+        match reply {
+            Ok(lines) => {
+                for line in lines {
+                    log_to_console!("{}", line);
+                }
+            }
+            Err(err) => log_to_console!("error: {}", err),
+        }
+    }
+
+    fn undo_move(&mut self) {
+        if self.session.result_recorded {
+            return;
+        }
+        if let Err(err) = self.try_undo() {
+            self.effects.explain(err.explanation());
+            log_to_console!("{}", err)
+        }
+    }
+
+    fn try_undo(&mut self) -> Result<(), UndoError> {
+        let score = self.undo.undo(&mut self.table)?;
+        let penalty =
+            self.settings.preferences.undo_penalty && self.session.mode != GameMode::Practice;
+        self.session.record_undo(score, penalty);
+        if !self.replaying {
+            self.telemetry.undo_used(&self.session);
+        }
+        if self.phase.current() == GamePhase::Stuck {
+            self.enter_phase(GamePhase::Playing);
+        }
+        self.autosave(JournalEntry::Undo);
+        self.update_active_cards();
+        self.practice_hint();
+        Ok(())
+    }
+
+    fn redo_move(&mut self) {
+        if self.session.result_recorded {
+            return;
+        }
+        match self.undo.redo() {
+            Ok(command) => {
+                if let Err(err) = self.execute(command, true) {
+                    self.effects.explain(err.explanation());
+                    log_to_console!("{}", err);
+                }
+                self.update_active_cards();
+                self.practice_hint();
+            }
+            Err(err) => {
+                self.effects.explain(err.explanation());
+                log_to_console!("{}", err)
+            }
+        }
+    }
+
+    fn check_overlay_buttons(&mut self, pushed: PDButtons) -> Result<(), Error> {
+        match self.overlay.as_mut() {
+            Some(Overlay::Options(options)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    let previous = self.settings;
+                    match options.activate(
+                        &mut self.settings.rules,
+                        &mut self.settings.preferences,
+                        &mut self.stats,
+                    ) {
+                        OptionsAction::None => (),
+                        OptionsAction::StatsChanged => self.save_stats(),
+                        OptionsAction::ShowCardBacks => {
+                            let picker = CardBackPicker::new(self.settings.preferences.card_back);
+                            self.overlay = Some(Overlay::CardBacks(picker));
+                        }
+                        OptionsAction::ShowHint => {
+                            self.overlay = None;
+                            self.run_command(Command::Hint);
+                        }
+                        OptionsAction::ShowStats => {
+                            self.overlay = Some(Overlay::Stats(StatsPage::Summary))
+                        }
+                        OptionsAction::ShowBankroll => self.overlay = Some(Overlay::Bankroll),
+                        OptionsAction::ShowHistory => {
+                            self.overlay = Some(Overlay::History(HistoryBrowser::default()))
+                        }
+                        OptionsAction::BookmarkDeal => {
+                            let title =
+                                format!("{} #{}", self.session.mode.name(), self.session.seed);
+                            let line = if self.bookmark_deal(None) {
+                                format!("Bookmarked {}", title)
+                            } else {
+                                format!("{} is already bookmarked", title)
+                            };
+                            self.overlay = Some(Overlay::Notice(vec![line]));
+                        }
+                        OptionsAction::ShowBookmarks => {
+                            self.overlay = Some(Overlay::Bookmarks(BookmarkBrowser::default()))
+                        }
+                        OptionsAction::ShareGame if self.session.mode == GameMode::Puzzle => {
+                            self.overlay = Some(Overlay::Notice(vec![String::from(
+                                "Puzzles don't have share codes",
+                            )]));
+                        }
+                        OptionsAction::ShareGame => {
+                            let share =
+                                ShareCode::new(&self.table, self.session.seed, &self.session.plays);
+                            let code = share.encode();
+                            log_to_console!("share code: {}", code);
+                            self.overlay = Some(Overlay::ShareCode(ShareCodeView::new(&code)));
+                        }
+                        OptionsAction::EnterShareCode => {
+                            let entry =
+                                CrankEntry::new("Share code", CODE_CHARACTERS, SHARE_CODE_LIMIT);
+                            self.overlay = Some(Overlay::Entry(entry, EntryPurpose::ShareCode));
+                        }
+                        OptionsAction::EnterSeed => {
+                            let entry = CrankEntry::new("Deal number", DIGITS, SEED_DIGITS)
+                                .with_text(&format!("{}", self.session.seed));
+                            self.overlay = Some(Overlay::Entry(entry, EntryPurpose::Seed));
+                        }
+                        OptionsAction::ExportStats => {
+                            let lines = match export::export(&self.stats, &self.history) {
+                                Ok(()) => vec![
+                                    String::from("Saved to the data disk:"),
+                                    String::from(STATS_EXPORT_PATH),
+                                    String::from(HISTORY_EXPORT_PATH),
+                                ],
+                                Err(err) => {
+                                    log_to_console!("exporting stats failed: {}", err);
+                                    vec![String::from("Export failed")]
+                                }
+                            };
+                            self.overlay = Some(Overlay::Notice(lines));
+                        }
+                        OptionsAction::ShowSaveSlots => {
+                            self.leave_slot();
+                            let picker =
+                                SlotPicker::load(self.session.mode, &self.stats.slot_names);
+                            self.overlay = Some(Overlay::SaveSlots(picker));
+                        }
+                        OptionsAction::Undo => {
+                            self.overlay = None;
+                            self.undo_move();
+                        }
+                        OptionsAction::Redo => {
+                            self.overlay = None;
+                            self.redo_move();
+                        }
+                        OptionsAction::GiveUp => {
+                            self.overlay = None;
+                            if self.session.result_recorded {
+                                self.deal_new_game()?;
+                            } else {
+                                self.record_result(GameResult::Lost);
+                                if self.overlay.is_none() {
+                                    self.deal_new_game()?;
+                                }
+                            }
+                        }
+                        OptionsAction::NewGame => self.deal_new_game()?,
+                        OptionsAction::ReplayDeal => self.deal(self.session.seed, None),
+                        OptionsAction::DailyDeal => self.deal_daily(Self::today()?),
+                        OptionsAction::ShowCalendar => {
+                            self.overlay =
+                                Some(Overlay::Calendar(CalendarView::new(Self::today()?)))
+                        }
+                    }
+                    self.settings_changed(previous);
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = None;
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+                    options.previous();
+                } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+                    options.next();
+                }
+            }
+            Some(Overlay::CardBacks(picker)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    let previous = self.settings;
+                    self.settings.preferences.card_back = picker.selected;
+                    self.settings_changed(previous);
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonLeft) == PDButtons::kButtonLeft {
+                    picker.previous();
+                } else if (pushed & PDButtons::kButtonRight) == PDButtons::kButtonRight {
+                    picker.next();
+                }
+            }
+            Some(Overlay::Stats(page))
+                if (pushed & PDButtons::kButtonLeft) == PDButtons::kButtonLeft
+                    || (pushed & PDButtons::kButtonRight) == PDButtons::kButtonRight =>
+            {
+                *page = page.toggle();
+            }
+            Some(Overlay::Stats(_)) | Some(Overlay::Bankroll)
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA
+                    || (pushed & PDButtons::kButtonB) == PDButtons::kButtonB =>
+            {
+                self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+            }
+            Some(Overlay::History(browser)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    if let Some(seed) = browser
+                        .selected_entry(&self.history)
+                        .map(|entry| entry.seed)
+                    {
+                        self.deal(seed, None);
+                    }
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+                    browser.previous(&self.history);
+                } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+                    browser.next(&self.history);
+                }
+            }
+            Some(Overlay::Bookmarks(browser)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    if let Some(bookmark) = browser.selected_bookmark(&self.stats.bookmarks) {
+                        let (seed, mode) = (bookmark.seed, bookmark.mode);
+                        let previous = self.settings;
+                        self.settings.preferences.game_mode = mode;
+                        self.settings_changed(previous);
+                        self.deal(seed, None);
+                    }
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+                    browser.previous(&self.stats.bookmarks);
+                } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+                    browser.next(&self.stats.bookmarks);
+                }
+            }
+            Some(Overlay::Calendar(view)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    let day = view.selected_day();
+                    self.deal_daily(day);
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonLeft) == PDButtons::kButtonLeft {
+                    view.move_by(-1);
+                } else if (pushed & PDButtons::kButtonRight) == PDButtons::kButtonRight {
+                    view.move_by(1);
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+                    view.move_by(-7);
+                } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+                    view.move_by(7);
+                }
+            }
+            Some(Overlay::SaveSlots(picker)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    let mode = picker.selected_mode();
+                    self.switch_slot(mode)?;
+                } else if (pushed & PDButtons::kButtonRight) == PDButtons::kButtonRight {
+                    let mode = picker.selected_mode();
+                    let name = self.stats.slot_names.get(&mode).cloned();
+                    let entry =
+                        CrankEntry::new("Name this slot", NAME_CHARACTERS, SLOT_NAME_LENGTH)
+                            .with_text(name.as_deref().unwrap_or(""));
+                    self.overlay = Some(Overlay::Entry(entry, EntryPurpose::SlotName(mode)));
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+                    picker.previous();
+                } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+                    picker.next();
+                }
+            }
+            Some(Overlay::ShareCode(view)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA
+                    || (pushed & PDButtons::kButtonB) == PDButtons::kButtonB
+                {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+                    view.scroll_up();
+                } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+                    view.scroll_down();
+                } else if (pushed & PDButtons::kButtonLeft) == PDButtons::kButtonLeft
+                    || (pushed & PDButtons::kButtonRight) == PDButtons::kButtonRight
+                {
+                    view.toggle_qr();
+                }
+            }
+            Some(Overlay::Entry(entry, purpose)) => match entry.handle(pushed) {
+                EntryEvent::Editing => (),
+                EntryEvent::Confirmed(text) => {
+                    let purpose = *purpose;
+                    self.entry_confirmed(purpose, &text)?;
+                }
+                EntryEvent::Cancelled => {
+                    self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+                }
+            },
+            Some(Overlay::Notice(_))
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA
+                    || (pushed & PDButtons::kButtonB) == PDButtons::kButtonB =>
+            {
+                self.overlay = Some(Overlay::Options(OptionsMenu::default()));
+            }
+            Some(Overlay::NewGame) if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA => {
+                self.overlay = None;
+            }
+            Some(Overlay::ResumeGame(choice)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    match *choice {
+                        ResumeChoice::Resume => self.overlay = None,
+                        ResumeChoice::NewGame => self.deal_new_game()?,
+                    }
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = None;
+                } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp
+                    || (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown
+                {
+                    *choice = choice.toggle();
+                }
+            }
+            Some(Overlay::ConfirmRecycle) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    self.overlay = None;
+                    self.confirm();
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.overlay = None;
+                }
+            }
+            Some(Overlay::RaceHandoff) if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA => {
+                self.next_race_turn();
+            }
+            Some(Overlay::RaceResults) if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA => {
+                self.deal_new_game()?;
+            }
+            Some(Overlay::Won(_)) if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA => {
+                self.deal_new_game()?;
+            }
+            Some(Overlay::TimeUp(..)) if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA => {
+                self.deal_new_game()?;
+            }
+            Some(Overlay::OutOfMoves(_)) => {
+                if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+                    self.deal_new_game()?;
+                } else if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+                    self.deal(self.session.seed, self.session.daily);
+                }
+            }
+            Some(Overlay::Won(_)) if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB => {
+                let note = format!("won in {}", format_time(self.session.elapsed_ms));
+                self.bookmark_deal(Some(note));
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn entry_confirmed(&mut self, purpose: EntryPurpose, text: &str) -> Result<(), Error> {
+        match purpose {
+            EntryPurpose::Seed => match text.parse() {
+                Ok(seed) => self.deal(seed, None),
+                Err(_) => {
+                    self.overlay = Some(Overlay::Notice(vec![String::from("Not a deal number")]))
+                }
+            },
+            EntryPurpose::ShareCode => {
+                if let Err(err) = self.open_share_code(text) {
+                    self.overlay = Some(Overlay::Notice(vec![
+                        String::from("Can't open that code:"),
+                        format!("{}", err),
+                    ]));
+                }
+            }
+            EntryPurpose::SlotName(mode) => {
+                if text.is_empty() {
+                    self.stats.slot_names.remove(&mode);
+                } else {
+                    self.stats.slot_names.insert(mode, String::from(text));
+                }
+                self.save_stats();
+                let picker = SlotPicker::load(mode, &self.stats.slot_names);
+                self.overlay = Some(Overlay::SaveSlots(picker));
+            }
+        }
+        Ok(())
+    }
+
+    // Deals the code's game and plays it forward, as long as it's for the
+    // current game mode.
+    fn open_share_code(&mut self, code: &str) -> Result<(), Error> {
+        let share = ShareCode::decode(code).map_err(Error::msg)?;
+        if share.variant != self.settings.preferences.game_mode.variant() {
+            return Err(Error::msg("it's for another game"));
+        }
+        share.replay().map_err(Error::msg)?;
+        self.deal(share.seed, None);
+        self.table.rules = share.rules;
+        self.replaying = true;
+        let played = share
+            .plays
+            .iter()
+            .filter(|play| **play != Play::Setup)
+            .try_for_each(|play| self.execute(TableCommand::new(*play), false));
+        self.replaying = false;
+        self.start_autosave();
+        self.update_active_cards();
+        played.map_err(Error::msg)
+    }
+
+    fn start_attract(&mut self) {
+        match AttractMode::start(&mut self.table) {
+            Ok(attract) => {
+                self.attract = Some(attract);
+                self.reset_table_state();
+                self.overlay = Some(Overlay::Attract);
+            }
+            Err(err) => log_to_console!("demo game failed: {}", err),
+        }
+    }
+
+    fn stop_attract(&mut self) {
+        if let Some(attract) = self.attract.take() {
+            attract.stop(&mut self.table);
+            self.reset_table_state();
+            self.overlay = Some(Overlay::NewGame);
+        }
+    }
+
+    fn tick_attract(&mut self, delta_ms: usize) {
+        let ticked = match self.attract.as_mut() {
+            Some(attract) => attract.tick(&mut self.table, delta_ms),
+            None => return,
+        };
+        match ticked {
+            Ok(true) => self.update_active_cards(),
+            Ok(false) => (),
+            Err(err) => {
+                log_to_console!("demo game failed: {}", err);
+                self.stop_attract();
+            }
+        }
+    }
+
+    fn check_input(&mut self, delta_ms: usize) -> Result<(), Error> {
+        let system = System::get();
+        let (held, pushed, _) = system.get_button_state()?;
+        let input = RawInput {
+            held,
+            pushed,
+            crank_change: system.get_crank_change()?,
+            crank_docked: system.is_crank_docked()?,
+            delta_ms,
+        };
+        let (input, key_commands) = merge_keys(&input);
+        if input.is_idle() && key_commands.is_empty() {
+            self.idle_ms += delta_ms;
+        } else {
+            self.idle_ms = 0;
+            if self.attract.is_some() {
+                self.stop_attract();
+                return Ok(());
+            }
+        }
+        if self.idle_ms >= ATTRACT_IDLE_MS
+            && matches!(self.overlay, Some(Overlay::NewGame))
+            && self.waterfall.is_none()
+        {
+            self.start_attract();
+        }
+        let scheme = self.settings.preferences.control_scheme;
+        let repeat = self.settings.preferences.key_repeat;
+        if let Some(waterfall) = self.waterfall.as_mut() {
+            waterfall.crank(input.crank_change);
+            let pushed = self.input.overlay_buttons(scheme, repeat, &input);
+            if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA
+                || (pushed & PDButtons::kButtonB) == PDButtons::kButtonB
+            {
+                self.waterfall = None;
+            }
+            return Ok(());
+        }
+        if let Some(Overlay::Entry(entry, _)) = self.overlay.as_mut() {
+            entry.crank(input.crank_change);
+        }
+        if self.overlay.is_some() {
+            let pushed = self.input.overlay_buttons(scheme, repeat, &input);
+            return self.check_overlay_buttons(pushed);
+        }
+        let holding = self.table.cards_in_hand();
+        let auto_completing = self.phase.current() == GamePhase::AutoCompleting;
+        let commands = self.input.commands(scheme, repeat, &input, holding);
+        for command in commands.into_iter().chain(key_commands) {
+            if !auto_completing || command == Command::OpenMenu {
+                self.run_command(command);
+            }
+        }
+        Ok(())
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Next => self.selection.next(&self.table, &mut self.cursor),
+            Command::Previous => self.selection.previous(&self.table, &mut self.cursor),
+            Command::OpenMenu => self.overlay = Some(Overlay::Options(OptionsMenu::default())),
+            Command::Undo => self.undo_move(),
+            Command::Hint => {
+                self.telemetry.hint_used(&self.session);
+                self.show_hint();
+            }
+            Command::Cancel => {
+                if let Err(err) = self.cursor.return_hand(&mut self.table) {
+                    log_to_console!("{}", err);
+                }
+                self.update_active_cards();
+                self.practice_hint();
+            }
+            Command::Confirm => {
+                if self.recycle_needs_confirmation() {
+                    self.overlay = Some(Overlay::ConfirmRecycle);
+                } else {
+                    self.confirm();
+                }
+            }
+        }
+    }
+
+    fn legal_moves_cached(&mut self) -> &[Play] {
+        self.legal_moves.get(&self.table)
+    }
+
+    fn recycle_needs_confirmation(&mut self) -> bool {
+        if !self.settings.preferences.confirm_recycle
+            || self.table.cards_in_hand()
+            || self.cursor.source.stack != StackId::Stock
+            || !self.table.get_stack(StackId::Stock).is_empty()
+            || self.table.get_stack(StackId::Waste).is_empty()
+        {
+            return false;
+        }
+        let table = &self.table;
+        table.moves_iter().any(|play| match play {
+            Play::MoveCards(_, target) => matches!(
+                table.get_stack(target).stack_type,
+                StackType::Tableau | StackType::Foundation
+            ),
+            Play::Setup | Play::DrawFromStock | Play::RecycleWaste => false,
+        })
+    }
+
+    fn confirm(&mut self) {
+        let dropping = self.table.cards_in_hand();
+        let recycling = !dropping
+            && self.cursor.source.stack == StackId::Stock
+            && self.table.get_stack(StackId::Stock).is_empty();
+        let target = self.cursor.target;
+        let completed_before = self.completed_foundations();
+        match self.pick_up_or_drop() {
+            Ok(()) => {
+                let target_stack = self.table.get_stack(target);
+                if dropping {
+                    let cue = if target_stack.stack_type == StackType::Foundation {
+                        Cue::FoundationGrew(target_stack.len())
+                    } else {
+                        Cue::Accepted
+                    };
+                    self.play_cue_after(AnimationEvent::CardsLanded(target), cue);
+                } else if recycling {
+                    self.play_cue_after(AnimationEvent::FlipCompleted, Cue::Accepted);
+                } else {
+                    self.play_cue(Cue::Accepted);
+                }
+                let target_stack = self.table.get_stack(target);
+                if dropping {
+                    self.effects.trigger(
+                        TableEvent::CardsLanded(target),
+                        &self.settings.preferences.effects,
+                    );
+                    if let Some(count) = self.session.combo_count() {
+                        if target_stack.stack_type == StackType::Foundation {
+                            self.effects.trigger(
+                                TableEvent::Combo(target, count),
+                                &self.settings.preferences.effects,
+                            );
+                        }
+                    }
+                }
+                for stack_id in self.completed_foundations() {
+                    if !completed_before.contains(&stack_id) {
+                        self.effects.trigger(
+                            TableEvent::SuitCompleted(stack_id),
+                            &self.settings.preferences.effects,
+                        );
+                    }
+                }
+                if self.goal_met() {
+                    self.record_result(GameResult::Won);
+                }
+                self.practice_hint();
+            }
+            Err(err) => {
+                self.play_cue(Cue::Rejected);
+                self.effects
+                    .trigger(TableEvent::InvalidMove, &self.settings.preferences.effects);
+                self.effects.explain(err.explanation());
+                log_to_console!("{}", err)
+            }
+        }
+    }
+
+    // Drawing only gets a view of the table; state changes belong in update.
+    fn draw(&self, table: TableView<'_>, now_ms: usize) -> Result<(), Error> {
+        let cards_in_hand = table.cards_in_hand();
+        let graphics = Graphics::get();
+        graphics.clear(LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+        let offset = self.viewport.offset() + self.effects.shake_offset();
+
+        for (stack_id, view) in &self.views {
+            graphics.set_draw_offset(offset + self.effects.stack_offset(*stack_id))?;
+            if *stack_id == StackId::Stock && self.recycle_animation.is_some() {
+                view.draw_empty(&self.resources)?;
+            } else if *stack_id != StackId::Hand || cards_in_hand {
+                let stack = table.get_stack(*stack_id);
+                view.draw(&self.cursor.source, &stack, &self.resources)?;
+            }
+        }
+        graphics.set_draw_offset(offset)?;
+        self.effects.draw_flash(&self.views)?;
+        self.effects.draw_combo(&self.views)?;
+
+        if self.settings.preferences.show_playable && !cards_in_hand {
+            let rects: Vec<ScreenRect> = self
+                .playable
+                .iter()
+                .filter_map(|source| {
+                    let view = self.views.get(&source.stack)?;
+                    Some(view.card_rect(source.index))
+                })
+                .collect();
+            draw_playable_tabs(&rects)?;
+        }
+
+        if let Some(animation) = &self.recycle_animation {
+            animation.draw()?;
+        }
+
+        let redeals = match &self.recycle_animation {
+            Some(animation) => animation.redeals_shown(),
+            None => table.redeals_remaining(),
+        };
+        if let (Some(redeals), Some(stock_view), true) = (
+            redeals,
+            self.views.get(&StackId::Stock),
+            table.has_stack(StackId::Waste),
+        ) {
+            draw_redeals_badge(
+                stock_view.position + vec2(stock_view.metrics.width, 0),
+                redeals,
+            )?;
+        }
+
+        if table.stock_is_dead() {
+            if let Some(stock_view) = self.views.get(&StackId::Stock) {
+                stock_view.draw_dead()?;
+            }
+        }
+
+        if cards_in_hand {
+            let target = table.get_stack(self.cursor.target);
+            let target_view = self.views.get(&target.stack_id).expect("target_view");
+            let top_card_index = target.top_card_index();
+            let drop_rect = target_view.card_rect(top_card_index);
+            let hand = table.get_stack(StackId::Hand);
+            draw_ghost(&target_view.landing_rects(&target, hand.len()))?;
+            draw_pulse(drop_rect, now_ms)?;
+            let hand_view = self.views.get(&StackId::Hand).expect("hand_view");
+            draw_marquee(hand_view.run_rect(&hand, CardIndex::BOTTOM), now_ms)?;
+        } else {
+            let source = table.get_stack(self.cursor.source.stack);
+            let source_view = self.views.get(&source.stack_id).expect("source_view");
+            draw_marquee(
+                source_view.run_rect(&source, self.cursor.source.index),
+                now_ms,
+            )?;
+        }
+
+        if let Some(arrow) = &self.hint_arrow {
+            arrow.draw()?;
+        }
+
+        graphics.set_draw_offset(ScreenVector::zero())?;
+
+        if self.settings.preferences.show_hud {
+            draw_hud(&self.session, table, &self.undo)?;
+        }
+        self.effects
+            .draw_reason(layout::playfield_bottom(self.settings.preferences.show_hud))?;
+
+        match &self.overlay {
+            Some(Overlay::Options(options)) => options.draw(
+                &self.settings.rules,
+                &self.settings.preferences,
+                &self.stats,
+            )?,
+            Some(Overlay::Stats(StatsPage::Summary)) => {
+                let mut lines = self.stats.summary_lines(self.session.mode);
+                lines.push(String::from("Left/Right: lost game progress"));
+                draw_panel(&lines, None)?
+            }
+            Some(Overlay::Stats(StatsPage::Progress)) => {
+                draw_panel(&self.stats.mode(self.session.mode).progress_lines(), None)?
+            }
+            Some(Overlay::Bankroll) => draw_panel(&self.stats.vegas.ledger_lines(), None)?,
+            Some(Overlay::History(browser)) => browser.draw(&self.history)?,
+            Some(Overlay::Bookmarks(browser)) => browser.draw(&self.stats.bookmarks)?,
+            Some(Overlay::Calendar(view)) => {
+                view.draw(self.stats.daily_results(self.session.mode))?
+            }
+            Some(Overlay::SaveSlots(picker)) => picker.draw()?,
+            Some(Overlay::Notice(lines)) => draw_panel(lines, None)?,
+            Some(Overlay::ShareCode(view)) => view.draw()?,
+            Some(Overlay::Entry(entry, _)) => entry.draw()?,
+            Some(Overlay::CardBacks(picker)) => {
+                let preview = self
+                    .resources
+                    .backs
+                    .get(&picker.selected)
+                    .unwrap_or(&self.resources.empty);
+                picker.draw(preview)?
+            }
+            Some(Overlay::NewGame) => draw_panel(&self.new_game_lines(), None)?,
+            Some(Overlay::Attract) => AttractMode::draw()?,
+            Some(Overlay::ResumeGame(choice)) => {
+                let selected = match choice {
+                    ResumeChoice::Resume => 3,
+                    ResumeChoice::NewGame => 4,
+                };
+                let lines = self.resume_lines();
+                draw_panel(&lines, Some(selected))?;
+                Thumbnail::render(table).draw_below_panel(lines.len())?
+            }
+            Some(Overlay::ConfirmRecycle) => draw_panel(
+                &[
+                    String::from("You still have moves -"),
+                    String::from("recycle anyway?"),
+                    String::from("A: Recycle  B: Keep playing"),
+                ],
+                None,
+            )?,
+            Some(Overlay::Won(summary)) => draw_panel(&self.won_lines(summary), None)?,
+            Some(Overlay::TimeUp(summary, rank)) => {
+                draw_panel(&Self::time_up_lines(summary, *rank), None)?
+            }
+            Some(Overlay::OutOfMoves(summary)) => {
+                draw_panel(&Self::out_of_moves_lines(summary), None)?
+            }
+            Some(Overlay::RaceHandoff) => draw_panel(&self.race_handoff_lines(), None)?,
+            Some(Overlay::RaceResults) => draw_panel(
+                &self
+                    .session
+                    .race
+                    .as_ref()
+                    .map(Race::result_lines)
+                    .unwrap_or_default(),
+                None,
+            )?,
+            None => (),
+        }
+
+        Ok(())
+    }
+}
+
+impl Game for KlondikeGame {
+    fn update(
+        &mut self,
+        _playdate: &mut crankstart::Playdate,
+    ) -> core::result::Result<(), anyhow::Error> {
+        let now_ms = System::get().get_current_time_milliseconds()?;
+        let delta_ms = now_ms.saturating_sub(self.last_frame_ms);
+        #[cfg(not(test))]
+        for message in serial::take_messages() {
+            self.stop_attract();
+            self.run_serial(&message);
+        }
+        match (self.phase.current(), self.overlay.is_some()) {
+            (GamePhase::Dealing, false) => self.enter_phase(GamePhase::Playing),
+            (GamePhase::Playing, true) | (GamePhase::AutoCompleting, true) => {
+                self.enter_phase(GamePhase::Paused)
+            }
+            (GamePhase::Paused, false) => {
+                if let Err(err) = self.phase.resume() {
+                    log_to_console!("{}", err);
+                }
+            }
+            _ => (),
+        }
+        if self.phase.current().is_live() {
+            self.session.tick(delta_ms);
+        }
+        self.last_frame_ms = now_ms;
+
+        let mut reached = Vec::new();
+        if let Some(animation) = self.recycle_animation.as_mut() {
+            reached.extend(animation.tick(delta_ms));
+            if animation.is_finished() {
+                self.recycle_animation = None;
+            }
+        }
+        reached.extend(self.effects.tick(delta_ms));
+        for event in reached {
+            self.animation_reached(event);
+        }
+        if let Some(arrow) = self.hint_arrow.as_mut() {
+            arrow.tick(delta_ms);
+            if arrow.is_finished() {
+                if self.session.mode == GameMode::Practice {
+                    arrow.restart();
+                } else {
+                    self.hint_arrow = None;
+                }
+            }
+        }
+
+        self.check_input(delta_ms)?;
+        self.tick_attract(delta_ms);
+        if (self.session.out_of_time() || self.session.out_of_moves())
+            && self.phase.current().is_live()
+        {
+            self.play_cue(Cue::Rejected);
+            self.record_result(GameResult::Lost);
+        }
+        self.check_phase(delta_ms);
+
+        if let Some(waterfall) = self.waterfall.as_mut() {
+            waterfall.tick(delta_ms);
+            waterfall.draw(&self.resources)?;
+            if waterfall.is_finished() {
+                self.waterfall = None;
+            }
+            return Ok(());
+        }
+
+        let cards_in_hand = self.table.cards_in_hand();
+        if cards_in_hand {
+            let top_card_index = self.table.get_stack(self.cursor.target).top_card_index();
+            let position = self
+                .views
+                .get(&self.cursor.target)
+                .and_then(|view| {
+                    Some(view.get_card_position(top_card_index) + Vector2D::new(10, 10))
+                })
+                .unwrap_or_else(|| ScreenPoint::zero());
+            if let Some(in_hand) = self.views.get_mut(&StackId::Hand) {
+                in_hand.position = position;
+            }
+        }
+
+        let focus = if cards_in_hand {
+            let hand = self.table.get_stack(StackId::Hand);
+            self.views
+                .get(&StackId::Hand)
+                .map(|view| view.run_rect(&hand, CardIndex::BOTTOM))
+        } else {
+            let source = self.table.get_stack(self.cursor.source.stack);
+            self.views
+                .get(&source.stack_id)
+                .map(|view| view.run_rect(&source, self.cursor.source.index))
+        };
+        match (self.settings.preferences.scroll_tableaux, focus) {
+            (true, Some(focus)) => self.viewport.follow(
+                focus,
+                layout::playfield_bottom(self.settings.preferences.show_hud),
+            ),
+            (true, None) => (),
+            (false, _) => self.viewport = Viewport::default(),
+        }
+        self.update_tilt()?;
+
+        self.draw(self.table.view(), now_ms)
+    }
+}
+
+#[cfg(all(not(test), feature = "simulator"))]
+use crate::simulator::merge_keys;
+
+// Outside the simulator there are no key events to merge.
+#[cfg(not(all(not(test), feature = "simulator")))]
+fn merge_keys(input: &RawInput) -> (RawInput, Vec<Command>) {
+    (*input, Vec::new())
 }
 
-#[cfg(not(test))]
+#[cfg(all(not(test), not(feature = "simulator")))]
 crankstart_game!(KlondikeGame);
@@ -0,0 +1,55 @@
+use crate::klondike::{Play, Table};
+use alloc::vec::Vec;
+
+// Keyed on the stack generations, so any move, undo or pick-up refreshes it.
+// A table swapped in wholesale starts its generations over and has to be
+// invalidated by hand.
+#[derive(Debug, Default)]
+pub struct LegalMoveCache {
+    moves: Option<Vec<Play>>,
+    generations: Vec<u32>,
+}
+
+impl LegalMoveCache {
+    pub fn get(&mut self, table: &Table) -> &[Play] {
+        if self.generations[..] != *table.generations() {
+            self.moves = None;
+        }
+        let generations = &mut self.generations;
+        self.moves.get_or_insert_with(|| {
+            generations.clear();
+            generations.extend_from_slice(table.generations());
+            table.legal_moves()
+        })
+    }
+
+    pub fn invalidate(&mut self) {
+        self.moves = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::StackId;
+
+    #[test]
+    fn test_cache_refreshes_after_mutation() {
+        let mut table = Table::new(1);
+        let mut cache = LegalMoveCache::default();
+        let dealt = cache.get(&table).to_vec();
+        assert_eq!(table.legal_moves(), dealt);
+
+        table.deal_from_stock().expect("deal");
+        assert_eq!(table.legal_moves(), cache.get(&table));
+
+        table
+            .take_top_card_from_stack(StackId::Waste)
+            .expect("take");
+        assert_eq!(table.legal_moves(), cache.get(&table));
+
+        table = Table::new(2);
+        cache.invalidate();
+        assert_eq!(table.legal_moves(), cache.get(&table));
+    }
+}
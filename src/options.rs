@@ -0,0 +1,454 @@
+use crate::{
+    card_back::CardBack,
+    effects::EffectSettings,
+    input::{ControlScheme, RepeatSpeed},
+    klondike::RulesConfig,
+    layout::CardScale,
+    panel::draw_panel,
+    scoring::ScoringMode,
+    stats::{GameMode, Stats},
+    storage::Versioned,
+    undo::UndoLimit,
+};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+pub const PREFERENCES_PATH: &str = "preferences.json";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OptionItem {
+    GameMode,
+    Controls,
+    KeyRepeat,
+    ShowHud,
+    CardSize,
+    TallColumns,
+    TiltToPan,
+    CardBack,
+    SuitPatterns,
+    ShowPlayable,
+    ConfirmRecycle,
+    Sounds,
+    LandingThunk,
+    SuitFlash,
+    ShakeOnMistake,
+    ComboPopup,
+    Scoring,
+    UndoLimit,
+    UndoPenalty,
+    AnyCardOnEmptyTableau,
+    AllowFoundationPickUp,
+    MaxRecycles,
+    StrictWasteTop,
+    AbandonBreaksStreak,
+    Bankroll,
+    ResetBankroll,
+    Undo,
+    Redo,
+    Hint,
+    Statistics,
+    History,
+    BookmarkDeal,
+    Bookmarks,
+    ShareGame,
+    EnterShareCode,
+    EnterSeed,
+    ExportStats,
+    SaveSlots,
+    DailyDeal,
+    Calendar,
+    ReplayDeal,
+    GiveUp,
+    NewGame,
+}
+
+const OPTION_ITEMS: &[OptionItem] = &[
+    OptionItem::GameMode,
+    OptionItem::Controls,
+    OptionItem::KeyRepeat,
+    OptionItem::ShowHud,
+    OptionItem::CardSize,
+    OptionItem::TallColumns,
+    OptionItem::TiltToPan,
+    OptionItem::CardBack,
+    OptionItem::SuitPatterns,
+    OptionItem::ShowPlayable,
+    OptionItem::ConfirmRecycle,
+    OptionItem::Sounds,
+    OptionItem::LandingThunk,
+    OptionItem::SuitFlash,
+    OptionItem::ShakeOnMistake,
+    OptionItem::ComboPopup,
+    OptionItem::Scoring,
+    OptionItem::UndoLimit,
+    OptionItem::UndoPenalty,
+    OptionItem::AnyCardOnEmptyTableau,
+    OptionItem::AllowFoundationPickUp,
+    OptionItem::MaxRecycles,
+    OptionItem::StrictWasteTop,
+    OptionItem::AbandonBreaksStreak,
+    OptionItem::Bankroll,
+    OptionItem::ResetBankroll,
+    OptionItem::Undo,
+    OptionItem::Redo,
+    OptionItem::Hint,
+    OptionItem::Statistics,
+    OptionItem::History,
+    OptionItem::BookmarkDeal,
+    OptionItem::Bookmarks,
+    OptionItem::ShareGame,
+    OptionItem::EnterShareCode,
+    OptionItem::EnterSeed,
+    OptionItem::ExportStats,
+    OptionItem::SaveSlots,
+    OptionItem::DailyDeal,
+    OptionItem::Calendar,
+    OptionItem::ReplayDeal,
+    OptionItem::GiveUp,
+    OptionItem::NewGame,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptionsAction {
+    None,
+    StatsChanged,
+    ShowCardBacks,
+    Undo,
+    Redo,
+    ShowHint,
+    ShowStats,
+    ShowBankroll,
+    ShowHistory,
+    BookmarkDeal,
+    ShowBookmarks,
+    ShareGame,
+    EnterShareCode,
+    EnterSeed,
+    ExportStats,
+    ShowSaveSlots,
+    ReplayDeal,
+    DailyDeal,
+    ShowCalendar,
+    GiveUp,
+    NewGame,
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub game_mode: GameMode,
+    pub control_scheme: ControlScheme,
+    pub key_repeat: RepeatSpeed,
+    pub show_hud: bool,
+    pub card_scale: CardScale,
+    pub scroll_tableaux: bool,
+    pub tilt_to_pan: bool,
+    pub card_back: CardBack,
+    pub suit_patterns: bool,
+    pub show_playable: bool,
+    pub confirm_recycle: bool,
+    pub sounds: bool,
+    pub effects: EffectSettings,
+    pub scoring: ScoringMode,
+    pub undo_limit: UndoLimit,
+    pub undo_penalty: bool,
+}
+
+impl Versioned for Preferences {
+    const VERSION: u32 = 1;
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            game_mode: GameMode::Klondike,
+            control_scheme: ControlScheme::Standard,
+            key_repeat: RepeatSpeed::Normal,
+            show_hud: true,
+            card_scale: CardScale::Medium,
+            scroll_tableaux: false,
+            tilt_to_pan: false,
+            card_back: CardBack::Stripes,
+            suit_patterns: false,
+            show_playable: false,
+            confirm_recycle: true,
+            sounds: true,
+            effects: EffectSettings::default(),
+            scoring: ScoringMode::Standard,
+            undo_limit: UndoLimit::Unlimited,
+            undo_penalty: true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OptionsMenu {
+    selected: usize,
+}
+
+impl OptionsMenu {
+    pub fn previous(&mut self) {
+        if self.selected == 0 {
+            self.selected = OPTION_ITEMS.len() - 1;
+        } else {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % OPTION_ITEMS.len();
+    }
+
+    pub fn activate(
+        &mut self,
+        rules: &mut RulesConfig,
+        preferences: &mut Preferences,
+        stats: &mut Stats,
+    ) -> OptionsAction {
+        match OPTION_ITEMS[self.selected] {
+            OptionItem::GameMode => {
+                preferences.game_mode = preferences.game_mode.next();
+                OptionsAction::None
+            }
+            OptionItem::Controls => {
+                preferences.control_scheme = preferences.control_scheme.next();
+                OptionsAction::None
+            }
+            OptionItem::KeyRepeat => {
+                preferences.key_repeat = preferences.key_repeat.next();
+                OptionsAction::None
+            }
+            OptionItem::ShowHud => {
+                preferences.show_hud = !preferences.show_hud;
+                OptionsAction::None
+            }
+            OptionItem::CardSize => {
+                preferences.card_scale = preferences.card_scale.next();
+                OptionsAction::None
+            }
+            OptionItem::TallColumns => {
+                preferences.scroll_tableaux = !preferences.scroll_tableaux;
+                OptionsAction::None
+            }
+            OptionItem::TiltToPan => {
+                preferences.tilt_to_pan = !preferences.tilt_to_pan;
+                OptionsAction::None
+            }
+            OptionItem::CardBack => OptionsAction::ShowCardBacks,
+            OptionItem::SuitPatterns => {
+                preferences.suit_patterns = !preferences.suit_patterns;
+                OptionsAction::None
+            }
+            OptionItem::ShowPlayable => {
+                preferences.show_playable = !preferences.show_playable;
+                OptionsAction::None
+            }
+            OptionItem::ConfirmRecycle => {
+                preferences.confirm_recycle = !preferences.confirm_recycle;
+                OptionsAction::None
+            }
+            OptionItem::Sounds => {
+                preferences.sounds = !preferences.sounds;
+                OptionsAction::None
+            }
+            OptionItem::LandingThunk => {
+                preferences.effects.thunk = !preferences.effects.thunk;
+                OptionsAction::None
+            }
+            OptionItem::SuitFlash => {
+                preferences.effects.flash = !preferences.effects.flash;
+                OptionsAction::None
+            }
+            OptionItem::ShakeOnMistake => {
+                preferences.effects.shake = !preferences.effects.shake;
+                OptionsAction::None
+            }
+            OptionItem::ComboPopup => {
+                preferences.effects.combo = !preferences.effects.combo;
+                OptionsAction::None
+            }
+            OptionItem::Scoring => {
+                preferences.scoring = preferences.scoring.next();
+                OptionsAction::None
+            }
+            OptionItem::UndoLimit => {
+                preferences.undo_limit = preferences.undo_limit.next();
+                OptionsAction::None
+            }
+            OptionItem::UndoPenalty => {
+                preferences.undo_penalty = !preferences.undo_penalty;
+                OptionsAction::None
+            }
+            OptionItem::AnyCardOnEmptyTableau => {
+                rules.any_card_on_empty_tableau = !rules.any_card_on_empty_tableau;
+                OptionsAction::None
+            }
+            OptionItem::AllowFoundationPickUp => {
+                rules.allow_foundation_pick_up = !rules.allow_foundation_pick_up;
+                OptionsAction::None
+            }
+            OptionItem::MaxRecycles => {
+                rules.max_recycles = match rules.max_recycles {
+                    None => Some(2),
+                    Some(2) => Some(0),
+                    _ => None,
+                };
+                OptionsAction::None
+            }
+            OptionItem::StrictWasteTop => {
+                rules.strict_waste_top = !rules.strict_waste_top;
+                OptionsAction::None
+            }
+            OptionItem::AbandonBreaksStreak => {
+                stats.abandon_breaks_streak = !stats.abandon_breaks_streak;
+                OptionsAction::StatsChanged
+            }
+            OptionItem::Bankroll => OptionsAction::ShowBankroll,
+            OptionItem::ResetBankroll => {
+                stats.vegas.reset();
+                OptionsAction::StatsChanged
+            }
+            OptionItem::Undo => OptionsAction::Undo,
+            OptionItem::Redo => OptionsAction::Redo,
+            OptionItem::Hint => OptionsAction::ShowHint,
+            OptionItem::Statistics => OptionsAction::ShowStats,
+            OptionItem::History => OptionsAction::ShowHistory,
+            OptionItem::BookmarkDeal => OptionsAction::BookmarkDeal,
+            OptionItem::Bookmarks => OptionsAction::ShowBookmarks,
+            OptionItem::ShareGame => OptionsAction::ShareGame,
+            OptionItem::EnterShareCode => OptionsAction::EnterShareCode,
+            OptionItem::EnterSeed => OptionsAction::EnterSeed,
+            OptionItem::ExportStats => OptionsAction::ExportStats,
+            OptionItem::SaveSlots => OptionsAction::ShowSaveSlots,
+            OptionItem::DailyDeal => OptionsAction::DailyDeal,
+            OptionItem::Calendar => OptionsAction::ShowCalendar,
+            OptionItem::ReplayDeal => OptionsAction::ReplayDeal,
+            OptionItem::GiveUp => OptionsAction::GiveUp,
+            OptionItem::NewGame => OptionsAction::NewGame,
+        }
+    }
+
+    fn labels(rules: &RulesConfig, preferences: &Preferences, stats: &Stats) -> Vec<String> {
+        OPTION_ITEMS
+            .iter()
+            .map(|item| match item {
+                OptionItem::GameMode => format!("Game: {}", preferences.game_mode.name()),
+                OptionItem::Controls => {
+                    format!("Controls: {}", preferences.control_scheme.name())
+                }
+                OptionItem::KeyRepeat => format!("Key repeat: {}", preferences.key_repeat.name()),
+                OptionItem::ShowHud => format!("Status bar: {}", on_off(preferences.show_hud)),
+                OptionItem::CardSize => format!("Card size: {}", preferences.card_scale.name()),
+                OptionItem::TallColumns => {
+                    if preferences.scroll_tableaux {
+                        String::from("Tall columns: Scroll")
+                    } else {
+                        String::from("Tall columns: Compress")
+                    }
+                }
+                OptionItem::TiltToPan => {
+                    format!("Tilt to pan: {}", on_off(preferences.tilt_to_pan))
+                }
+                OptionItem::CardBack => format!("Card back: {}", preferences.card_back.name()),
+                OptionItem::SuitPatterns => {
+                    format!("Suit patterns: {}", on_off(preferences.suit_patterns))
+                }
+                OptionItem::ShowPlayable => {
+                    format!("Playable cards: {}", on_off(preferences.show_playable))
+                }
+                OptionItem::ConfirmRecycle => {
+                    format!("Confirm recycle: {}", on_off(preferences.confirm_recycle))
+                }
+                OptionItem::Sounds => format!("Sounds: {}", on_off(preferences.sounds)),
+                OptionItem::LandingThunk => {
+                    format!("Landing thunk: {}", on_off(preferences.effects.thunk))
+                }
+                OptionItem::SuitFlash => {
+                    format!("Flash on full suit: {}", on_off(preferences.effects.flash))
+                }
+                OptionItem::ShakeOnMistake => {
+                    format!("Shake on bad move: {}", on_off(preferences.effects.shake))
+                }
+                OptionItem::ComboPopup => {
+                    format!("Combo pop-ups: {}", on_off(preferences.effects.combo))
+                }
+                OptionItem::Scoring => format!("Scoring: {}", preferences.scoring.name()),
+                OptionItem::UndoLimit => format!("Undo: {}", preferences.undo_limit.label()),
+                OptionItem::UndoPenalty => {
+                    format!("Undo penalty: {}", on_off(preferences.undo_penalty))
+                }
+                OptionItem::AnyCardOnEmptyTableau => format!(
+                    "Any card on empty column: {}",
+                    on_off(rules.any_card_on_empty_tableau)
+                ),
+                OptionItem::AllowFoundationPickUp => format!(
+                    "Take from foundations: {}",
+                    on_off(rules.allow_foundation_pick_up)
+                ),
+                OptionItem::MaxRecycles => match rules.max_recycles {
+                    Some(max_recycles) => format!("Redeals: {}", max_recycles),
+                    None => String::from("Redeals: Unlimited"),
+                },
+                OptionItem::StrictWasteTop => {
+                    if rules.strict_waste_top {
+                        String::from("Waste: Top card only")
+                    } else {
+                        String::from("Waste: Any visible card")
+                    }
+                }
+                OptionItem::AbandonBreaksStreak => format!(
+                    "Quitting breaks streak: {}",
+                    on_off(stats.abandon_breaks_streak)
+                ),
+                OptionItem::Bankroll => String::from("Vegas bankroll"),
+                OptionItem::ResetBankroll => {
+                    format!("Reset Vegas bankroll (${})", stats.vegas.bankroll)
+                }
+                OptionItem::Undo => String::from("Undo last move"),
+                OptionItem::Redo => String::from("Redo undone move"),
+                OptionItem::Hint => String::from("Hint"),
+                OptionItem::Statistics => String::from("Statistics"),
+                OptionItem::History => String::from("Recent games"),
+                OptionItem::BookmarkDeal => String::from("Bookmark this deal"),
+                OptionItem::Bookmarks => String::from("Bookmarked deals"),
+                OptionItem::ShareGame => String::from("Share code for this game"),
+                OptionItem::EnterShareCode => String::from("Open a share code"),
+                OptionItem::EnterSeed => String::from("Play deal by number"),
+                OptionItem::ExportStats => String::from("Export statistics"),
+                OptionItem::SaveSlots => String::from("Games in progress"),
+                OptionItem::DailyDeal => String::from("Daily deal"),
+                OptionItem::Calendar => String::from("Daily calendar"),
+                OptionItem::ReplayDeal => String::from("Replay this deal"),
+                OptionItem::GiveUp => {
+                    if preferences.game_mode == GameMode::Race {
+                        String::from("End my turn")
+                    } else {
+                        String::from("Give up this deal")
+                    }
+                }
+                OptionItem::NewGame => String::from("New game"),
+            })
+            .collect()
+    }
+
+    pub fn draw(
+        &self,
+        rules: &RulesConfig,
+        preferences: &Preferences,
+        stats: &Stats,
+    ) -> Result<(), Error> {
+        draw_panel(
+            &Self::labels(rules, preferences, stats),
+            Some(self.selected),
+        )
+    }
+}
@@ -0,0 +1,71 @@
+use alloc::{format, string::String};
+use anyhow::Error;
+use core::ops::Range;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDColor, LCDSolidColor, LCD_COLUMNS, LCD_ROWS},
+};
+
+const PANEL_WIDTH: i32 = 300;
+pub const PANEL_MARGIN: i32 = 10;
+pub const PANEL_TOP: i32 = 20;
+pub const LINE_HEIGHT: i32 = 20;
+// As many lines as fit on screen below the top of the panel.
+pub const VISIBLE_LINES: usize =
+    ((LCD_ROWS as i32 - PANEL_TOP - PANEL_MARGIN * 2) / LINE_HEIGHT) as usize;
+
+pub fn panel_bottom(line_count: usize) -> i32 {
+    PANEL_TOP + line_count.min(VISIBLE_LINES) as i32 * LINE_HEIGHT + PANEL_MARGIN * 2
+}
+
+// The lines that fit, scrolled so the selected one stays in view.
+fn visible_lines(line_count: usize, selected: Option<usize>) -> Range<usize> {
+    let first = selected
+        .unwrap_or(0)
+        .saturating_sub(VISIBLE_LINES - 1)
+        .min(line_count.saturating_sub(VISIBLE_LINES));
+    first..line_count.min(first + VISIBLE_LINES)
+}
+
+pub fn draw_panel(lines: &[String], selected: Option<usize>) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    let height = panel_bottom(lines.len()) - PANEL_TOP;
+    let left = (LCD_COLUMNS as i32 - PANEL_WIDTH) / 2;
+    let rect = ScreenRect::new(
+        ScreenPoint::new(left, PANEL_TOP),
+        ScreenSize::new(PANEL_WIDTH, height),
+    );
+    graphics.fill_rect(rect, LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+    graphics.draw_rect(rect, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+    let mut position = ScreenPoint::new(left + PANEL_MARGIN, PANEL_TOP + PANEL_MARGIN);
+    let visible = visible_lines(lines.len(), selected);
+    for (index, line) in lines
+        .iter()
+        .enumerate()
+        .skip(visible.start)
+        .take(visible.len())
+    {
+        if selected.is_some() {
+            let marker = if Some(index) == selected { "> " } else { "  " };
+            graphics.draw_text(&format!("{}{}", marker, line), position)?;
+        } else {
+            graphics.draw_text(line, position)?;
+        }
+        position.y += LINE_HEIGHT;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_visible_lines_follow_selection() {
+        assert_eq!(0..4, visible_lines(4, Some(3)));
+        assert_eq!(0..VISIBLE_LINES, visible_lines(45, None));
+        assert_eq!(0..VISIBLE_LINES, visible_lines(45, Some(VISIBLE_LINES - 1)));
+        assert_eq!(1..VISIBLE_LINES + 1, visible_lines(45, Some(VISIBLE_LINES)));
+        assert_eq!(45 - VISIBLE_LINES..45, visible_lines(45, Some(44)));
+    }
+}
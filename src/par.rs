@@ -0,0 +1,321 @@
+use crate::{puzzles::puzzle, stats::GameMode};
+use serde::{Deserialize, Serialize};
+
+// Par for each deal in WINABLE_SEEDS: the solver's winning line with any
+// loops back to an earlier position cut out. Regenerate with
+// `klondike_solver --seed <seed> --par`. Sorted by seed.
+const PAR_MOVES: &[(u64, u16)] = &[
+    (322, 111),
+    (331, 110),
+    (341, 119),
+    (1004, 119),
+    (1006, 125),
+    (1013, 116),
+    (1016, 98),
+    (1018, 127),
+    (1021, 104),
+    (1023, 115),
+    (1026, 119),
+    (1032, 107),
+    (1038, 121),
+    (1040, 108),
+    (1041, 103),
+    (1042, 125),
+    (1044, 117),
+    (1055, 104),
+    (1056, 118),
+    (1058, 109),
+    (1061, 130),
+    (1064, 122),
+    (1079, 117),
+    (1082, 122),
+    (1088, 118),
+    (1093, 101),
+    (1095, 117),
+    (1104, 103),
+    (1113, 109),
+    (1118, 107),
+    (1119, 115),
+    (1120, 107),
+    (1125, 130),
+    (1132, 122),
+    (1138, 146),
+    (1145, 125),
+    (1146, 121),
+    (1165, 118),
+    (1172, 95),
+    (1176, 141),
+    (1177, 133),
+    (1178, 103),
+    (1180, 105),
+    (1181, 118),
+    (1191, 101),
+    (1193, 110),
+    (1195, 124),
+    (1203, 115),
+    (1207, 127),
+    (1208, 124),
+    (1211, 113),
+    (1215, 135),
+    (1219, 130),
+    (1222, 129),
+    (1225, 114),
+    (1227, 123),
+    (1229, 108),
+    (1231, 136),
+    (1239, 116),
+    (1240, 109),
+    (1244, 104),
+    (1245, 125),
+    (1247, 118),
+    (1248, 138),
+    (1249, 128),
+    (1252, 134),
+    (1256, 110),
+    (1265, 120),
+    (1272, 119),
+    (1273, 123),
+    (1274, 132),
+    (1275, 110),
+    (1277, 110),
+    (1278, 116),
+    (1291, 118),
+    (1293, 113),
+    (1295, 113),
+    (1306, 132),
+    (1307, 116),
+    (1308, 117),
+    (1312, 109),
+    (1318, 118),
+    (1320, 122),
+    (1329, 126),
+    (1330, 102),
+    (1336, 108),
+    (1341, 128),
+    (1354, 131),
+    (1357, 114),
+    (1360, 124),
+    (1362, 110),
+    (1366, 135),
+    (1367, 112),
+    (1369, 119),
+    (1373, 140),
+    (1378, 134),
+    (1379, 118),
+    (1380, 110),
+    (1382, 111),
+    (1385, 133),
+    (1386, 123),
+    (1397, 125),
+    (1409, 124),
+    (1415, 110),
+    (1418, 155),
+    (1428, 131),
+    (1434, 112),
+    (1435, 134),
+    (1441, 137),
+    (1447, 110),
+    (1448, 112),
+    (1451, 110),
+    (1455, 135),
+    (1458, 105),
+    (1460, 135),
+    (1463, 123),
+    (1466, 139),
+    (1476, 112),
+    (1477, 110),
+    (1478, 122),
+    (1481, 131),
+    (1497, 121),
+    (1499, 127),
+    (1512, 122),
+    (1515, 103),
+    (1518, 140),
+    (1520, 115),
+    (1527, 116),
+    (1532, 112),
+    (1536, 130),
+    (1541, 120),
+    (1542, 111),
+    (1545, 122),
+    (1556, 114),
+    (1557, 111),
+    (1561, 134),
+    (1562, 108),
+    (1573, 100),
+    (1581, 122),
+    (1585, 136),
+    (1592, 121),
+    (1599, 119),
+    (1600, 134),
+    (1602, 134),
+    (1616, 113),
+    (1621, 116),
+    (1622, 120),
+    (1623, 134),
+    (1624, 133),
+    (1625, 113),
+    (1627, 117),
+    (1628, 147),
+    (1631, 109),
+    (1632, 123),
+    (1639, 110),
+    (1642, 124),
+    (1653, 109),
+    (1657, 122),
+    (1659, 115),
+    (1660, 105),
+    (1668, 123),
+    (1678, 112),
+    (1679, 126),
+    (1682, 114),
+    (1683, 140),
+    (1684, 125),
+    (1694, 132),
+    (1712, 125),
+    (1714, 131),
+    (1731, 106),
+    (1748, 126),
+    (1750, 129),
+    (1753, 119),
+    (1754, 119),
+    (1758, 120),
+    (1762, 121),
+    (1764, 126),
+    (1777, 114),
+    (1778, 118),
+    (1791, 113),
+    (1808, 140),
+    (1812, 122),
+    (1813, 112),
+    (1816, 126),
+    (1825, 112),
+    (1846, 134),
+    (1851, 104),
+    (1860, 126),
+    (1864, 128),
+    (1866, 122),
+    (1867, 121),
+    (1869, 128),
+    (1872, 128),
+    (1876, 121),
+    (1882, 122),
+    (1884, 94),
+    (1886, 116),
+    (1889, 104),
+    (1891, 121),
+    (1893, 136),
+    (1896, 117),
+    (1901, 120),
+    (1902, 111),
+    (1904, 131),
+    (1906, 143),
+    (1916, 137),
+    (1920, 122),
+    (1921, 116),
+    (1922, 102),
+    (1927, 123),
+    (1929, 122),
+    (1934, 111),
+    (1935, 105),
+    (1943, 127),
+    (1944, 127),
+    (1946, 119),
+    (1954, 103),
+    (1955, 108),
+    (1956, 107),
+    (1959, 108),
+    (1968, 107),
+    (1972, 108),
+    (1978, 142),
+    (1987, 135),
+    (1990, 125),
+    (1993, 124),
+];
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Grade {
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Grade {
+    pub fn from_moves(moves: usize, par: usize) -> Self {
+        match moves * 100 / par.max(1) {
+            0..=100 => Grade::S,
+            101..=125 => Grade::A,
+            126..=150 => Grade::B,
+            151..=200 => Grade::C,
+            _ => Grade::D,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Grade::S => "S",
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::D => "D",
+        }
+    }
+}
+
+const MOVE_BUDGET_MARGIN_PERCENT: usize = 25;
+
+pub fn par_for(mode: GameMode, seed: u64) -> Option<usize> {
+    match mode {
+        GameMode::Klondike | GameMode::MoveChallenge => PAR_MOVES
+            .binary_search_by_key(&seed, |(seed, _)| *seed)
+            .ok()
+            .map(|index| PAR_MOVES[index].1 as usize),
+        GameMode::Westcliff
+        | GameMode::Easthaven
+        | GameMode::Yukon
+        | GameMode::Canfield
+        | GameMode::Agnes
+        | GameMode::FortyThieves
+        | GameMode::Spiderette
+        | GameMode::Practice
+        | GameMode::TimeAttack
+        | GameMode::Puzzle
+        | GameMode::Wildcard
+        | GameMode::Race => None,
+    }
+}
+
+pub fn move_budget(mode: GameMode, seed: u64) -> Option<usize> {
+    match mode {
+        GameMode::MoveChallenge => {
+            par_for(mode, seed).map(|par| par + par * MOVE_BUDGET_MARGIN_PERCENT / 100)
+        }
+        GameMode::Puzzle => Some(puzzle(seed).move_limit),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_par_lookup_and_grades() {
+        assert!(PAR_MOVES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        let par = par_for(GameMode::Klondike, 322).expect("par");
+        assert!(par > 0);
+        assert_eq!(None, par_for(GameMode::Practice, 322));
+        assert_eq!(None, par_for(GameMode::Klondike, 323));
+        assert_eq!(None, par_for(GameMode::Yukon, 322));
+        assert_eq!(None, move_budget(GameMode::Klondike, 322));
+        let budget = move_budget(GameMode::MoveChallenge, 322).expect("budget");
+        assert_eq!(par + par / 4, budget);
+
+        assert_eq!(Grade::S, Grade::from_moves(90, 100));
+        assert_eq!(Grade::A, Grade::from_moves(125, 100));
+        assert_eq!(Grade::B, Grade::from_moves(126, 100));
+        assert_eq!(Grade::D, Grade::from_moves(201, 100));
+    }
+}
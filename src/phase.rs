@@ -0,0 +1,127 @@
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GamePhase {
+    // A fresh deal is on the table and the new game panel is up.
+    Dealing,
+    Playing,
+    AutoCompleting,
+    Won,
+    // Nothing left to play: out of moves, out of time or no legal plays.
+    Stuck,
+    Paused,
+}
+
+impl GamePhase {
+    // Whether the game clock runs.
+    pub fn is_live(self) -> bool {
+        match self {
+            GamePhase::Playing | GamePhase::AutoCompleting => true,
+            GamePhase::Dealing | GamePhase::Won | GamePhase::Stuck | GamePhase::Paused => false,
+        }
+    }
+
+    fn can_enter(self, next: GamePhase) -> bool {
+        match (self, next) {
+            (_, GamePhase::Dealing) => true,
+            (GamePhase::Paused, GamePhase::Paused) => false,
+            (_, GamePhase::Paused) => true,
+            (GamePhase::Dealing, GamePhase::Playing) => true,
+            (GamePhase::Playing, GamePhase::AutoCompleting)
+            | (GamePhase::Playing, GamePhase::Won)
+            | (GamePhase::Playing, GamePhase::Stuck) => true,
+            (GamePhase::AutoCompleting, GamePhase::Playing)
+            | (GamePhase::AutoCompleting, GamePhase::Won) => true,
+            // Undo can back out of a dead end.
+            (GamePhase::Stuck, GamePhase::Playing) => true,
+            // A game can end from the menu, by giving up a race turn.
+            (GamePhase::Paused, GamePhase::Won) | (GamePhase::Paused, GamePhase::Stuck) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhaseError {
+    pub from: GamePhase,
+    pub to: GamePhase,
+}
+
+impl fmt::Display for PhaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "can't go from {:?} to {:?}",
+            self.from, self.to
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub struct PhaseMachine {
+    phase: GamePhase,
+    paused_from: GamePhase,
+}
+
+impl Default for PhaseMachine {
+    fn default() -> Self {
+        Self {
+            phase: GamePhase::Dealing,
+            paused_from: GamePhase::Dealing,
+        }
+    }
+}
+
+impl PhaseMachine {
+    pub fn current(&self) -> GamePhase {
+        self.phase
+    }
+
+    pub fn enter(&mut self, next: GamePhase) -> Result<(), PhaseError> {
+        if !self.phase.can_enter(next) {
+            return Err(PhaseError {
+                from: self.phase,
+                to: next,
+            });
+        }
+        if next == GamePhase::Paused {
+            self.paused_from = self.phase;
+        }
+        self.phase = next;
+        Ok(())
+    }
+
+    pub fn resume(&mut self) -> Result<(), PhaseError> {
+        if self.phase != GamePhase::Paused {
+            return Err(PhaseError {
+                from: self.phase,
+                to: self.paused_from,
+            });
+        }
+        self.phase = self.paused_from;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_phase_transitions() {
+        let mut phases = PhaseMachine::default();
+        assert!(phases.enter(GamePhase::Won).is_err());
+        assert_eq!(Ok(()), phases.enter(GamePhase::Playing));
+        assert_eq!(Ok(()), phases.enter(GamePhase::Paused));
+        assert!(!phases.current().is_live());
+        assert!(phases.enter(GamePhase::Paused).is_err());
+        assert_eq!(Ok(()), phases.resume());
+        assert_eq!(GamePhase::Playing, phases.current());
+        assert!(phases.resume().is_err());
+
+        assert_eq!(Ok(()), phases.enter(GamePhase::AutoCompleting));
+        assert!(phases.enter(GamePhase::Stuck).is_err());
+        assert_eq!(Ok(()), phases.enter(GamePhase::Won));
+        assert!(phases.enter(GamePhase::Playing).is_err());
+        assert_eq!(Ok(()), phases.enter(GamePhase::Dealing));
+    }
+}
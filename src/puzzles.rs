@@ -0,0 +1,142 @@
+use crate::klondike::{
+    KlondikeError, Rank, RulesConfig, StackId, StackType, Suit, Table, VariantId,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PuzzleGoal {
+    CardHome(Rank, Suit),
+    FoundationCards(usize),
+}
+
+#[derive(Debug)]
+pub struct Puzzle {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub goal: PuzzleGoal,
+    pub move_limit: usize,
+    pub layout: &'static [(StackId, &'static str)],
+}
+
+pub const PUZZLES: &[Puzzle] = &[
+    Puzzle {
+        name: "Dig for the seven",
+        description: "Get the 7 of diamonds home",
+        goal: PuzzleGoal::CardHome(Rank::Seven, Suit::Diamond),
+        move_limit: 4,
+        layout: &[
+            (StackId::Foundation1, "AD 2D 3D 4D 5D 6D"),
+            (StackId::Tableau1, "-8C -7D 9H"),
+            (StackId::Tableau2, "-4S TC 3S"),
+            (StackId::Tableau3, "-2H 4H"),
+        ],
+    },
+    Puzzle {
+        name: "Ten home",
+        description: "Get ten cards to the foundations",
+        goal: PuzzleGoal::FoundationCards(10),
+        move_limit: 5,
+        layout: &[
+            (StackId::Foundation1, "AC 2C 3C"),
+            (StackId::Foundation2, "AH 2H"),
+            (StackId::Foundation3, "AS"),
+            (StackId::Tableau1, "-KD 4C 3H"),
+            (StackId::Tableau2, "-QD -2S 5C"),
+        ],
+    },
+    Puzzle {
+        name: "Two-step",
+        description: "Get the 5 of spades home",
+        goal: PuzzleGoal::CardHome(Rank::Five, Suit::Spade),
+        move_limit: 5,
+        layout: &[
+            (StackId::Foundation1, "AS 2S 3S"),
+            (StackId::Tableau1, "-5S 7H"),
+            (StackId::Tableau2, "-4S 8C"),
+            (StackId::Tableau3, "-6D 9D"),
+        ],
+    },
+    Puzzle {
+        name: "Waste not",
+        description: "Get the 3 of diamonds home",
+        goal: PuzzleGoal::CardHome(Rank::Three, Suit::Diamond),
+        move_limit: 3,
+        layout: &[
+            (StackId::Foundation1, "AD 2D"),
+            (StackId::Waste, "3D 8S JC"),
+            (StackId::Tableau1, "-5C QD"),
+            (StackId::Tableau2, "-6C 9H"),
+        ],
+    },
+    Puzzle {
+        name: "Make room",
+        description: "Get the 2 of clubs home",
+        goal: PuzzleGoal::CardHome(Rank::Two, Suit::Club),
+        move_limit: 5,
+        layout: &[
+            (StackId::Tableau1, ""),
+            (StackId::Tableau2, "-AC KH"),
+            (StackId::Tableau3, "-2C QS"),
+            (StackId::Tableau4, "-3H JD"),
+            (StackId::Tableau5, "-4H TS"),
+            (StackId::Tableau6, "-5H 9D"),
+            (StackId::Tableau7, "-6H 8S"),
+        ],
+    },
+];
+
+pub fn puzzle(index: u64) -> &'static Puzzle {
+    &PUZZLES[index as usize % PUZZLES.len()]
+}
+
+impl Puzzle {
+    pub fn table(&self, rules: RulesConfig) -> Result<Table, KlondikeError> {
+        Table::from_layout(VariantId::Klondike, rules, self.layout)
+    }
+
+    pub fn goal_met(&self, table: &Table) -> bool {
+        match self.goal {
+            PuzzleGoal::CardHome(rank, suit) => table
+                .find_card(rank, suit)
+                .map(|source| table.get_stack(source.stack).stack_type == StackType::Foundation)
+                .unwrap_or(false),
+            PuzzleGoal::FoundationCards(count) => table.foundation_count() >= count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn solvable(puzzle: &Puzzle, table: &mut Table, moves_left: usize) -> bool {
+        if puzzle.goal_met(table) {
+            return true;
+        }
+        if moves_left == 0 {
+            return false;
+        }
+        let plays = table.plays_with_stock();
+        plays.iter().any(|play| match table.apply_move(*play) {
+            Ok(applied) => {
+                let solved = solvable(puzzle, table, moves_left - 1);
+                table.unapply(applied);
+                solved
+            }
+            Err(_) => false,
+        })
+    }
+
+    #[test]
+    fn test_puzzles_are_solvable() {
+        for puzzle in PUZZLES {
+            let mut table = puzzle.table(RulesConfig::default()).expect(puzzle.name);
+            assert_eq!(52, table.card_count(), "{}", puzzle.name);
+            assert!(!puzzle.goal_met(&table), "{}", puzzle.name);
+            assert!(
+                solvable(puzzle, &mut table, puzzle.move_limit),
+                "{}",
+                puzzle.name
+            );
+        }
+    }
+}
@@ -0,0 +1,506 @@
+use alloc::{vec, vec::Vec};
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDColor, LCDSolidColor},
+};
+
+// A QR code generator for share codes: alphanumeric mode, low error
+// correction, and only the versions that still fit the screen at two pixels
+// a module.
+const MAX_VERSION: usize = 23;
+const QUIET_ZONE: usize = 4;
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+// Per version, from the QR specification's tables for error correction
+// level L.
+const ECC_CODEWORDS_PER_BLOCK: [usize; 41] = [
+    0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30,
+    30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+];
+const ECC_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14,
+    15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+];
+// Format bits for level L.
+const ECC_LEVEL_BITS: u32 = 1;
+
+struct BitBuffer {
+    bits: Vec<bool>,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, width: usize) {
+        for shift in (0..width).rev() {
+            self.bits.push((value >> shift) & 1 != 0);
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (index, bit)| byte | ((*bit as u8) << (7 - index)))
+            })
+            .collect()
+    }
+}
+
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for shift in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1d);
+        z ^= ((y >> shift) & 1) * x;
+    }
+    z
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0; degree];
+    divisor[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for index in 0..degree {
+            divisor[index] = gf_multiply(divisor[index], root);
+            if index + 1 < degree {
+                divisor[index] ^= divisor[index + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0; divisor.len()];
+    for byte in data {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (value, coefficient) in remainder.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(*coefficient, factor);
+        }
+    }
+    remainder
+}
+
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * ECC_BLOCKS[version]
+}
+
+fn count_bits(version: usize) -> usize {
+    match version {
+        1..=9 => 9,
+        10..=26 => 11,
+        _ => 13,
+    }
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let mut positions = vec![6; count];
+    let mut position = version * 4 + 10;
+    for index in (1..count).rev() {
+        positions[index] = position;
+        position -= step;
+    }
+    positions
+}
+
+// Splits the data into blocks, appends each block's error correction and
+// interleaves them in the order they are read.
+fn add_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = ECC_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_len = raw_codewords / blocks;
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut rows = Vec::with_capacity(blocks);
+    let mut start = 0;
+    for block in 0..blocks {
+        let len = short_len - ecc_len + if block < short_blocks { 0 } else { 1 };
+        let mut row = data[start..start + len].to_vec();
+        start += len;
+        let ecc = reed_solomon_remainder(&row, &divisor);
+        if block < short_blocks {
+            row.push(0);
+        }
+        row.extend(ecc);
+        rows.push(row);
+    }
+    let mut codewords = Vec::with_capacity(raw_codewords);
+    for index in 0..short_len + 1 {
+        for (block, row) in rows.iter().enumerate() {
+            if index != short_len - ecc_len || block >= short_blocks {
+                codewords.push(row[index]);
+            }
+        }
+    }
+    codewords
+}
+
+fn masked(mask: u8, x: usize, y: usize) -> bool {
+    let value = match mask {
+        0 => (x + y) % 2,
+        1 => y % 2,
+        2 => x % 3,
+        3 => (x + y) % 3,
+        4 => (x / 3 + y / 2) % 2,
+        5 => x * y % 2 + x * y % 3,
+        6 => (x * y % 2 + x * y % 3) % 2,
+        _ => ((x + y) % 2 + x * y % 3) % 2,
+    };
+    value == 0
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl QrCode {
+    // None if the text has characters outside the alphanumeric set or is too
+    // long to show.
+    pub fn encode(text: &str) -> Option<Self> {
+        let values = text
+            .bytes()
+            .map(|byte| ALPHANUMERIC.iter().position(|letter| *letter == byte))
+            .collect::<Option<Vec<usize>>>()?;
+        let data_bits = 4 + values.len() / 2 * 11 + values.len() % 2 * 6;
+        let version = (1..=MAX_VERSION)
+            .find(|version| data_bits + count_bits(*version) <= data_codewords(*version) * 8)?;
+        let capacity = data_codewords(version) * 8;
+
+        let mut buffer = BitBuffer { bits: Vec::new() };
+        buffer.push(0b0010, 4);
+        buffer.push(values.len() as u32, count_bits(version));
+        for pair in values.chunks(2) {
+            match pair {
+                [first, second] => buffer.push((first * 45 + second) as u32, 11),
+                [single] => buffer.push(*single as u32, 6),
+                _ => (),
+            }
+        }
+        let terminator = (capacity - buffer.bits.len()).min(4);
+        buffer.push(0, terminator);
+        let padding = (8 - buffer.bits.len() % 8) % 8;
+        buffer.push(0, padding);
+        let mut data = buffer.bytes();
+        for pad in [0xec, 0x11].iter().cycle() {
+            if data.len() * 8 >= capacity {
+                break;
+            }
+            data.push(*pad);
+        }
+
+        let size = version * 4 + 17;
+        let mut code = Self {
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        };
+        code.draw_function_patterns(version);
+        code.draw_codewords(&add_error_correction(version, &data));
+        let mask = (0..8)
+            .min_by_key(|mask| {
+                let mut candidate = code.clone();
+                candidate.apply_mask(*mask);
+                candidate.draw_format_bits(*mask);
+                candidate.penalty()
+            })
+            .unwrap_or(0);
+        code.apply_mask(mask);
+        code.draw_format_bits(mask);
+        Some(code)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for index in 0..size {
+            self.set_function(6, index, index & 1 == 0);
+            self.set_function(index, 6, index & 1 == 0);
+        }
+        for (x, y) in &[(3, 3), (size - 4, 3), (3, size - 4)] {
+            self.draw_finder(*x as isize, *y as isize);
+        }
+        let positions = alignment_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, x) in positions.iter().enumerate() {
+            for (j, y) in positions.iter().enumerate() {
+                let corner = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+                if !corner {
+                    self.draw_alignment(*x, *y);
+                }
+            }
+        }
+        // Reserve the format areas now; the real bits go in after masking.
+        self.draw_format_bits(0);
+        if version >= 7 {
+            let mut remainder = version as u32;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1f25);
+            }
+            let bits = (version as u32) << 12 | remainder;
+            for index in 0..18 {
+                let dark = (bits >> index) & 1 != 0;
+                let a = size - 11 + index % 3;
+                let b = index / 3;
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_finder(&mut self, x: isize, y: isize) {
+        for dy in -4..=4isize {
+            for dx in -4..=4isize {
+                let (xx, yy) = (x + dx, y + dy);
+                if xx >= 0 && yy >= 0 && (xx as usize) < self.size && (yy as usize) < self.size {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in 0..5 {
+            for dx in 0..5 {
+                let distance = (dx as isize - 2).abs().max((dy as isize - 2).abs());
+                self.set_function(x + dx - 2, y + dy - 2, distance != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let data = ECC_LEVEL_BITS << 3 | u32::from(mask);
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |index: usize| (bits >> index) & 1 != 0;
+        let size = self.size;
+        for index in 0..6 {
+            self.set_function(8, index, bit(index));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for index in 9..15 {
+            self.set_function(14 - index, 8, bit(index));
+        }
+        for index in 0..8 {
+            self.set_function(size - 1 - index, 8, bit(index));
+        }
+        for index in 8..15 {
+            self.set_function(8, size - 15 + index, bit(index));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    // Fills the data modules in two-column strips, right to left, snaking up
+    // and down.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let total = codewords.len() * 8;
+        let mut index = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for column in 0..2 {
+                    let x = right - column;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y * size + x] && index < total {
+                        self.modules[y * size + x] =
+                            (codewords[index / 8] >> (7 - index % 8)) & 1 != 0;
+                        index += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if !self.function[y * self.size + x] && masked(mask, x, y) {
+                    self.modules[y * self.size + x] ^= true;
+                }
+            }
+        }
+    }
+
+    // The specification's four penalty rules: long runs, 2x2 blocks,
+    // finder-like patterns and an uneven dark balance.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        for transpose in &[false, true] {
+            let get = |a: usize, b: usize| {
+                if *transpose {
+                    self.get(b, a)
+                } else {
+                    self.get(a, b)
+                }
+            };
+            for line in 0..size {
+                let mut run = 1;
+                for position in 1..size {
+                    if get(position, line) == get(position - 1, line) {
+                        run += 1;
+                        if run == 5 {
+                            penalty += 3;
+                        } else if run > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        run = 1;
+                    }
+                }
+                for position in 0..size.saturating_sub(10) {
+                    let mut pattern = [false; 11];
+                    for (offset, dark) in pattern.iter_mut().enumerate() {
+                        *dark = get(position + offset, line);
+                    }
+                    let finder = [true, false, true, true, true, false, true];
+                    let light = [false; 4];
+                    if (pattern[..7] == finder && pattern[7..] == light)
+                        || (pattern[..4] == light && pattern[4..] == finder)
+                    {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.get(x, y);
+                if dark == self.get(x + 1, y)
+                    && dark == self.get(x, y + 1)
+                    && dark == self.get(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|dark| **dark).count();
+        let total = size * size;
+        let deviation = (dark * 20).max(total * 10) - (dark * 20).min(total * 10);
+        penalty + deviation / total * 10
+    }
+
+    // Draws the code as large as fits in a square of the given side, with
+    // its quiet zone, centered on a point.
+    pub fn draw(&self, center: ScreenPoint, side: i32) -> Result<(), Error> {
+        let graphics = Graphics::get();
+        let modules = (self.size + QUIET_ZONE * 2) as i32;
+        let scale = (side / modules).max(1);
+        let extent = modules * scale;
+        let origin = ScreenPoint::new(center.x - extent / 2, center.y - extent / 2);
+        graphics.fill_rect(
+            ScreenRect::new(origin, ScreenSize::new(extent, extent)),
+            LCDColor::Solid(LCDSolidColor::kColorWhite),
+        )?;
+        let inset = QUIET_ZONE as i32 * scale;
+        for y in 0..self.size {
+            let mut x = 0;
+            while x < self.size {
+                if !self.get(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < self.size && self.get(x, y) {
+                    x += 1;
+                }
+                let run = ScreenRect::new(
+                    ScreenPoint::new(
+                        origin.x + inset + start as i32 * scale,
+                        origin.y + inset + y as i32 * scale,
+                    ),
+                    ScreenSize::new((x - start) as i32 * scale, scale),
+                );
+                graphics.fill_rect(run, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reed_solomon() {
+        // HELLO WORLD at 1-M, the usual worked example.
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23],
+            reed_solomon_remainder(&data, &reed_solomon_divisor(10))
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(None, QrCode::encode("lower case"));
+        let code = QrCode::encode("HELLO WORLD").expect("code");
+        assert_eq!(21, code.size);
+        // Finder corners and the always-dark module.
+        assert!(code.get(0, 0) && code.get(20, 0) && code.get(0, 20));
+        assert!(!code.get(7, 0) && !code.get(1, 1));
+        assert!(code.get(8, 13));
+        assert_eq!(vec![6, 22, 38], alignment_positions(7));
+        assert_eq!(19, data_codewords(1));
+        assert_eq!(2_956, data_codewords(40));
+
+        let long: alloc::string::String = core::iter::repeat("ABCDE-").take(200).collect();
+        let code = QrCode::encode(&long).expect("long code");
+        assert!(code.size <= MAX_VERSION * 4 + 17);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    klondike::Table,
+    session::{format_time, Session},
+};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::cmp::{Ordering, Reverse};
+use serde::{Deserialize, Serialize};
+
+pub const RACE_PLAYERS: usize = 2;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TurnResult {
+    pub won: bool,
+    pub cards_home: usize,
+    pub elapsed_ms: usize,
+    pub moves: usize,
+}
+
+impl TurnResult {
+    pub fn new(session: &Session, table: &Table) -> Self {
+        Self {
+            won: table.winner(),
+            cards_home: table.foundation_count(),
+            elapsed_ms: session.elapsed_ms,
+            moves: session.moves,
+        }
+    }
+
+    // A win beats a loss, then more cards home, then the faster and shorter game.
+    fn rank(&self) -> (bool, usize, Reverse<usize>, Reverse<usize>) {
+        (
+            self.won,
+            self.cards_home,
+            Reverse(self.elapsed_ms),
+            Reverse(self.moves),
+        )
+    }
+
+    pub fn label(&self) -> String {
+        if self.won {
+            format!(
+                "won in {}, {} moves",
+                format_time(self.elapsed_ms),
+                self.moves
+            )
+        } else {
+            format!(
+                "{} cards home in {}",
+                self.cards_home,
+                format_time(self.elapsed_ms)
+            )
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Race {
+    pub turns: Vec<TurnResult>,
+}
+
+impl Race {
+    pub fn current_player(&self) -> usize {
+        self.turns.len() + 1
+    }
+
+    pub fn finish_turn(&mut self, result: TurnResult) {
+        if !self.is_over() {
+            self.turns.push(result);
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.turns.len() >= RACE_PLAYERS
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        match (self.turns.first(), self.turns.get(1)) {
+            (Some(first), Some(second)) => match first.rank().cmp(&second.rank()) {
+                Ordering::Greater => Some(1),
+                Ordering::Less => Some(2),
+                Ordering::Equal => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn turn_lines(&self) -> Vec<String> {
+        self.turns
+            .iter()
+            .enumerate()
+            .map(|(index, turn)| format!("Player {}: {}", index + 1, turn.label()))
+            .collect()
+    }
+
+    pub fn result_lines(&self) -> Vec<String> {
+        let mut lines = vec![String::from("Race results")];
+        lines.extend(self.turn_lines());
+        lines.push(match self.winner() {
+            Some(player) => format!("Player {} wins!", player),
+            None => String::from("It's a tie!"),
+        });
+        lines.push(String::from("Press A for a new race"));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_race_winner() {
+        let won = TurnResult {
+            won: true,
+            cards_home: 52,
+            elapsed_ms: 200_000,
+            moves: 150,
+        };
+        let mut race = Race::default();
+        race.finish_turn(won);
+        assert_eq!(2, race.current_player());
+        assert!(!race.is_over());
+        race.finish_turn(TurnResult {
+            elapsed_ms: 180_000,
+            ..won
+        });
+        assert!(race.is_over());
+        assert_eq!(Some(2), race.winner());
+        race.finish_turn(won);
+        assert_eq!(RACE_PLAYERS, race.turns.len());
+
+        race.turns[1] = TurnResult {
+            won: false,
+            cards_home: 40,
+            elapsed_ms: 100_000,
+            moves: 90,
+        };
+        assert_eq!(Some(1), race.winner());
+        race.turns[1] = won;
+        assert_eq!(None, race.winner());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::session::{format_time, Session};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::Error;
+
+pub const DAILY_SCORE_BOARD: &str = "daily-score";
+pub const DAILY_TIME_BOARD: &str = "daily-time";
+pub const TIME_ATTACK_BOARD: &str = "time-attack";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ranking {
+    pub rank: u32,
+    pub player: String,
+    pub value: u32,
+}
+
+pub trait ScoreboardBackend {
+    fn add_score(&mut self, board_id: &str, value: u32) -> Result<(), Error>;
+    fn rankings(&mut self, board_id: &str) -> Result<Vec<Ranking>, Error>;
+}
+
+pub fn submit_daily(backend: &mut dyn ScoreboardBackend, session: &Session) -> Result<(), Error> {
+    backend.add_score(DAILY_SCORE_BOARD, session.score.max(0) as u32)?;
+    backend.add_score(DAILY_TIME_BOARD, (session.elapsed_ms / 1000) as u32)?;
+    Ok(())
+}
+
+pub fn submit_time_attack(
+    backend: &mut dyn ScoreboardBackend,
+    session: &Session,
+) -> Result<(), Error> {
+    backend.add_score(TIME_ATTACK_BOARD, session.score.max(0) as u32)
+}
+
+pub fn ranking_lines(backend: &mut dyn ScoreboardBackend) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (title, board_id) in &[
+        ("Daily deal - Score", DAILY_SCORE_BOARD),
+        ("Daily deal - Time", DAILY_TIME_BOARD),
+        ("Time attack - Score", TIME_ATTACK_BOARD),
+    ] {
+        lines.push(String::from(*title));
+        match backend.rankings(board_id) {
+            Ok(rankings) => {
+                for ranking in rankings.iter().take(3) {
+                    let value = if *board_id == DAILY_TIME_BOARD {
+                        format_time(ranking.value as usize * 1000)
+                    } else {
+                        format!("{}", ranking.value)
+                    };
+                    lines.push(format!("{}. {} {}", ranking.rank, ranking.player, value));
+                }
+            }
+            Err(_) => lines.push(String::from("Rankings unavailable")),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scoring::ScoringMode, stats::GameMode};
+    use alloc::vec;
+
+    #[derive(Default)]
+    struct FakeBackend {
+        posted: Vec<(String, u32)>,
+    }
+
+    impl ScoreboardBackend for FakeBackend {
+        fn add_score(&mut self, board_id: &str, value: u32) -> Result<(), Error> {
+            self.posted.push((String::from(board_id), value));
+            Ok(())
+        }
+
+        fn rankings(&mut self, board_id: &str) -> Result<Vec<Ranking>, Error> {
+            if board_id == TIME_ATTACK_BOARD {
+                return Err(Error::msg("offline"));
+            }
+            Ok(vec![Ranking {
+                rank: 1,
+                player: String::from("crank"),
+                value: 95,
+            }])
+        }
+    }
+
+    #[test]
+    fn test_submit_scores() {
+        let mut backend = FakeBackend::default();
+        let mut session = Session::new(322, GameMode::Klondike, ScoringMode::Standard);
+        session.score = 640;
+        session.elapsed_ms = 185_400;
+        submit_daily(&mut backend, &session).expect("daily");
+        session.score = -20;
+        submit_time_attack(&mut backend, &session).expect("time attack");
+        assert_eq!(
+            vec![
+                (String::from(DAILY_SCORE_BOARD), 640),
+                (String::from(DAILY_TIME_BOARD), 185),
+                (String::from(TIME_ATTACK_BOARD), 0),
+            ],
+            backend.posted
+        );
+
+        let lines = ranking_lines(&mut backend);
+        assert_eq!("1. crank 95", lines[1]);
+        assert_eq!("1. crank 1:35", lines[3]);
+        assert_eq!("Rankings unavailable", lines[5]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::klondike::StackType;
+use alloc::{format, string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoreEvent {
+    MoveCards {
+        from: StackType,
+        to: StackType,
+    },
+    RevealCard,
+    RecycleWaste,
+    Undo,
+    Combo(usize),
+    TimePassed {
+        previous_ms: usize,
+        elapsed_ms: usize,
+    },
+    Won {
+        elapsed_ms: usize,
+    },
+}
+
+// Points actually gained or lost per kind of event, after any multiplier and
+// the zero floor, so the parts always add up to the score.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ScoreBreakdown {
+    pub cards: i32,
+    pub recycles: i32,
+    pub undos: i32,
+    pub time: i32,
+    pub combos: i32,
+    pub win_bonus: i32,
+}
+
+impl ScoreBreakdown {
+    pub fn add(&mut self, event: ScoreEvent, points: i32) {
+        match event {
+            ScoreEvent::MoveCards { .. } | ScoreEvent::RevealCard => self.cards += points,
+            ScoreEvent::RecycleWaste => self.recycles += points,
+            ScoreEvent::Undo => self.undos += points,
+            ScoreEvent::Combo(_) => self.combos += points,
+            ScoreEvent::TimePassed { .. } => self.time += points,
+            ScoreEvent::Won { .. } => self.win_bonus += points,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let parts = [
+            ("cards", self.cards),
+            ("stock", self.recycles),
+            ("undo", self.undos),
+            ("time", self.time),
+            ("combo", self.combos),
+            ("win", self.win_bonus),
+        ];
+        let parts: Vec<String> = parts
+            .iter()
+            .filter(|(_, points)| *points != 0)
+            .map(|(name, points)| format!("{:+} {}", points, name))
+            .collect();
+        parts.join(" ")
+    }
+}
+
+pub trait Scorer {
+    fn initial_score(&self) -> i32 {
+        0
+    }
+
+    fn allows_negative(&self) -> bool {
+        false
+    }
+
+    fn speed_bonus(&self) -> bool {
+        false
+    }
+
+    fn score(&self, event: ScoreEvent) -> i32;
+}
+
+// Foundation plays made soon after the previous one earn a multiplier that
+// decays linearly back to 100% over the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeedBonus {
+    pub window_ms: usize,
+    pub max_percent: usize,
+}
+
+impl SpeedBonus {
+    pub fn percent(&self, since_ms: usize) -> usize {
+        100 + (self.max_percent - 100) * self.window_ms.saturating_sub(since_ms) / self.window_ms
+    }
+}
+
+pub struct NoScoring;
+
+impl Scorer for NoScoring {
+    fn score(&self, _event: ScoreEvent) -> i32 {
+        0
+    }
+}
+
+pub struct StandardScoring;
+
+const STANDARD_TIME_STEP_MS: usize = 10_000;
+const STANDARD_MIN_BONUS_SECONDS: usize = 30;
+
+impl Scorer for StandardScoring {
+    fn speed_bonus(&self) -> bool {
+        true
+    }
+
+    fn score(&self, event: ScoreEvent) -> i32 {
+        match event {
+            ScoreEvent::MoveCards { from, to } => match (from, to) {
+                (StackType::Waste, StackType::Tableau) => 5,
+                (StackType::Waste, StackType::Foundation) => 10,
+                (StackType::Tableau, StackType::Foundation) => 10,
+                (StackType::Foundation, StackType::Tableau) => -15,
+                _ => 0,
+            },
+            ScoreEvent::RevealCard => 5,
+            ScoreEvent::RecycleWaste => -20,
+            ScoreEvent::Undo => -15,
+            ScoreEvent::Combo(count) => 5 * (count as i32 - 1),
+            ScoreEvent::TimePassed {
+                previous_ms,
+                elapsed_ms,
+            } => {
+                let steps =
+                    elapsed_ms / STANDARD_TIME_STEP_MS - previous_ms / STANDARD_TIME_STEP_MS;
+                -2 * steps as i32
+            }
+            ScoreEvent::Won { elapsed_ms } => {
+                let seconds = elapsed_ms / 1000;
+                if seconds >= STANDARD_MIN_BONUS_SECONDS {
+                    (700_000 / seconds) as i32
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+pub struct TimeAttackScoring;
+
+pub const TIME_ATTACK_LIMIT_MS: usize = 5 * 60 * 1000;
+
+impl Scorer for TimeAttackScoring {
+    fn speed_bonus(&self) -> bool {
+        true
+    }
+
+    fn score(&self, event: ScoreEvent) -> i32 {
+        match event {
+            ScoreEvent::MoveCards { from, to } => match (from, to) {
+                (StackType::Foundation, StackType::Foundation) => 0,
+                (_, StackType::Foundation) => 10,
+                (StackType::Foundation, _) => -10,
+                _ => 0,
+            },
+            ScoreEvent::Won { elapsed_ms } => {
+                (TIME_ATTACK_LIMIT_MS.saturating_sub(elapsed_ms) / 1000) as i32 * 2
+            }
+            ScoreEvent::Combo(count) => 5 * (count as i32 - 1),
+            ScoreEvent::RevealCard
+            | ScoreEvent::RecycleWaste
+            | ScoreEvent::Undo
+            | ScoreEvent::TimePassed { .. } => 0,
+        }
+    }
+}
+
+pub struct VegasScoring;
+
+impl Scorer for VegasScoring {
+    fn initial_score(&self) -> i32 {
+        -52
+    }
+
+    fn allows_negative(&self) -> bool {
+        true
+    }
+
+    fn score(&self, event: ScoreEvent) -> i32 {
+        match event {
+            ScoreEvent::MoveCards { from, to } => match (from, to) {
+                (StackType::Foundation, StackType::Foundation) => 0,
+                (_, StackType::Foundation) => 5,
+                (StackType::Foundation, _) => -5,
+                _ => 0,
+            },
+            ScoreEvent::Undo => -5,
+            ScoreEvent::RevealCard
+            | ScoreEvent::RecycleWaste
+            | ScoreEvent::Combo(_)
+            | ScoreEvent::TimePassed { .. }
+            | ScoreEvent::Won { .. } => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ScoringMode {
+    None,
+    Standard,
+    Vegas,
+    TimeAttack,
+}
+
+impl ScoringMode {
+    pub fn scorer(self) -> &'static dyn Scorer {
+        match self {
+            ScoringMode::None => &NoScoring,
+            ScoringMode::Standard => &StandardScoring,
+            ScoringMode::Vegas => &VegasScoring,
+            ScoringMode::TimeAttack => &TimeAttackScoring,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ScoringMode::None => ScoringMode::Standard,
+            ScoringMode::Standard => ScoringMode::Vegas,
+            ScoringMode::Vegas | ScoringMode::TimeAttack => ScoringMode::None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoringMode::None => "None",
+            ScoringMode::Standard => "Standard",
+            ScoringMode::Vegas => "Vegas",
+            ScoringMode::TimeAttack => "Time attack",
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    cursor::Cursor,
+    klondike::{ActiveCardIterator, CardLocation, KlondikeError, StackId, Table},
+};
+use alloc::vec::Vec;
+use core::iter;
+use enum_iterator::IntoEnumIterator;
+
+// The cards the cursor can pick up and the stacks it can drop a hand on, in
+// the order previous and next step through them. Both the Playdate and the
+// host frontend drive the cursor through this.
+#[derive(Debug, Default)]
+pub struct Selection {
+    active_cards: Vec<CardLocation>,
+    source_index: usize,
+    targets: Vec<StackId>,
+    target_index: usize,
+}
+
+impl Selection {
+    pub fn new(table: &Table, cursor: &mut Cursor) -> Self {
+        let mut selection = Self::default();
+        selection.update_active_cards(table, cursor);
+        selection
+    }
+
+    pub fn update_active_cards(&mut self, table: &Table, cursor: &mut Cursor) {
+        self.active_cards = iter::once(CardLocation::stock())
+            .chain(ActiveCardIterator::new(table))
+            .collect();
+        if let Some(source_index) = self
+            .active_cards
+            .iter()
+            .position(|source| *source == cursor.source)
+        {
+            self.source_index = source_index;
+        } else {
+            self.source_index = self.source_index.min(self.active_cards.len() - 1);
+            cursor.source = self.active_cards[self.source_index];
+        }
+    }
+
+    pub fn update_targets(&mut self, table: &Table, cursor: &Cursor) {
+        let origin = table.hand_origin.map(|origin| origin.stack);
+
+        self.targets = StackId::into_enum_iter()
+            .filter(|stack_id| Some(*stack_id) == origin || table.stack_can_accept_hand(*stack_id))
+            .collect();
+        let target = cursor.target;
+        self.target_index = self
+            .targets
+            .iter()
+            .position(|stack_id| *stack_id == target)
+            .unwrap_or(0);
+    }
+
+    pub fn previous(&mut self, table: &Table, cursor: &mut Cursor) {
+        if table.cards_in_hand() {
+            if self.target_index == 0 {
+                self.target_index = self.targets.len().saturating_sub(1);
+            } else {
+                self.target_index -= 1;
+            }
+            cursor.target = self.targets[self.target_index];
+        } else {
+            if self.source_index == 0 {
+                self.source_index = self.active_cards.len().saturating_sub(1);
+            } else {
+                self.source_index -= 1;
+            }
+            cursor.source = self.active_cards[self.source_index];
+        }
+    }
+
+    pub fn next(&mut self, table: &Table, cursor: &mut Cursor) {
+        if table.cards_in_hand() {
+            let max_index = self.targets.len().saturating_sub(1);
+            if self.target_index == max_index {
+                self.target_index = 0;
+            } else {
+                self.target_index += 1;
+            }
+            cursor.target = self.targets[self.target_index];
+        } else if !self.active_cards.is_empty() {
+            let max_index = self.active_cards.len().saturating_sub(1);
+            if self.source_index >= max_index {
+                self.source_index = 0;
+            } else {
+                self.source_index += 1;
+            }
+            cursor.source = self.active_cards[self.source_index];
+        }
+    }
+
+    // Takes the cards under the cursor into the hand and aims it at the best
+    // drop. The stock deals rather than picks up, which each frontend does its
+    // own way, so it's left alone here.
+    pub fn pick_up(&mut self, table: &mut Table, cursor: &mut Cursor) -> Result<(), KlondikeError> {
+        let source = cursor.source;
+        match source.stack {
+            StackId::Stock | StackId::Hand => return Ok(()),
+            StackId::Reserve1
+            | StackId::Reserve2
+            | StackId::Reserve3
+            | StackId::Reserve4
+            | StackId::Reserve5
+            | StackId::Reserve6
+            | StackId::Reserve7
+            | StackId::Foundation1
+            | StackId::Foundation2
+            | StackId::Foundation3
+            | StackId::Foundation4
+            | StackId::Foundation5
+            | StackId::Foundation6
+            | StackId::Foundation7
+            | StackId::Foundation8 => table.take_top_card_from_stack(source.stack)?,
+            StackId::Waste
+            | StackId::Tableau1
+            | StackId::Tableau2
+            | StackId::Tableau3
+            | StackId::Tableau4
+            | StackId::Tableau5
+            | StackId::Tableau6
+            | StackId::Tableau7
+            | StackId::Tableau8
+            | StackId::Tableau9
+            | StackId::Tableau10 => {
+                table.take_selected_cards_from_stack(source.stack, source.index)?
+            }
+        }
+        cursor.hand_taken(table);
+        self.update_targets(table, cursor);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_SEED: u64 = 326;
+
+    #[test]
+    fn test_selection_wraps_and_targets_hand() {
+        let mut table = Table::new(TEST_SEED);
+        let mut cursor = Cursor::new(&table);
+        let mut selection = Selection::new(&table, &mut cursor);
+        assert_eq!(CardLocation::stock(), cursor.source);
+        selection.previous(&table, &mut cursor);
+        assert_eq!(selection.active_cards.last(), Some(&cursor.source));
+        selection.next(&table, &mut cursor);
+        assert_eq!(CardLocation::stock(), cursor.source);
+
+        selection.next(&table, &mut cursor);
+        let source = cursor.source;
+        selection.pick_up(&mut table, &mut cursor).expect("pick_up");
+        assert!(table.cards_in_hand());
+        assert!(selection.targets.contains(&source.stack));
+        assert_eq!(selection.targets[selection.target_index], cursor.target);
+        for _ in 0..selection.targets.len() {
+            selection.next(&table, &mut cursor);
+            assert!(selection.targets.contains(&cursor.target));
+        }
+        assert_eq!(selection.targets[selection.target_index], cursor.target);
+    }
+}
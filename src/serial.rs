@@ -0,0 +1,138 @@
+use crate::klondike::{CardIndex, CardLocation, Play, StackId, Table};
+use alloc::{format, string::String, vec::Vec};
+use anyhow::Error;
+use enum_iterator::IntoEnumIterator;
+
+// Commands a host script can send over the serial port, one per message:
+//   table                          list each stack in card notation
+//   share                          print the game's share code
+//   draw, recycle                  play the stock
+//   move <stack> <index> <stack>   move cards, index counting from the bottom
+//   deal [seed]                    deal a new game, random without a seed
+// Replies go back as console lines, which the device also sends over serial.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SerialCommand {
+    Table,
+    Share,
+    Play(Play),
+    Deal(Option<u64>),
+}
+
+fn stack_id(name: &str) -> Result<StackId, Error> {
+    StackId::into_enum_iter()
+        .find(|stack_id| format!("{:?}", stack_id).eq_ignore_ascii_case(name))
+        .ok_or_else(|| Error::msg(format!("unknown stack {}", name)))
+}
+
+fn number<T: core::str::FromStr>(text: &str) -> Result<T, Error> {
+    text.parse()
+        .map_err(|_| Error::msg(format!("bad number {}", text)))
+}
+
+impl SerialCommand {
+    pub fn parse(message: &str) -> Result<Self, Error> {
+        let words: Vec<&str> = message.split_whitespace().collect();
+        match words.as_slice() {
+            ["table"] => Ok(SerialCommand::Table),
+            ["share"] => Ok(SerialCommand::Share),
+            ["draw"] => Ok(SerialCommand::Play(Play::DrawFromStock)),
+            ["recycle"] => Ok(SerialCommand::Play(Play::RecycleWaste)),
+            ["move", source, index, target] => Ok(SerialCommand::Play(Play::MoveCards(
+                CardLocation::new(stack_id(source)?, CardIndex::new(number(index)?)),
+                stack_id(target)?,
+            ))),
+            ["deal"] => Ok(SerialCommand::Deal(None)),
+            ["deal", seed] => Ok(SerialCommand::Deal(Some(number(seed)?))),
+            _ => Err(Error::msg(format!("unknown command {}", message.trim()))),
+        }
+    }
+}
+
+// One line per stack in the notation Table::from_layout reads back.
+pub fn table_notation(table: &Table) -> Vec<String> {
+    StackId::into_enum_iter()
+        .filter(|stack_id| table.has_stack(*stack_id))
+        .map(|stack_id| {
+            let mut line = format!("{:?}", stack_id);
+            for card in table.get_stack(stack_id).cards() {
+                line.push(' ');
+                line.push_str(&card.to_notation());
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(not(test))]
+mod port {
+    use alloc::{string::String, vec::Vec};
+    use anyhow::Error;
+    use core::ptr::addr_of_mut;
+    use crankstart::system::System;
+    use crankstart_sys::ctypes::c_char;
+
+    static mut MESSAGES: Option<Vec<String>> = None;
+
+    unsafe extern "C" fn serial_message(data: *const c_char) {
+        if data.is_null() {
+            return;
+        }
+        let mut bytes = Vec::new();
+        let mut next = data;
+        while *next != 0 {
+            bytes.push(*next as u8);
+            next = next.add(1);
+        }
+        (*addr_of_mut!(MESSAGES))
+            .get_or_insert_with(Vec::new)
+            .push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    pub fn listen() -> Result<(), Error> {
+        System::get().set_serial_message_callback(Some(serial_message))
+    }
+
+    // Messages arrive between frames; the game handles them at the start of
+    // the next update.
+    pub fn take_messages() -> Vec<String> {
+        unsafe { (*addr_of_mut!(MESSAGES)).take().unwrap_or_default() }
+    }
+}
+
+#[cfg(not(test))]
+pub use port::{listen, take_messages};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serial_commands() {
+        assert_eq!(
+            SerialCommand::Play(Play::MoveCards(
+                CardLocation::new(StackId::Tableau3, CardIndex::new(2)),
+                StackId::Foundation1
+            )),
+            SerialCommand::parse("move tableau3 2 Foundation1\n").expect("move")
+        );
+        assert_eq!(
+            SerialCommand::Deal(Some(322)),
+            SerialCommand::parse("deal 322").expect("deal")
+        );
+        assert!(SerialCommand::parse("move waste x tableau1").is_err());
+        assert!(SerialCommand::parse("shuffle").is_err());
+
+        let table = Table::new(322);
+        let lines = table_notation(&table);
+        assert!(lines.contains(&String::from("Tableau2 -8D 5D")));
+        let layout: Vec<(StackId, &str)> = lines
+            .iter()
+            .filter_map(|line| {
+                let (name, cards) = line.split_at(line.find(' ').unwrap_or(line.len()));
+                Some((stack_id(name).ok()?, cards))
+            })
+            .collect();
+        let rebuilt = Table::from_layout(table.variant, table.rules, &layout).expect("layout");
+        assert_eq!(table_notation(&rebuilt), lines);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{
+    klondike::{Play, StackType},
+    par::move_budget,
+    race::Race,
+    scoring::{ScoreBreakdown, ScoreEvent, ScoringMode},
+    stats::GameMode,
+    wildcard::Modifier,
+};
+use alloc::{format, string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+pub fn format_time(ms: usize) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Session {
+    pub seed: u64,
+    pub daily: Option<u32>,
+    pub mode: GameMode,
+    pub scoring: ScoringMode,
+    pub moves: usize,
+    pub undos: usize,
+    pub score: i32,
+    pub elapsed_ms: usize,
+    pub started: bool,
+    pub result_recorded: bool,
+    #[serde(default)]
+    pub plays: Vec<Play>,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    #[serde(default)]
+    pub race: Option<Race>,
+    #[serde(default)]
+    pub last_foundation_ms: Option<usize>,
+    #[serde(default)]
+    pub combo: usize,
+    #[serde(default)]
+    pub breakdown: ScoreBreakdown,
+    // The daily was dealt on its own day rather than picked from the
+    // calendar. It stays set past midnight so the old day's deal can still be
+    // finished and posted.
+    #[serde(default)]
+    pub live_daily: bool,
+    // Dealt with any card allowed on an empty column, read from the rules
+    // when the game starts since changing them only affects the next deal.
+    #[serde(default)]
+    pub relaxed: bool,
+}
+
+impl Session {
+    pub fn new(seed: u64, mode: GameMode, scoring: ScoringMode) -> Self {
+        Self {
+            seed,
+            daily: None,
+            mode,
+            scoring,
+            moves: 0,
+            undos: 0,
+            score: scoring.scorer().initial_score(),
+            elapsed_ms: 0,
+            started: false,
+            result_recorded: false,
+            plays: Vec::new(),
+            modifiers: Vec::new(),
+            race: None,
+            last_foundation_ms: None,
+            combo: 0,
+            breakdown: ScoreBreakdown::default(),
+            live_daily: false,
+            relaxed: false,
+        }
+    }
+
+    pub fn tick(&mut self, delta_ms: usize) {
+        if self.started && !self.result_recorded {
+            let previous_ms = self.elapsed_ms;
+            self.elapsed_ms += delta_ms;
+            if let Some(limit_ms) = self.mode.time_limit_ms() {
+                self.elapsed_ms = self.elapsed_ms.min(limit_ms);
+            }
+            self.apply(ScoreEvent::TimePassed {
+                previous_ms,
+                elapsed_ms: self.elapsed_ms,
+            });
+        }
+    }
+
+    pub fn remaining_ms(&self) -> Option<usize> {
+        self.mode
+            .time_limit_ms()
+            .map(|limit_ms| limit_ms.saturating_sub(self.elapsed_ms))
+    }
+
+    pub fn out_of_time(&self) -> bool {
+        self.remaining_ms() == Some(0)
+    }
+
+    pub fn moves_remaining(&self) -> Option<usize> {
+        move_budget(self.mode, self.seed).map(|budget| budget.saturating_sub(self.moves))
+    }
+
+    pub fn out_of_moves(&self) -> bool {
+        self.moves_remaining() == Some(0)
+    }
+
+    pub fn clock_ms(&self) -> usize {
+        self.remaining_ms().unwrap_or(self.elapsed_ms)
+    }
+
+    pub fn speed_percent(&self) -> Option<usize> {
+        if !self.scoring.scorer().speed_bonus() {
+            return None;
+        }
+        let bonus = self.mode.speed_bonus()?;
+        let since_ms = self.elapsed_ms.saturating_sub(self.last_foundation_ms?);
+        Some(bonus.percent(since_ms))
+    }
+
+    pub fn apply(&mut self, event: ScoreEvent) {
+        let scorer = self.scoring.scorer();
+        let mut points = scorer.score(event);
+        if let ScoreEvent::MoveCards {
+            from,
+            to: StackType::Foundation,
+        } = event
+        {
+            if from != StackType::Foundation && points > 0 {
+                if let Some(percent) = self.speed_percent() {
+                    points = points * percent as i32 / 100;
+                }
+                self.last_foundation_ms = Some(self.elapsed_ms);
+            }
+        }
+        let before = self.score;
+        self.score += points;
+        if !scorer.allows_negative() {
+            self.score = self.score.max(0);
+        }
+        self.breakdown.add(event, self.score - before);
+    }
+
+    pub fn record_deal(&mut self, recycled: bool) {
+        self.started = true;
+        self.moves += 1;
+        self.combo = 0;
+        if recycled {
+            self.apply(ScoreEvent::RecycleWaste);
+        }
+    }
+
+    pub fn record_move(&mut self, from: StackType, to: StackType, revealed: bool) {
+        self.started = true;
+        self.moves += 1;
+        self.apply(ScoreEvent::MoveCards { from, to });
+        if revealed {
+            self.apply(ScoreEvent::RevealCard);
+        }
+        // Only dealing from the stock breaks a run of foundation plays.
+        if to == StackType::Foundation && from != StackType::Foundation {
+            self.combo += 1;
+            if self.combo > 1 {
+                self.apply(ScoreEvent::Combo(self.combo));
+            }
+        }
+    }
+
+    pub fn combo_count(&self) -> Option<usize> {
+        if self.combo > 1 {
+            Some(self.combo)
+        } else {
+            None
+        }
+    }
+
+    pub fn record_undo(&mut self, score: i32, penalty: bool) {
+        self.moves += 1;
+        self.undos += 1;
+        self.combo = 0;
+        self.plays.pop();
+        self.breakdown.undos += score - self.score;
+        self.score = score;
+        if penalty {
+            self.apply(ScoreEvent::Undo);
+        }
+    }
+
+    pub fn record_win(&mut self) {
+        self.apply(ScoreEvent::Won {
+            elapsed_ms: self.elapsed_ms,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scoring_modes() {
+        let mut standard = Session::new(322, GameMode::Klondike, ScoringMode::Standard);
+        standard.record_move(StackType::Waste, StackType::Tableau, false);
+        standard.record_move(StackType::Tableau, StackType::Foundation, true);
+        assert_eq!(20, standard.score);
+        standard.tick(25_000);
+        assert_eq!(16, standard.score);
+        standard.record_deal(true);
+        assert_eq!(0, standard.score);
+
+        let mut vegas = Session::new(322, GameMode::Klondike, ScoringMode::Vegas);
+        assert_eq!(-52, vegas.score);
+        vegas.record_move(StackType::Waste, StackType::Foundation, false);
+        vegas.record_move(StackType::Tableau, StackType::Tableau, true);
+        vegas.tick(25_000);
+        assert_eq!(-47, vegas.score);
+
+        let mut none = Session::new(322, GameMode::Klondike, ScoringMode::None);
+        none.record_move(StackType::Waste, StackType::Foundation, true);
+        none.record_win();
+        assert_eq!(0, none.score);
+
+        let mut quick = Session::new(322, GameMode::Klondike, ScoringMode::Standard);
+        quick.record_move(StackType::Tableau, StackType::Foundation, false);
+        assert_eq!(10, quick.score);
+        assert_eq!(Some(200), quick.speed_percent());
+        quick.tick(5_000);
+        assert_eq!(Some(150), quick.speed_percent());
+        quick.record_deal(false);
+        quick.record_move(StackType::Waste, StackType::Foundation, false);
+        assert_eq!(25, quick.score);
+        quick.tick(20_000);
+        assert_eq!(Some(100), quick.speed_percent());
+        quick.record_deal(false);
+        quick.record_move(StackType::Waste, StackType::Foundation, false);
+        assert_eq!(31, quick.score);
+
+        let mut time_attack = Session::new(322, GameMode::TimeAttack, ScoringMode::TimeAttack);
+        time_attack.record_move(StackType::Tableau, StackType::Foundation, true);
+        time_attack.record_move(StackType::Waste, StackType::Foundation, false);
+        time_attack.tick(240_000);
+        assert_eq!(Some(60_000), time_attack.remaining_ms());
+        time_attack.record_win();
+        assert_eq!(165, time_attack.score);
+        time_attack.tick(90_000);
+        assert!(time_attack.out_of_time());
+    }
+
+    #[test]
+    fn test_foundation_combo() {
+        let mut session = Session::new(322, GameMode::Practice, ScoringMode::Standard);
+        session.record_move(StackType::Waste, StackType::Foundation, false);
+        assert_eq!(None, session.combo_count());
+        session.record_move(StackType::Tableau, StackType::Tableau, true);
+        session.record_move(StackType::Tableau, StackType::Foundation, false);
+        session.record_move(StackType::Tableau, StackType::Foundation, false);
+        assert_eq!(Some(3), session.combo_count());
+        assert_eq!(5 + 10 * 3 + 5 + 10, session.score);
+        session.record_deal(false);
+        assert_eq!(None, session.combo_count());
+        session.record_move(StackType::Waste, StackType::Foundation, false);
+        assert_eq!(None, session.combo_count());
+        session.record_undo(50, true);
+        assert_eq!(5 + 10, session.breakdown.combos);
+        assert_eq!(-10 - 15, session.breakdown.undos);
+        let parts = session.breakdown;
+        assert_eq!(
+            session.score,
+            parts.cards
+                + parts.recycles
+                + parts.undos
+                + parts.time
+                + parts.combos
+                + parts.win_bonus
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    klondike::RulesConfig,
+    options::{Preferences, PREFERENCES_PATH},
+    storage::{self, Versioned},
+};
+use alloc::vec::Vec;
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+pub const SETTINGS_PATH: &str = "settings.json";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingsChange {
+    GameMode,
+    Controls,
+    Layout,
+    Theme,
+    Assists,
+    Sounds,
+    Scoring,
+    Rules,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub preferences: Preferences,
+    pub rules: RulesConfig,
+}
+
+impl Versioned for Settings {
+    const VERSION: u32 = 1;
+}
+
+impl Settings {
+    pub fn load() -> Result<Self, Error> {
+        if let Some(settings) = storage::load(SETTINGS_PATH)? {
+            return Ok(settings);
+        }
+        let preferences = storage::load(PREFERENCES_PATH)?.unwrap_or_default();
+        Ok(Self {
+            preferences,
+            rules: RulesConfig::default(),
+        })
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        storage::save(SETTINGS_PATH, self)
+    }
+
+    pub fn changes(&self, previous: &Settings) -> Vec<SettingsChange> {
+        let (now, then) = (&self.preferences, &previous.preferences);
+        let mut changes = Vec::new();
+        if now.game_mode != then.game_mode {
+            changes.push(SettingsChange::GameMode);
+        }
+        if now.control_scheme != then.control_scheme
+            || now.key_repeat != then.key_repeat
+            || now.tilt_to_pan != then.tilt_to_pan
+        {
+            changes.push(SettingsChange::Controls);
+        }
+        if now.show_hud != then.show_hud
+            || now.card_scale != then.card_scale
+            || now.scroll_tableaux != then.scroll_tableaux
+        {
+            changes.push(SettingsChange::Layout);
+        }
+        if now.card_back != then.card_back || now.suit_patterns != then.suit_patterns {
+            changes.push(SettingsChange::Theme);
+        }
+        if now.show_playable != then.show_playable
+            || now.confirm_recycle != then.confirm_recycle
+            || now.undo_limit != then.undo_limit
+            || now.undo_penalty != then.undo_penalty
+        {
+            changes.push(SettingsChange::Assists);
+        }
+        if now.sounds != then.sounds || now.effects != then.effects {
+            changes.push(SettingsChange::Sounds);
+        }
+        if now.scoring != then.scoring {
+            changes.push(SettingsChange::Scoring);
+        }
+        if self.rules != previous.rules {
+            changes.push(SettingsChange::Rules);
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::layout::CardScale;
+    use alloc::vec;
+
+    #[test]
+    fn test_changes_name_touched_groups() {
+        let previous = Settings::default();
+        assert!(previous.changes(&previous).is_empty());
+
+        let mut settings = previous;
+        settings.preferences.card_scale = CardScale::Large;
+        settings.preferences.sounds = false;
+        settings.rules.max_recycles = Some(2);
+        assert_eq!(
+            vec![
+                SettingsChange::Layout,
+                SettingsChange::Sounds,
+                SettingsChange::Rules
+            ],
+            settings.changes(&previous)
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{panel::draw_panel, qr::QrCode};
+use alloc::{string::String, vec::Vec};
+use anyhow::Error;
+use crankstart::{
+    geometry::ScreenPoint,
+    graphics::{LCD_COLUMNS, LCD_ROWS},
+};
+
+const GROUPS_PER_LINE: usize = 4;
+const VISIBLE_LINES: usize = 8;
+
+pub struct ShareCodeView {
+    lines: Vec<String>,
+    first: usize,
+    qr_code: Option<QrCode>,
+    showing_qr: bool,
+}
+
+impl ShareCodeView {
+    pub fn new(code: &str) -> Self {
+        let groups: Vec<&str> = code.split('-').collect();
+        let lines = groups
+            .chunks(GROUPS_PER_LINE)
+            .map(|chunk| chunk.join("-"))
+            .collect();
+        Self {
+            lines,
+            first: 0,
+            qr_code: QrCode::encode(code),
+            showing_qr: false,
+        }
+    }
+
+    // Switches between the code as text and as a QR code; codes too long
+    // for a QR code that fits on screen stay as text.
+    pub fn toggle_qr(&mut self) {
+        self.showing_qr = !self.showing_qr && self.qr_code.is_some();
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.first = self.first.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        if self.first + VISIBLE_LINES < self.lines.len() {
+            self.first += 1;
+        }
+    }
+
+    pub fn draw(&self) -> Result<(), Error> {
+        if let (true, Some(qr_code)) = (self.showing_qr, &self.qr_code) {
+            let center = ScreenPoint::new(LCD_COLUMNS as i32 / 2, LCD_ROWS as i32 / 2);
+            return qr_code.draw(center, LCD_ROWS as i32);
+        }
+        let mut lines = Vec::with_capacity(VISIBLE_LINES + 2);
+        lines.push(String::from("Share code:"));
+        lines.extend(
+            self.lines
+                .iter()
+                .skip(self.first)
+                .take(VISIBLE_LINES)
+                .cloned(),
+        );
+        if self.qr_code.is_some() {
+            lines.push(String::from("Left/Right: QR code"));
+        }
+        draw_panel(&lines, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_share_code_lines() {
+        let mut view = ShareCodeView::new("AAAAA-BBBBB-CCCCC-DDDDD-EEEEE");
+        assert_eq!(
+            vec![
+                String::from("AAAAA-BBBBB-CCCCC-DDDDD"),
+                String::from("EEEEE")
+            ],
+            view.lines
+        );
+        view.scroll_down();
+        assert_eq!(0, view.first);
+        view.toggle_qr();
+        assert!(view.showing_qr);
+        view.toggle_qr();
+        assert!(!view.showing_qr);
+    }
+}
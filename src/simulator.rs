@@ -0,0 +1,194 @@
+use crate::input::{Command, RawInput};
+use alloc::vec::Vec;
+use crankstart::system::PDButtons;
+
+// Desktop keys the simulator reports. Letters arrive as ASCII, arrows as the
+// macOS function key codes.
+const KEY_UP: u32 = 0xf700;
+const KEY_DOWN: u32 = 0xf701;
+const KEY_LEFT: u32 = 0xf702;
+const KEY_RIGHT: u32 = 0xf703;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KeyBinding {
+    Button(PDButtons),
+    Command(Command),
+}
+
+fn key_binding(key: u32) -> Option<KeyBinding> {
+    match key {
+        KEY_UP => Some(KeyBinding::Button(PDButtons::kButtonUp)),
+        KEY_DOWN => Some(KeyBinding::Button(PDButtons::kButtonDown)),
+        KEY_LEFT => Some(KeyBinding::Button(PDButtons::kButtonLeft)),
+        KEY_RIGHT => Some(KeyBinding::Button(PDButtons::kButtonRight)),
+        _ => match core::char::from_u32(key).map(|key| key.to_ascii_lowercase()) {
+            Some('z') => Some(KeyBinding::Button(PDButtons::kButtonB)),
+            Some('x') => Some(KeyBinding::Button(PDButtons::kButtonA)),
+            Some('u') => Some(KeyBinding::Command(Command::Undo)),
+            Some('h') => Some(KeyBinding::Command(Command::Hint)),
+            _ => None,
+        },
+    }
+}
+
+// Key presses collected from simulator events between frames, folded into
+// the button state the input mapper already understands.
+#[derive(Debug)]
+pub struct SimulatorKeys {
+    held: PDButtons,
+    pushed: PDButtons,
+    commands: Vec<Command>,
+}
+
+impl Default for SimulatorKeys {
+    fn default() -> Self {
+        Self {
+            held: PDButtons(0),
+            pushed: PDButtons(0),
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl SimulatorKeys {
+    pub fn key_pressed(&mut self, key: u32) {
+        match key_binding(key) {
+            Some(KeyBinding::Button(button)) => {
+                self.held = self.held | button;
+                self.pushed = self.pushed | button;
+            }
+            Some(KeyBinding::Command(command)) => self.commands.push(command),
+            None => (),
+        }
+    }
+
+    pub fn key_released(&mut self, key: u32) {
+        if let Some(KeyBinding::Button(button)) = key_binding(key) {
+            self.held = PDButtons(self.held.0 & !button.0);
+        }
+    }
+
+    // Merges the keys into this frame's input and hands back the commands
+    // that have no button of their own.
+    pub fn merge(&mut self, input: &RawInput) -> (RawInput, Vec<Command>) {
+        let merged = RawInput {
+            held: input.held | self.held,
+            pushed: input.pushed | self.pushed,
+            ..*input
+        };
+        self.pushed = PDButtons(0);
+        (merged, core::mem::take(&mut self.commands))
+    }
+}
+
+// crankstart_game! with one change: the simulator's key events are passed on
+// to SimulatorKeys instead of being dropped.
+#[cfg(not(test))]
+mod game_setup {
+    use super::SimulatorKeys;
+    use crate::{
+        input::{Command, RawInput},
+        KlondikeGame,
+    };
+    use alloc::vec::Vec;
+    use crankstart::{graphics::PDRect, log_to_console, system::System, GameRunner, Playdate};
+    use crankstart_sys::{LCDSprite, PDSystemEvent, PlaydateAPI};
+
+    static mut GAME_RUNNER: Option<GameRunner<KlondikeGame>> = None;
+    static mut KEYS: Option<SimulatorKeys> = None;
+
+    fn keys() -> &'static mut SimulatorKeys {
+        unsafe { KEYS.get_or_insert_with(SimulatorKeys::default) }
+    }
+
+    pub fn merge_keys(input: &RawInput) -> (RawInput, Vec<Command>) {
+        keys().merge(input)
+    }
+
+    extern "C" fn sprite_update(sprite: *mut LCDSprite) {
+        let game_runner = unsafe { GAME_RUNNER.as_mut().expect("GAME_RUNNER") };
+        game_runner.update_sprite(sprite);
+    }
+
+    extern "C" fn sprite_draw(sprite: *mut LCDSprite, bounds: PDRect, drawrect: PDRect) {
+        let game_runner = unsafe { GAME_RUNNER.as_mut().expect("GAME_RUNNER") };
+        game_runner.draw_sprite(sprite, bounds, drawrect);
+    }
+
+    extern "C" fn update(_user_data: *mut core::ffi::c_void) -> i32 {
+        let game_runner = unsafe { GAME_RUNNER.as_mut().expect("GAME_RUNNER") };
+        game_runner.update();
+        1
+    }
+
+    #[no_mangle]
+    extern "C" fn eventHandler(
+        playdate: *mut PlaydateAPI,
+        event: PDSystemEvent,
+        arg: u32,
+    ) -> crankstart_sys::ctypes::c_int {
+        if event == PDSystemEvent::kEventInit {
+            let mut playdate = Playdate::new(playdate, sprite_update, sprite_draw);
+            System::get()
+                .set_update_callback(Some(update))
+                .unwrap_or_else(|err| {
+                    log_to_console!("Got error while setting update callback: {}", err);
+                });
+            let game = match KlondikeGame::new(&mut playdate) {
+                Ok(game) => Some(game),
+                Err(err) => {
+                    log_to_console!("Got error while creating game: {}", err);
+                    None
+                }
+            };
+            unsafe {
+                GAME_RUNNER = Some(GameRunner::new(game, playdate));
+            }
+        } else if event == PDSystemEvent::kEventKeyPressed {
+            keys().key_pressed(arg);
+        } else if event == PDSystemEvent::kEventKeyReleased {
+            keys().key_released(arg);
+        }
+        0
+    }
+}
+
+#[cfg(not(test))]
+pub use game_setup::merge_keys;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    fn input() -> RawInput {
+        RawInput {
+            held: PDButtons(0),
+            pushed: PDButtons(0),
+            crank_change: 0.0,
+            crank_docked: true,
+            delta_ms: 20,
+        }
+    }
+
+    #[test]
+    fn test_simulator_keys() {
+        let mut keys = SimulatorKeys::default();
+        keys.key_pressed(KEY_RIGHT);
+        keys.key_pressed(u32::from(b'U'));
+        keys.key_pressed(u32::from(b'h'));
+        keys.key_pressed(u32::from(b'q'));
+        let (merged, commands) = keys.merge(&input());
+        assert_eq!(PDButtons::kButtonRight, merged.pushed);
+        assert_eq!(vec![Command::Undo, Command::Hint], commands);
+
+        let (merged, commands) = keys.merge(&input());
+        assert_eq!(PDButtons(0), merged.pushed);
+        assert_eq!(PDButtons::kButtonRight, merged.held);
+        assert!(commands.is_empty());
+        keys.key_released(KEY_RIGHT);
+        keys.key_pressed(u32::from(b'x'));
+        let (merged, _) = keys.merge(&input());
+        assert_eq!(PDButtons::kButtonA, merged.held);
+    }
+}
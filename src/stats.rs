@@ -0,0 +1,768 @@
+use crate::{
+    bookmarks::{Bookmark, BOOKMARK_LIMIT},
+    klondike::VariantId,
+    par::Grade,
+    puzzles::PUZZLES,
+    scoring::{ScoringMode, SpeedBonus, TIME_ATTACK_LIMIT_MS},
+    session::{format_time, Session},
+    storage::Versioned,
+};
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+use enum_iterator::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+
+pub const STATS_PATH: &str = "stats.json";
+pub const TIME_ATTACK_BOARD_LIMIT: usize = 10;
+const PROGRESS_BAR_WIDTH: usize = 16;
+
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, IntoEnumIterator, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum GameMode {
+    Klondike,
+    Westcliff,
+    Easthaven,
+    Yukon,
+    Canfield,
+    Agnes,
+    FortyThieves,
+    Spiderette,
+    Practice,
+    TimeAttack,
+    MoveChallenge,
+    Puzzle,
+    Wildcard,
+    Race,
+}
+
+impl GameMode {
+    pub fn variant(self) -> VariantId {
+        match self {
+            GameMode::Klondike => VariantId::Klondike,
+            GameMode::Westcliff => VariantId::Westcliff,
+            GameMode::Easthaven => VariantId::Easthaven,
+            GameMode::Yukon => VariantId::Yukon,
+            GameMode::Canfield => VariantId::Canfield,
+            GameMode::Agnes => VariantId::Agnes,
+            GameMode::FortyThieves => VariantId::FortyThieves,
+            GameMode::Spiderette => VariantId::Spiderette,
+            GameMode::Practice => VariantId::Thoughtful,
+            GameMode::TimeAttack => VariantId::Klondike,
+            GameMode::MoveChallenge => VariantId::Klondike,
+            GameMode::Puzzle => VariantId::Klondike,
+            GameMode::Wildcard => VariantId::Klondike,
+            GameMode::Race => VariantId::Klondike,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Klondike => "Klondike",
+            GameMode::Westcliff => "Westcliff",
+            GameMode::Easthaven => "Easthaven",
+            GameMode::Yukon => "Yukon",
+            GameMode::Canfield => "Canfield",
+            GameMode::Agnes => "Agnes",
+            GameMode::FortyThieves => "Forty Thieves",
+            GameMode::Spiderette => "Spiderette",
+            GameMode::Practice => "Practice",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::MoveChallenge => "Move Challenge",
+            GameMode::Puzzle => "Puzzles",
+            GameMode::Wildcard => "Wildcard",
+            GameMode::Race => "Pass-and-play Race",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Klondike => GameMode::Westcliff,
+            GameMode::Westcliff => GameMode::Easthaven,
+            GameMode::Easthaven => GameMode::Yukon,
+            GameMode::Yukon => GameMode::Canfield,
+            GameMode::Canfield => GameMode::Agnes,
+            GameMode::Agnes => GameMode::FortyThieves,
+            GameMode::FortyThieves => GameMode::Spiderette,
+            GameMode::Spiderette => GameMode::Practice,
+            GameMode::Practice => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::MoveChallenge,
+            GameMode::MoveChallenge => GameMode::Puzzle,
+            GameMode::Puzzle => GameMode::Wildcard,
+            GameMode::Wildcard => GameMode::Race,
+            GameMode::Race => GameMode::Klondike,
+        }
+    }
+
+    pub fn time_limit_ms(self) -> Option<usize> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_LIMIT_MS),
+            _ => None,
+        }
+    }
+
+    pub fn speed_bonus(self) -> Option<SpeedBonus> {
+        match self {
+            GameMode::TimeAttack => Some(SpeedBonus {
+                window_ms: 5_000,
+                max_percent: 300,
+            }),
+            GameMode::Practice | GameMode::MoveChallenge | GameMode::Puzzle => None,
+            _ => Some(SpeedBonus {
+                window_ms: 10_000,
+                max_percent: 200,
+            }),
+        }
+    }
+
+    pub fn scoring(self, preferred: ScoringMode) -> ScoringMode {
+        match self {
+            GameMode::TimeAttack => ScoringMode::TimeAttack,
+            _ => preferred,
+        }
+    }
+}
+
+fn record_key(mode: GameMode, seed: u64) -> String {
+    match mode {
+        GameMode::Klondike => format!("{}", seed),
+        GameMode::Westcliff
+        | GameMode::Easthaven
+        | GameMode::Yukon
+        | GameMode::Canfield
+        | GameMode::Agnes
+        | GameMode::FortyThieves
+        | GameMode::Spiderette
+        | GameMode::Practice
+        | GameMode::TimeAttack
+        | GameMode::MoveChallenge
+        | GameMode::Puzzle
+        | GameMode::Wildcard
+        | GameMode::Race => format!("{:?}-{}", mode, seed),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum GameResult {
+    Won,
+    Lost,
+    Abandoned,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ModeStats {
+    pub played: usize,
+    pub won: usize,
+    pub current_win_streak: usize,
+    pub longest_win_streak: usize,
+    pub current_loss_streak: usize,
+    pub longest_loss_streak: usize,
+    #[serde(default)]
+    pub grades: BTreeMap<Grade, usize>,
+    // Games that weren't won, counted by the quarter of the deck they got
+    // onto the foundations.
+    #[serde(default)]
+    pub progress: [usize; 4],
+}
+
+impl ModeStats {
+    fn win(&mut self) {
+        self.current_win_streak += 1;
+        self.current_loss_streak = 0;
+        self.longest_win_streak = self.longest_win_streak.max(self.current_win_streak);
+    }
+
+    fn lose(&mut self) {
+        self.current_loss_streak += 1;
+        self.current_win_streak = 0;
+        self.longest_loss_streak = self.longest_loss_streak.max(self.current_loss_streak);
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        let win_percentage = (self.won * 100).checked_div(self.played).unwrap_or(0);
+        let mut lines = vec![
+            format!(
+                "Played: {}  Won: {} ({}%)",
+                self.played, self.won, win_percentage
+            ),
+            format!(
+                "Win streak: {}  Longest: {}",
+                self.current_win_streak, self.longest_win_streak
+            ),
+            format!(
+                "Loss streak: {}  Longest: {}",
+                self.current_loss_streak, self.longest_loss_streak
+            ),
+        ];
+        if !self.grades.is_empty() {
+            let grades: Vec<String> = self
+                .grades
+                .iter()
+                .map(|(grade, count)| format!("{} {}", grade.name(), count))
+                .collect();
+            lines.push(format!("Grades vs par: {}", grades.join("  ")));
+        }
+        lines
+    }
+
+    fn record_progress(&mut self, cards_home: usize, deck_size: usize) {
+        let quarter = (cards_home * 4).checked_div(deck_size).unwrap_or(0).min(3);
+        self.progress[quarter] += 1;
+    }
+
+    pub fn progress_lines(&self) -> Vec<String> {
+        let most = self.progress.iter().copied().max().unwrap_or(0);
+        if most == 0 {
+            return vec![String::from("No lost games yet")];
+        }
+        let mut lines = vec![String::from("Lost games by cards home")];
+        for (quarter, count) in self.progress.iter().enumerate() {
+            lines.push(format!(
+                "{}-{}%  {} {}",
+                quarter * 25,
+                quarter * 25 + 24,
+                "#".repeat(count * PROGRESS_BAR_WIDTH / most),
+                count
+            ));
+        }
+        lines
+    }
+
+    pub fn record(&mut self, result: GameResult, abandon_breaks_streak: bool) {
+        self.played += 1;
+        match result {
+            GameResult::Won => {
+                self.won += 1;
+                self.win();
+            }
+            GameResult::Lost => self.lose(),
+            GameResult::Abandoned => {
+                if abandon_breaks_streak {
+                    self.lose();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SeedRecord {
+    pub best_time_ms: Option<usize>,
+    pub best_score: Option<i32>,
+    pub fewest_moves: Option<usize>,
+    #[serde(default)]
+    pub best_grade: Option<Grade>,
+}
+
+impl SeedRecord {
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(best_time_ms) = self.best_time_ms {
+            lines.push(format!("Best time: {}", format_time(best_time_ms)));
+        }
+        if let Some(best_score) = self.best_score {
+            lines.push(format!("Best score: {}", best_score));
+        }
+        if let Some(fewest_moves) = self.fewest_moves {
+            lines.push(format!("Fewest moves: {}", fewest_moves));
+        }
+        if let Some(best_grade) = self.best_grade {
+            lines.push(format!("Best grade: {}", best_grade.name()));
+        }
+        lines
+    }
+}
+
+// The best and worst single games over a stretch of Vegas play. The game
+// counts and nets are kept by the bankroll.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct VegasLedger {
+    pub biggest_win: i32,
+    pub biggest_loss: i32,
+}
+
+impl VegasLedger {
+    fn record(&mut self, net: i32) {
+        self.biggest_win = self.biggest_win.max(net);
+        self.biggest_loss = self.biggest_loss.min(net);
+    }
+
+    fn lines(&self, title: &str, games: usize, net: i32) -> Vec<String> {
+        vec![
+            format!("{}: {} games", title, games),
+            format!("  Net: ${}", net),
+            format!("  Biggest win: ${}", self.biggest_win),
+            format!("  Biggest loss: ${}", -self.biggest_loss),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct VegasBankroll {
+    pub bankroll: i32,
+    pub lifetime_winnings: i32,
+    pub games: usize,
+    // Since the bankroll was last reset.
+    #[serde(default)]
+    pub session_games: usize,
+    #[serde(default)]
+    pub session: VegasLedger,
+    // Only covers games settled since the ledger was added.
+    #[serde(default)]
+    pub lifetime: VegasLedger,
+}
+
+impl VegasBankroll {
+    pub fn settle(&mut self, net: i32) {
+        self.bankroll += net;
+        self.lifetime_winnings += net;
+        self.games += 1;
+        self.session_games += 1;
+        self.session.record(net);
+        self.lifetime.record(net);
+    }
+
+    pub fn reset(&mut self) {
+        self.bankroll = 0;
+        self.session_games = 0;
+        self.session = VegasLedger::default();
+    }
+
+    pub fn ledger_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Vegas bankroll: ${}", self.bankroll)];
+        lines.extend(
+            self.session
+                .lines("This session", self.session_games, self.bankroll),
+        );
+        lines.extend(
+            self.lifetime
+                .lines("Lifetime", self.games, self.lifetime_winnings),
+        );
+        lines
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!("Vegas bankroll: ${}", self.bankroll),
+            format!(
+                "Lifetime winnings: ${} over {} games",
+                self.lifetime_winnings, self.games
+            ),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TimeAttackEntry {
+    pub seed: u64,
+    pub cards: usize,
+    pub score: i32,
+}
+
+impl TimeAttackEntry {
+    fn label(&self) -> String {
+        format!("{} pts  {} cards  #{}", self.score, self.cards, self.seed)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PersonalBests {
+    pub time: bool,
+    pub score: bool,
+    pub moves: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Stats {
+    pub abandon_breaks_streak: bool,
+    pub modes: BTreeMap<GameMode, ModeStats>,
+    #[serde(default)]
+    pub records: BTreeMap<String, SeedRecord>,
+    #[serde(default)]
+    pub vegas: VegasBankroll,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub time_attack: Vec<TimeAttackEntry>,
+    #[serde(default)]
+    pub puzzles: BTreeMap<u64, usize>,
+    #[serde(default)]
+    pub daily: BTreeMap<GameMode, BTreeMap<u32, GameResult>>,
+    #[serde(default)]
+    pub slot_names: BTreeMap<GameMode, String>,
+}
+
+impl Versioned for Stats {
+    const VERSION: u32 = 1;
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            abandon_breaks_streak: true,
+            modes: BTreeMap::new(),
+            records: BTreeMap::new(),
+            vegas: VegasBankroll::default(),
+            bookmarks: Vec::new(),
+            time_attack: Vec::new(),
+            puzzles: BTreeMap::new(),
+            daily: BTreeMap::new(),
+            slot_names: BTreeMap::new(),
+        }
+    }
+}
+
+impl Stats {
+    pub fn record(&mut self, mode: GameMode, result: GameResult) {
+        let abandon_breaks_streak = self.abandon_breaks_streak;
+        self.modes
+            .entry(mode)
+            .or_default()
+            .record(result, abandon_breaks_streak);
+    }
+
+    pub fn mode(&self, mode: GameMode) -> ModeStats {
+        self.modes.get(&mode).cloned().unwrap_or_default()
+    }
+
+    pub fn summary_lines(&self, mode: GameMode) -> Vec<String> {
+        let mut lines = self.mode(mode).summary_lines();
+        if self.vegas.games > 0 {
+            lines.extend(self.vegas.summary_lines());
+        }
+        if mode == GameMode::TimeAttack {
+            lines.extend(self.time_attack_lines());
+        }
+        if mode == GameMode::Puzzle {
+            lines.push(self.puzzle_progress());
+        }
+        lines
+    }
+
+    pub fn record_progress(&mut self, mode: GameMode, cards_home: usize, deck_size: usize) {
+        self.modes
+            .entry(mode)
+            .or_default()
+            .record_progress(cards_home, deck_size);
+    }
+
+    pub fn record_daily(&mut self, mode: GameMode, day: u32, result: GameResult) {
+        let previous = self
+            .daily
+            .entry(mode)
+            .or_default()
+            .entry(day)
+            .or_insert(result);
+        if result == GameResult::Won {
+            *previous = result;
+        }
+    }
+
+    pub fn daily_results(&self, mode: GameMode) -> Option<&BTreeMap<u32, GameResult>> {
+        self.daily.get(&mode)
+    }
+
+    pub fn puzzle_progress(&self) -> String {
+        format!("Puzzles solved: {}/{}", self.puzzles.len(), PUZZLES.len())
+    }
+
+    pub fn puzzle_solved(&self, index: u64) -> bool {
+        self.puzzles.contains_key(&index)
+    }
+
+    pub fn record_puzzle(&mut self, index: u64, moves: usize) {
+        let fewest = self.puzzles.entry(index).or_insert(moves);
+        *fewest = (*fewest).min(moves);
+    }
+
+    pub fn time_attack_lines(&self) -> Vec<String> {
+        let mut lines = vec![String::from("Time attack best runs")];
+        lines.extend(
+            self.time_attack
+                .iter()
+                .take(5)
+                .enumerate()
+                .map(|(index, entry)| format!("{}. {}", index + 1, entry.label())),
+        );
+        lines
+    }
+
+    pub fn record_time_attack(&mut self, session: &Session, cards: usize) -> Option<usize> {
+        let rank = self
+            .time_attack
+            .iter()
+            .position(|entry| session.score > entry.score)
+            .unwrap_or(self.time_attack.len());
+        if rank >= TIME_ATTACK_BOARD_LIMIT {
+            return None;
+        }
+        self.time_attack.insert(
+            rank,
+            TimeAttackEntry {
+                seed: session.seed,
+                cards,
+                score: session.score,
+            },
+        );
+        self.time_attack.truncate(TIME_ATTACK_BOARD_LIMIT);
+        Some(rank)
+    }
+
+    pub fn record_for(&self, mode: GameMode, seed: u64) -> Option<&SeedRecord> {
+        self.records.get(&record_key(mode, seed))
+    }
+
+    pub fn is_bookmarked(&self, mode: GameMode, seed: u64) -> bool {
+        self.bookmarks
+            .iter()
+            .any(|bookmark| bookmark.mode == mode && bookmark.seed == seed)
+    }
+
+    pub fn bookmark(&mut self, mode: GameMode, seed: u64, note: Option<String>) -> bool {
+        if self.is_bookmarked(mode, seed) {
+            return false;
+        }
+        self.bookmarks.push(Bookmark { seed, mode, note });
+        if self.bookmarks.len() > BOOKMARK_LIMIT {
+            let excess = self.bookmarks.len() - BOOKMARK_LIMIT;
+            self.bookmarks.drain(..excess);
+        }
+        true
+    }
+
+    pub fn record_grade(&mut self, session: &Session, grade: Grade) {
+        *self
+            .modes
+            .entry(session.mode)
+            .or_default()
+            .grades
+            .entry(grade)
+            .or_default() += 1;
+        let record = self
+            .records
+            .entry(record_key(session.mode, session.seed))
+            .or_default();
+        record.best_grade = Some(
+            record
+                .best_grade
+                .map(|best| best.min(grade))
+                .unwrap_or(grade),
+        );
+    }
+
+    pub fn record_win(&mut self, session: &Session) -> PersonalBests {
+        let record = self
+            .records
+            .entry(record_key(session.mode, session.seed))
+            .or_default();
+        let bests = PersonalBests {
+            time: record
+                .best_time_ms
+                .map(|best| session.elapsed_ms < best)
+                .unwrap_or(true),
+            score: record
+                .best_score
+                .map(|best| session.score > best)
+                .unwrap_or(true),
+            moves: record
+                .fewest_moves
+                .map(|best| session.moves < best)
+                .unwrap_or(true),
+        };
+        if bests.time {
+            record.best_time_ms = Some(session.elapsed_ms);
+        }
+        if bests.score {
+            record.best_score = Some(session.score);
+        }
+        if bests.moves {
+            record.fewest_moves = Some(session.moves);
+        }
+        bests
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scoring::ScoringMode;
+
+    #[test]
+    fn test_streaks() {
+        let mut stats = Stats::default();
+        stats.record(GameMode::Klondike, GameResult::Won);
+        stats.record(GameMode::Klondike, GameResult::Won);
+        stats.record(GameMode::Klondike, GameResult::Lost);
+        stats.record(GameMode::Klondike, GameResult::Won);
+        let mode_stats = stats.mode(GameMode::Klondike);
+        assert_eq!(4, mode_stats.played);
+        assert_eq!(3, mode_stats.won);
+        assert_eq!(1, mode_stats.current_win_streak);
+        assert_eq!(2, mode_stats.longest_win_streak);
+        assert_eq!(0, mode_stats.current_loss_streak);
+        assert_eq!(1, mode_stats.longest_loss_streak);
+    }
+
+    #[test]
+    fn test_abandoned_games() {
+        let mut stats = Stats::default();
+        stats.abandon_breaks_streak = false;
+        stats.record(GameMode::Klondike, GameResult::Won);
+        stats.record(GameMode::Klondike, GameResult::Abandoned);
+        stats.record(GameMode::Klondike, GameResult::Won);
+        assert_eq!(2, stats.mode(GameMode::Klondike).current_win_streak);
+
+        stats.abandon_breaks_streak = true;
+        stats.record(GameMode::Klondike, GameResult::Abandoned);
+        let mode_stats = stats.mode(GameMode::Klondike);
+        assert_eq!(0, mode_stats.current_win_streak);
+        assert_eq!(1, mode_stats.current_loss_streak);
+        assert_eq!(4, mode_stats.played);
+    }
+
+    #[test]
+    fn test_seed_records() {
+        let mut stats = Stats::default();
+        let mut session = Session::new(322, GameMode::Klondike, ScoringMode::Standard);
+        session.elapsed_ms = 90_000;
+        session.score = 400;
+        session.moves = 120;
+        let bests = stats.record_win(&session);
+        assert_eq!(
+            PersonalBests {
+                time: true,
+                score: true,
+                moves: true
+            },
+            bests
+        );
+
+        session.elapsed_ms = 80_000;
+        session.score = 300;
+        session.moves = 120;
+        let bests = stats.record_win(&session);
+        assert_eq!(
+            PersonalBests {
+                time: true,
+                score: false,
+                moves: false
+            },
+            bests
+        );
+        let record = stats.record_for(GameMode::Klondike, 322).expect("record");
+        assert_eq!(Some(80_000), record.best_time_ms);
+        assert_eq!(Some(400), record.best_score);
+        assert_eq!(Some(120), record.fewest_moves);
+        assert!(stats.record_for(GameMode::Klondike, 324).is_none());
+        assert!(stats.record_for(GameMode::Westcliff, 322).is_none());
+
+        stats.record_grade(&session, Grade::B);
+        stats.record_grade(&session, Grade::A);
+        stats.record_grade(&session, Grade::C);
+        let record = stats.record_for(GameMode::Klondike, 322).expect("record");
+        assert_eq!(Some(Grade::A), record.best_grade);
+        assert_eq!(
+            3,
+            stats
+                .mode(GameMode::Klondike)
+                .grades
+                .values()
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_progress_chart() {
+        let mut stats = Stats::default();
+        assert_eq!(
+            vec![String::from("No lost games yet")],
+            stats.mode(GameMode::Klondike).progress_lines()
+        );
+        stats.record_progress(GameMode::Klondike, 5, 52);
+        stats.record_progress(GameMode::Klondike, 12, 52);
+        stats.record_progress(GameMode::Klondike, 40, 52);
+        stats.record_progress(GameMode::FortyThieves, 60, 104);
+        assert_eq!([2, 0, 0, 1], stats.mode(GameMode::Klondike).progress);
+        assert_eq!([0, 0, 1, 0], stats.mode(GameMode::FortyThieves).progress);
+        let lines = stats.mode(GameMode::Klondike).progress_lines();
+        assert_eq!("0-24%  ################ 2", lines[1]);
+        assert_eq!("75-99%  ######## 1", lines[4]);
+    }
+
+    #[test]
+    fn test_vegas_bankroll() {
+        let mut stats = Stats::default();
+        stats.vegas.settle(-52);
+        stats.vegas.settle(208);
+        assert_eq!(156, stats.vegas.bankroll);
+        stats.vegas.reset();
+        stats.vegas.settle(-42);
+        assert_eq!(-42, stats.vegas.bankroll);
+        assert_eq!(114, stats.vegas.lifetime_winnings);
+        assert_eq!(3, stats.vegas.games);
+        assert_eq!(
+            VegasLedger {
+                biggest_win: 0,
+                biggest_loss: -42
+            },
+            stats.vegas.session
+        );
+        assert_eq!(
+            VegasLedger {
+                biggest_win: 208,
+                biggest_loss: -52
+            },
+            stats.vegas.lifetime
+        );
+        let lines = stats.vegas.ledger_lines();
+        assert_eq!("This session: 1 games", lines[1]);
+        assert_eq!("Lifetime: 3 games", lines[5]);
+        assert_eq!("  Net: $114", lines[6]);
+        assert_eq!("  Biggest win: $208", lines[7]);
+    }
+
+    #[test]
+    fn test_time_attack_board() {
+        let mut stats = Stats::default();
+        let mut session = Session::new(322, GameMode::TimeAttack, ScoringMode::TimeAttack);
+        for score in 0..TIME_ATTACK_BOARD_LIMIT as i32 {
+            session.score = score * 10;
+            stats.record_time_attack(&session, score as usize);
+        }
+        assert_eq!(90, stats.time_attack[0].score);
+        session.score = 0;
+        assert_eq!(None, stats.record_time_attack(&session, 1));
+        session.score = 45;
+        assert_eq!(Some(5), stats.record_time_attack(&session, 4));
+        assert_eq!(TIME_ATTACK_BOARD_LIMIT, stats.time_attack.len());
+        assert_eq!(10, stats.time_attack.last().expect("entry").score);
+    }
+
+    #[test]
+    fn test_daily_results() {
+        let mut stats = Stats::default();
+        stats.record_daily(GameMode::Klondike, 9000, GameResult::Abandoned);
+        stats.record_daily(GameMode::Klondike, 9000, GameResult::Won);
+        stats.record_daily(GameMode::Klondike, 9000, GameResult::Lost);
+        stats.record_daily(GameMode::Klondike, 9001, GameResult::Lost);
+        let results = stats.daily_results(GameMode::Klondike).expect("results");
+        assert_eq!(Some(&GameResult::Won), results.get(&9000));
+        assert_eq!(Some(&GameResult::Lost), results.get(&9001));
+        assert!(stats.daily_results(GameMode::Yukon).is_none());
+        let json = serde_json::to_string(&stats).expect("save");
+        assert_eq!(stats, serde_json::from_str::<Stats>(&json).expect("load"));
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let mut stats = Stats::default();
+        assert!(stats.bookmark(GameMode::Klondike, 322, None));
+        assert!(!stats.bookmark(GameMode::Klondike, 322, Some(String::from("again"))));
+        assert!(stats.bookmark(GameMode::Yukon, 322, None));
+        assert!(stats.is_bookmarked(GameMode::Yukon, 322));
+        for seed in 0..BOOKMARK_LIMIT as u64 {
+            stats.bookmark(GameMode::Klondike, 1000 + seed, None);
+        }
+        assert_eq!(BOOKMARK_LIMIT, stats.bookmarks.len());
+        assert!(!stats.is_bookmarked(GameMode::Klondike, 322));
+    }
+}
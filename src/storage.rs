@@ -0,0 +1,198 @@
+use alloc::{format, string::String};
+use anyhow::{anyhow, Error};
+use crankstart::file::FileSystem;
+use crankstart_sys::FileOptions;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+// Files are written as {"version": n, "data": ...}. Anything without that
+// wrapper predates versioning and is treated as version 0.
+pub trait Versioned: DeserializeOwned + Serialize {
+    const VERSION: u32;
+
+    // Upgrades data saved at `version` to `version + 1`.
+    fn migrate(_version: u32, data: Value) -> Result<Value, Error> {
+        Ok(data)
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+pub fn encode<T: Versioned>(value: &T) -> Result<String, Error> {
+    let envelope = Envelope {
+        version: T::VERSION,
+        data: value,
+    };
+    serde_json::to_string(&envelope).map_err(Error::msg)
+}
+
+pub fn decode<T: Versioned>(json: &str) -> Result<T, Error> {
+    let value: Value = serde_json::from_str(json).map_err(Error::msg)?;
+    let (mut version, mut data) = match value {
+        Value::Object(mut map) if map.len() == 2 && map.contains_key("data") => {
+            let version = map
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow!("save file has no version"))?;
+            (version as u32, map.remove("data").unwrap_or(Value::Null))
+        }
+        value => (0, value),
+    };
+    if version > T::VERSION {
+        return Err(anyhow!(
+            "save file version {} is newer than {}",
+            version,
+            T::VERSION
+        ));
+    }
+    while version < T::VERSION {
+        data = T::migrate(version, data)?;
+        version += 1;
+    }
+    serde_json::from_value(data).map_err(Error::msg)
+}
+
+fn temp_path(path: &str) -> String {
+    format!("{}.tmp", path)
+}
+
+fn backup_path(path: &str) -> String {
+    format!("{}.bak", path)
+}
+
+fn read_text(path: &str) -> Option<String> {
+    let file_system = FileSystem::get();
+    if file_system.stat(path).is_err() {
+        return None;
+    }
+    file_system.read_file_as_string(path).ok()
+}
+
+fn decode_with_backup<T: Versioned>(
+    primary: Option<String>,
+    backup: Option<String>,
+) -> Result<Option<T>, Error> {
+    let primary = match primary.map(|json| decode(&json)) {
+        Some(Ok(value)) => return Ok(Some(value)),
+        Some(Err(err)) => Err(err),
+        None => Ok(None),
+    };
+    match backup.map(|json| decode(&json)) {
+        Some(Ok(value)) => Ok(Some(value)),
+        Some(Err(_)) | None => primary,
+    }
+}
+
+// Falls back to the copy kept by the previous save when the primary file is
+// missing or damaged.
+pub fn load<T: Versioned>(path: &str) -> Result<Option<T>, Error> {
+    decode_with_backup(read_text(path), read_text(&backup_path(path)))
+}
+
+pub fn save<T: Versioned>(path: &str, value: &T) -> Result<(), Error> {
+    let temp_path = temp_path(path);
+    write_text(&temp_path, &encode(value)?)?;
+    let file_system = FileSystem::get();
+    if file_system.stat(path).is_ok() {
+        let backup_path = backup_path(path);
+        if file_system.stat(&backup_path).is_ok() {
+            file_system.unlink(&backup_path, false)?;
+        }
+        file_system.rename(path, &backup_path)?;
+    }
+    file_system.rename(&temp_path, path)?;
+    Ok(())
+}
+
+pub fn remove(path: &str) -> Result<(), Error> {
+    let file_system = FileSystem::get();
+    for path in &[String::from(path), backup_path(path)] {
+        if file_system.stat(path).is_ok() {
+            file_system.unlink(path, false)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_text(path: &str, text: &str) -> Result<(), Error> {
+    let file = FileSystem::get().open(path, FileOptions::kFileWrite)?;
+    file.write(text.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Counter {
+        wins: u32,
+        name: String,
+    }
+
+    impl Versioned for Counter {
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, mut data: Value) -> Result<Value, Error> {
+            match version {
+                0 => Ok(data),
+                1 => {
+                    let name = Value::String(String::from("player"));
+                    if let Value::Object(map) = &mut data {
+                        map.insert(String::from("name"), name);
+                    }
+                    Ok(data)
+                }
+                _ => Err(anyhow!("unknown counter version {}", version)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_migrates_old_saves() {
+        let current = Counter {
+            wins: 3,
+            name: String::from("rob"),
+        };
+        let json = encode(&current).expect("encode");
+        assert_eq!(current, decode(&json).expect("current"));
+
+        let migrated = Counter {
+            wins: 3,
+            name: String::from("player"),
+        };
+        let legacy = r#"{"wins":3}"#;
+        assert_eq!(migrated, decode(legacy).expect("legacy"));
+        let version_one = r#"{"version":1,"data":{"wins":3}}"#;
+        assert_eq!(migrated, decode(version_one).expect("version one"));
+
+        let future = r#"{"version":3,"data":{"wins":3,"name":"rob"}}"#;
+        assert!(decode::<Counter>(future).is_err());
+    }
+
+    #[test]
+    fn test_backup_fallback() {
+        let saved = |wins| {
+            encode(&Counter {
+                wins,
+                name: String::from("rob"),
+            })
+            .ok()
+        };
+        let torn = Some(String::from(r#"{"version":2,"da"#));
+        let wins = |loaded: Result<Option<Counter>, Error>| {
+            loaded.expect("load").map(|counter| counter.wins)
+        };
+        assert_eq!(Some(2), wins(decode_with_backup(saved(2), saved(1))));
+        assert_eq!(Some(1), wins(decode_with_backup(torn.clone(), saved(1))));
+        assert_eq!(Some(1), wins(decode_with_backup(None, saved(1))));
+        assert_eq!(None, wins(decode_with_backup(None, None)));
+        assert!(decode_with_backup::<Counter>(torn.clone(), torn).is_err());
+    }
+}
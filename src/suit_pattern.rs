@@ -0,0 +1,75 @@
+use crate::klondike::Suit;
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDColor, LCDPattern, LCDSolidColor},
+};
+
+const MARKER_WIDTH: i32 = 10;
+const MARKER_HEIGHT: i32 = 7;
+const MARKER_INSET: i32 = 2;
+
+const STRIPED: LCDPattern = [
+    0x33, 0x66, 0xcc, 0x99, 0x33, 0x66, 0xcc, 0x99, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+const DOTTED: LCDPattern = [
+    0xee, 0xff, 0xbb, 0xff, 0xee, 0xff, 0xbb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SuitFill {
+    Solid,
+    Outline,
+    Striped,
+    Dotted,
+}
+
+pub fn suit_fill(suit: Suit) -> SuitFill {
+    match suit {
+        Suit::Spade => SuitFill::Solid,
+        Suit::Club => SuitFill::Outline,
+        Suit::Heart => SuitFill::Striped,
+        Suit::Diamond => SuitFill::Dotted,
+    }
+}
+
+pub fn draw_suit_marker(
+    card_position: ScreenPoint,
+    suit: Suit,
+    card_width: i32,
+) -> Result<(), Error> {
+    let graphics = Graphics::get();
+    let rect = ScreenRect::new(
+        ScreenPoint::new(
+            card_position.x + card_width - MARKER_WIDTH - MARKER_INSET,
+            card_position.y + MARKER_INSET,
+        ),
+        ScreenSize::new(MARKER_WIDTH, MARKER_HEIGHT),
+    );
+    let black = || LCDColor::Solid(LCDSolidColor::kColorBlack);
+    match suit_fill(suit) {
+        SuitFill::Solid => graphics.fill_rect(rect, black())?,
+        SuitFill::Outline => {
+            graphics.fill_rect(rect, LCDColor::Solid(LCDSolidColor::kColorWhite))?
+        }
+        SuitFill::Striped => graphics.fill_rect(rect, LCDColor::Pattern(STRIPED))?,
+        SuitFill::Dotted => graphics.fill_rect(rect, LCDColor::Pattern(DOTTED))?,
+    }
+    graphics.draw_rect(rect, black())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::Suit;
+
+    #[test]
+    fn test_color_groups_use_distinct_fills() {
+        let black = [suit_fill(Suit::Spade), suit_fill(Suit::Club)];
+        let red = [suit_fill(Suit::Heart), suit_fill(Suit::Diamond)];
+        assert_ne!(black[0], black[1]);
+        assert_ne!(red[0], red[1]);
+        assert!(red.iter().all(|fill| !black.contains(fill)));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    klondike::Table,
+    par::{move_budget, par_for, Grade},
+    scoring::ScoreBreakdown,
+    session::{format_time, Session},
+    stats::{GameMode, GameResult, PersonalBests},
+};
+use alloc::{format, string::String, vec, vec::Vec};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    // The solver found a win for this deal under the standard rules.
+    Winnable,
+    Unverified,
+}
+
+impl Verdict {
+    pub fn label(self) -> &'static str {
+        match self {
+            Verdict::Winnable => "winnable",
+            Verdict::Unverified => "not verified",
+        }
+    }
+}
+
+// Everything the results screens show, captured once when the game ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSummary {
+    pub mode: GameMode,
+    pub seed: u64,
+    pub result: GameResult,
+    pub elapsed_ms: usize,
+    pub remaining_ms: Option<usize>,
+    pub moves: usize,
+    pub move_budget: Option<usize>,
+    pub undos: usize,
+    pub score: i32,
+    pub breakdown: ScoreBreakdown,
+    pub stock_cycles: usize,
+    pub cards_home: usize,
+    pub deck_size: usize,
+    pub par: Option<usize>,
+    pub verdict: Verdict,
+    pub records: PersonalBests,
+}
+
+impl GameSummary {
+    pub fn new(
+        session: &Session,
+        table: &Table,
+        result: GameResult,
+        verdict: Verdict,
+        records: PersonalBests,
+    ) -> Self {
+        Self {
+            mode: session.mode,
+            seed: session.seed,
+            result,
+            elapsed_ms: session.elapsed_ms,
+            remaining_ms: session.remaining_ms(),
+            moves: session.moves,
+            move_budget: move_budget(session.mode, session.seed),
+            undos: session.undos,
+            score: session.score,
+            breakdown: session.breakdown,
+            stock_cycles: table.stock_cycles_used(),
+            cards_home: table.foundation_count(),
+            deck_size: table.deck_size(),
+            par: par_for(session.mode, session.seed),
+            verdict,
+            records,
+        }
+    }
+
+    pub fn grade(&self) -> Option<Grade> {
+        self.par.map(|par| Grade::from_moves(self.moves, par))
+    }
+
+    fn flag(best: bool) -> &'static str {
+        if best {
+            "  New best!"
+        } else {
+            ""
+        }
+    }
+
+    pub fn won_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            String::from(if self.mode == GameMode::Puzzle {
+                "Puzzle solved!"
+            } else {
+                "You won!"
+            }),
+            format!(
+                "Time: {}{}",
+                format_time(self.elapsed_ms),
+                Self::flag(self.records.time)
+            ),
+            format!("Score: {}{}", self.score, Self::flag(self.records.score)),
+            match (self.par, self.grade()) {
+                (Some(par), Some(grade)) => format!(
+                    "Moves: {} / par {}  Grade {}{}",
+                    self.moves,
+                    par,
+                    grade.name(),
+                    Self::flag(self.records.moves)
+                ),
+                _ => format!("Moves: {}{}", self.moves, Self::flag(self.records.moves)),
+            },
+            format!("Undos: {}  Stock passes: {}", self.undos, self.stock_cycles),
+        ];
+        if self.breakdown != ScoreBreakdown::default() {
+            lines.insert(3, self.breakdown.label());
+        }
+        lines
+    }
+
+    pub fn time_up_lines(&self) -> Vec<String> {
+        vec![
+            match self.remaining_ms {
+                Some(remaining_ms) if self.result == GameResult::Won => {
+                    format!("Cleared with {} to spare!", format_time(remaining_ms))
+                }
+                _ => String::from("Time's up!"),
+            },
+            format!("Cards home: {}/{}", self.cards_home, self.deck_size),
+            format!("Score: {}", self.score),
+        ]
+    }
+
+    pub fn out_of_moves_lines(&self) -> Vec<String> {
+        let budget = self.move_budget.unwrap_or(0);
+        vec![
+            String::from("Out of moves!"),
+            format!("Cards home: {}/{}", self.cards_home, self.deck_size),
+            match self.par {
+                Some(par) => format!("Budget: {} moves  Par: {}", budget, par),
+                None => format!("Budget: {} moves", budget),
+            },
+            format!("Deal: {}", self.verdict.label()),
+        ]
+    }
+}
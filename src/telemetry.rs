@@ -0,0 +1,138 @@
+use crate::{klondike::Table, session::Session, stats::GameResult};
+use alloc::string::String;
+use anyhow::Error;
+use crankstart::{file::FileSystem, log_to_console};
+use crankstart_sys::FileOptions;
+use serde::Serialize;
+
+pub const TELEMETRY_PATH: &str = "telemetry.jsonl";
+
+// Hooks for playtest builds. Nothing here identifies the player: events
+// carry the deal, the mode and how the game went.
+pub trait Telemetry {
+    fn game_started(&mut self, _session: &Session) {}
+    fn game_ended(&mut self, _session: &Session, _table: &Table, _result: GameResult) {}
+    fn hint_used(&mut self, _session: &Session) {}
+    fn undo_used(&mut self, _session: &Session) {}
+    fn stuck(&mut self, _session: &Session, _table: &Table) {}
+}
+
+pub struct NoTelemetry;
+
+impl Telemetry for NoTelemetry {}
+
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    event: &'a str,
+    mode: &'a str,
+    seed: u64,
+    elapsed_ms: usize,
+    moves: usize,
+    undos: usize,
+    hints: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cards_home: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<&'a str>,
+}
+
+// Appends one JSON line per event to a file in the game's data folder.
+#[derive(Debug, Default)]
+pub struct FileTelemetry {
+    hints: usize,
+}
+
+impl FileTelemetry {
+    fn line(
+        &self,
+        event: &str,
+        session: &Session,
+        table: Option<&Table>,
+        result: Option<GameResult>,
+    ) -> Result<String, Error> {
+        let record = Record {
+            event,
+            mode: session.mode.name(),
+            seed: session.seed,
+            elapsed_ms: session.elapsed_ms,
+            moves: session.moves,
+            undos: session.undos,
+            hints: self.hints,
+            cards_home: table.map(Table::foundation_count),
+            result: result.map(|result| match result {
+                GameResult::Won => "won",
+                GameResult::Lost => "lost",
+                GameResult::Abandoned => "abandoned",
+            }),
+        };
+        let mut line = serde_json::to_string(&record).map_err(Error::msg)?;
+        line.push('\n');
+        Ok(line)
+    }
+
+    fn write(
+        &self,
+        event: &str,
+        session: &Session,
+        table: Option<&Table>,
+        result: Option<GameResult>,
+    ) {
+        let written = self.line(event, session, table, result).and_then(|line| {
+            let file = FileSystem::get().open(TELEMETRY_PATH, FileOptions::kFileAppend)?;
+            file.write(line.as_bytes())?;
+            file.flush()
+        });
+        if let Err(err) = written {
+            log_to_console!("telemetry failed: {}", err);
+        }
+    }
+}
+
+impl Telemetry for FileTelemetry {
+    fn game_started(&mut self, session: &Session) {
+        self.hints = 0;
+        self.write("start", session, None, None);
+    }
+
+    fn game_ended(&mut self, session: &Session, table: &Table, result: GameResult) {
+        self.write("end", session, Some(table), Some(result));
+    }
+
+    fn hint_used(&mut self, session: &Session) {
+        self.hints += 1;
+        self.write("hint", session, None, None);
+    }
+
+    fn undo_used(&mut self, session: &Session) {
+        self.write("undo", session, None, None);
+    }
+
+    fn stuck(&mut self, session: &Session, table: &Table) {
+        self.write("stuck", session, Some(table), None);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scoring::ScoringMode, stats::GameMode};
+
+    #[test]
+    fn test_telemetry_lines() {
+        let mut session = Session::new(322, GameMode::Klondike, ScoringMode::Standard);
+        session.moves = 12;
+        let telemetry = FileTelemetry { hints: 2 };
+        assert_eq!(
+            "{\"event\":\"end\",\"mode\":\"Klondike\",\"seed\":322,\"elapsed_ms\":0,\"moves\":12,\
+             \"undos\":0,\"hints\":2,\"cards_home\":0,\"result\":\"lost\"}\n",
+            telemetry
+                .line(
+                    "end",
+                    &session,
+                    Some(&Table::new(322)),
+                    Some(GameResult::Lost)
+                )
+                .expect("line")
+        );
+    }
+}
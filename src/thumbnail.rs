@@ -0,0 +1,157 @@
+use crate::{
+    klondike::{Card, StackId, TableView},
+    panel::panel_bottom,
+};
+use alloc::{vec, vec::Vec};
+use anyhow::Error;
+use crankstart::{
+    geometry::{ScreenPoint, ScreenRect, ScreenSize},
+    graphics::{Graphics, LCDColor, LCDSolidColor, LCD_COLUMNS},
+};
+use serde::{Deserialize, Serialize};
+
+pub const THUMBNAIL_WIDTH: usize = 64;
+pub const THUMBNAIL_HEIGHT: usize = 32;
+
+const PANEL_GAP: i32 = 4;
+
+const TOP_ROW_HEIGHT: usize = 6;
+const TABLEAU_TOP: usize = 8;
+const CARD_STEP: usize = 2;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Thumbnail {
+    bits: Vec<u8>,
+}
+
+impl Default for Thumbnail {
+    fn default() -> Self {
+        Self {
+            bits: vec![0; THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT / 8],
+        }
+    }
+}
+
+impl Thumbnail {
+    pub fn render(table: TableView) -> Self {
+        let mut thumbnail = Self::default();
+        let mut top_row = vec![StackId::Stock];
+        if table.has_stack(StackId::Waste) {
+            top_row.push(StackId::Waste);
+        }
+        top_row.extend_from_slice(table.reserve_ids());
+        top_row.extend_from_slice(table.foundation_ids());
+        let tableaux = table.tableau_ids();
+        let columns = top_row.len().max(tableaux.len()).max(1);
+        let column_width = THUMBNAIL_WIDTH / columns;
+        for (column, stack_id) in top_row.iter().enumerate() {
+            if let Some(card) = table.get_stack(*stack_id).top_card() {
+                thumbnail.card(
+                    column * column_width,
+                    0,
+                    column_width - 1,
+                    TOP_ROW_HEIGHT,
+                    card,
+                );
+            }
+        }
+        for (column, stack_id) in tableaux.iter().enumerate() {
+            let stack = table.get_stack(*stack_id);
+            for (index, card) in stack.cards().iter().enumerate() {
+                let y = TABLEAU_TOP + index * CARD_STEP;
+                thumbnail.card(
+                    column * column_width,
+                    y,
+                    column_width - 1,
+                    TOP_ROW_HEIGHT,
+                    card,
+                );
+            }
+        }
+        thumbnail
+    }
+
+    fn set(&mut self, x: usize, y: usize, on: bool) {
+        if x >= THUMBNAIL_WIDTH || y >= THUMBNAIL_HEIGHT {
+            return;
+        }
+        let bit = y * THUMBNAIL_WIDTH + x;
+        if on {
+            self.bits[bit / 8] |= 0x80 >> (bit % 8);
+        } else {
+            self.bits[bit / 8] &= !(0x80 >> (bit % 8));
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let bit = y * THUMBNAIL_WIDTH + x;
+        self.bits
+            .get(bit / 8)
+            .map(|byte| byte & (0x80 >> (bit % 8)) != 0)
+            .unwrap_or(false)
+    }
+
+    // Later cards overlap earlier ones, so every card clears its interior.
+    fn card(&mut self, left: usize, top: usize, width: usize, height: usize, card: &Card) {
+        for y in top..top + height {
+            for x in left..left + width {
+                let edge = y == top || y == top + height - 1 || x == left || x == left + width - 1;
+                self.set(x, y, edge || !card.face_up);
+            }
+        }
+    }
+
+    pub fn draw_below_panel(&self, line_count: usize) -> Result<(), Error> {
+        let left = (LCD_COLUMNS as i32 - THUMBNAIL_WIDTH as i32 - 2) / 2;
+        self.draw(ScreenPoint::new(left, panel_bottom(line_count) + PANEL_GAP))
+    }
+
+    pub fn draw(&self, origin: ScreenPoint) -> Result<(), Error> {
+        let graphics = Graphics::get();
+        let bounds = ScreenRect::new(
+            origin,
+            ScreenSize::new(THUMBNAIL_WIDTH as i32 + 2, THUMBNAIL_HEIGHT as i32 + 2),
+        );
+        graphics.fill_rect(bounds, LCDColor::Solid(LCDSolidColor::kColorWhite))?;
+        graphics.draw_rect(bounds, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+        for y in 0..THUMBNAIL_HEIGHT {
+            let mut x = 0;
+            while x < THUMBNAIL_WIDTH {
+                if !self.get(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < THUMBNAIL_WIDTH && self.get(x, y) {
+                    x += 1;
+                }
+                let run = ScreenRect::new(
+                    ScreenPoint::new(origin.x + 1 + start as i32, origin.y + 1 + y as i32),
+                    ScreenSize::new((x - start) as i32, 1),
+                );
+                graphics.fill_rect(run, LCDColor::Solid(LCDSolidColor::kColorBlack))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::Table;
+
+    #[test]
+    fn test_render_klondike_deal() {
+        let thumbnail = Thumbnail::render(Table::new(322).view());
+        let column_width = THUMBNAIL_WIDTH / 7;
+        // The stock is face down and so solid; the empty waste is blank.
+        assert!(thumbnail.get(2, 2));
+        assert!(!thumbnail.get(column_width + 2, 2));
+        // The first tableau column holds one face up card, drawn hollow.
+        assert!(thumbnail.get(0, TABLEAU_TOP));
+        assert!(!thumbnail.get(2, TABLEAU_TOP + 2));
+        // The last column starts with six face down cards.
+        assert!(thumbnail.get(6 * column_width + 2, TABLEAU_TOP + 2));
+    }
+}
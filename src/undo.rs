@@ -0,0 +1,209 @@
+use crate::klondike::{AppliedCommand, KlondikeError, Play, Table, TableCommand};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum UndoLimit {
+    Unlimited,
+    Limited(usize),
+    Disabled,
+}
+
+impl UndoLimit {
+    pub fn next(self) -> Self {
+        match self {
+            UndoLimit::Unlimited => UndoLimit::Limited(3),
+            UndoLimit::Limited(_) => UndoLimit::Disabled,
+            UndoLimit::Disabled => UndoLimit::Unlimited,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            UndoLimit::Unlimited => String::from("Unlimited"),
+            UndoLimit::Limited(limit) => format!("{} per game", limit),
+            UndoLimit::Disabled => String::from("Off"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UndoError {
+    Disabled,
+    NothingToUndo,
+    NothingToRedo,
+    LimitReached(usize),
+}
+
+impl fmt::Display for UndoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UndoError::Disabled => f.write_str("undo is disabled"),
+            UndoError::NothingToUndo => f.write_str("nothing to undo"),
+            UndoError::NothingToRedo => f.write_str("nothing to redo"),
+            UndoError::LimitReached(limit) => {
+                f.write_fmt(format_args!("all {} undos have been used", limit))
+            }
+        }
+    }
+}
+
+impl UndoError {
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            UndoError::Disabled => "Undo is off for this game",
+            UndoError::NothingToUndo => "Nothing to undo",
+            UndoError::NothingToRedo => "Nothing to redo",
+            UndoError::LimitReached(_) => "No undos left",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UndoEntry {
+    pub command: AppliedCommand,
+    pub score: i32,
+}
+
+// The undo history as an autosave snapshot keeps it. Applied commands hold
+// deltas rather than plays, so loading plays them again from the table they
+// started from.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SavedUndo {
+    start: Table,
+    entries: Vec<(Play, i32)>,
+    undone: Vec<Play>,
+    undos_used: usize,
+}
+
+// Undone commands wait on the redo stack until a fresh move clears it.
+#[derive(Clone, Debug)]
+pub struct UndoHistory {
+    limit: UndoLimit,
+    entries: Vec<UndoEntry>,
+    undone: Vec<TableCommand>,
+    pub undos_used: usize,
+}
+
+impl UndoHistory {
+    pub fn new(limit: UndoLimit) -> Self {
+        Self {
+            limit,
+            entries: Vec::new(),
+            undone: Vec::new(),
+            undos_used: 0,
+        }
+    }
+
+    pub fn push(&mut self, command: AppliedCommand, score: i32) {
+        self.undone.clear();
+        self.push_redone(command, score);
+    }
+
+    pub fn push_redone(&mut self, command: AppliedCommand, score: i32) {
+        if self.limit != UndoLimit::Disabled {
+            self.entries.push(UndoEntry { command, score });
+        }
+    }
+
+    pub fn undos_remaining(&self) -> Option<usize> {
+        match self.limit {
+            UndoLimit::Unlimited => None,
+            UndoLimit::Limited(limit) => Some(limit.saturating_sub(self.undos_used)),
+            UndoLimit::Disabled => Some(0),
+        }
+    }
+
+    // Reverts the last command on the table and returns the score from
+    // before it.
+    pub fn undo(&mut self, table: &mut Table) -> Result<i32, UndoError> {
+        match self.limit {
+            UndoLimit::Unlimited => (),
+            UndoLimit::Limited(limit) => {
+                if self.undos_used >= limit {
+                    return Err(UndoError::LimitReached(limit));
+                }
+            }
+            UndoLimit::Disabled => return Err(UndoError::Disabled),
+        }
+        let entry = self.entries.pop().ok_or(UndoError::NothingToUndo)?;
+        self.undos_used += 1;
+        self.undone.push(entry.command.revert(table));
+        Ok(entry.score)
+    }
+
+    pub fn redo(&mut self) -> Result<TableCommand, UndoError> {
+        self.undone.pop().ok_or(UndoError::NothingToRedo)
+    }
+
+    pub fn save(&self, table: &Table) -> SavedUndo {
+        let mut start = table.clone();
+        for entry in self.entries.iter().rev() {
+            entry.command.clone().revert(&mut start);
+        }
+        SavedUndo {
+            start,
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| (entry.command.command().play(), entry.score))
+                .collect(),
+            undone: self.undone.iter().map(TableCommand::play).collect(),
+            undos_used: self.undos_used,
+        }
+    }
+
+    // Rebuilds a saved history, checking that it leads to the saved table.
+    pub fn load(limit: UndoLimit, saved: SavedUndo, table: &Table) -> Result<Self, KlondikeError> {
+        let mut history = Self::new(limit);
+        let mut replayed = saved.start;
+        for (play, score) in saved.entries {
+            let applied = TableCommand::new(play).apply(&mut replayed)?;
+            history.push_redone(applied, score);
+        }
+        if replayed != *table {
+            return Err(KlondikeError::InvalidTable);
+        }
+        history.undone = saved.undone.into_iter().map(TableCommand::new).collect();
+        history.undos_used = saved.undos_used;
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::Play;
+
+    #[test]
+    fn test_undo_limits() {
+        let mut table = Table::new(322);
+        let deal = |table: &mut Table| {
+            TableCommand::new(Play::DrawFromStock)
+                .apply(table)
+                .expect("deal")
+        };
+
+        let mut history = UndoHistory::new(UndoLimit::Limited(1));
+        assert_eq!(Err(UndoError::NothingToUndo), history.undo(&mut table));
+        let start = table.clone();
+        history.push(deal(&mut table), 5);
+        let dealt = table.clone();
+        history.push(deal(&mut table), 10);
+        assert_eq!(Ok(10), history.undo(&mut table));
+        assert_eq!(dealt, table);
+        assert_eq!(Err(UndoError::LimitReached(1)), history.undo(&mut table));
+        assert_eq!(Some(0), history.undos_remaining());
+        assert_eq!(
+            Ok(Play::DrawFromStock),
+            history.redo().map(|command| command.play())
+        );
+        assert_eq!(Err(UndoError::NothingToRedo), history.redo().map(|_| ()));
+
+        let mut history = UndoHistory::new(UndoLimit::Disabled);
+        table = start;
+        history.push(deal(&mut table), 5);
+        assert_eq!(Err(UndoError::Disabled), history.undo(&mut table));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::panel::draw_panel;
+use alloc::{format, string::String, vec, vec::Vec};
+use anyhow::Error;
+use crankstart::system::PDButtons;
+
+pub const DIGITS: &str = "0123456789";
+// The share code alphabet; decoding skips the dashes, so there's no need to
+// dial them in.
+pub const CODE_CHARACTERS: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+pub const NAME_CHARACTERS: &str = " ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+const CRANK_DEGREES_PER_STEP: f32 = 30.0;
+const VISIBLE_CHARACTERS: usize = 20;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntryEvent {
+    Editing,
+    Confirmed(String),
+    Cancelled,
+}
+
+// Text entry for a Playdate: the crank or up and down spin the character
+// under the cursor, left and right move it, A finishes and B deletes,
+// backing out once there's nothing left to delete.
+#[derive(Clone, Debug)]
+pub struct CrankEntry {
+    title: &'static str,
+    alphabet: &'static [u8],
+    chars: Vec<usize>,
+    position: usize,
+    max_len: usize,
+    crank: f32,
+}
+
+impl CrankEntry {
+    pub fn new(title: &'static str, alphabet: &'static str, max_len: usize) -> Self {
+        Self {
+            title,
+            alphabet: alphabet.as_bytes(),
+            chars: vec![0],
+            position: 0,
+            max_len: max_len.max(1),
+            crank: 0.0,
+        }
+    }
+
+    // Starts from existing text, in capitals, dropping characters the
+    // alphabet lacks.
+    pub fn with_text(mut self, text: &str) -> Self {
+        let chars: Vec<usize> = text
+            .bytes()
+            .map(|byte| byte.to_ascii_uppercase())
+            .filter_map(|byte| self.alphabet.iter().position(|letter| *letter == byte))
+            .take(self.max_len)
+            .collect();
+        if !chars.is_empty() {
+            self.position = chars.len() - 1;
+            self.chars = chars;
+        }
+        self
+    }
+
+    pub fn text(&self) -> String {
+        let text: String = self
+            .chars
+            .iter()
+            .map(|index| self.alphabet[*index] as char)
+            .collect();
+        String::from(text.trim())
+    }
+
+    fn spin(&mut self, steps: i32) {
+        let count = self.alphabet.len() as i32;
+        let current = self.chars[self.position] as i32;
+        self.chars[self.position] = (current + steps).rem_euclid(count) as usize;
+    }
+
+    pub fn crank(&mut self, change: f32) {
+        self.crank += change;
+        while self.crank >= CRANK_DEGREES_PER_STEP {
+            self.crank -= CRANK_DEGREES_PER_STEP;
+            self.spin(1);
+        }
+        while self.crank <= -CRANK_DEGREES_PER_STEP {
+            self.crank += CRANK_DEGREES_PER_STEP;
+            self.spin(-1);
+        }
+    }
+
+    pub fn handle(&mut self, pushed: PDButtons) -> EntryEvent {
+        if (pushed & PDButtons::kButtonA) == PDButtons::kButtonA {
+            return EntryEvent::Confirmed(self.text());
+        }
+        if (pushed & PDButtons::kButtonB) == PDButtons::kButtonB {
+            if self.chars.len() == 1 {
+                return EntryEvent::Cancelled;
+            }
+            self.chars.remove(self.position);
+            self.position = self.position.saturating_sub(1);
+        } else if (pushed & PDButtons::kButtonUp) == PDButtons::kButtonUp {
+            self.spin(1);
+        } else if (pushed & PDButtons::kButtonDown) == PDButtons::kButtonDown {
+            self.spin(-1);
+        } else if (pushed & PDButtons::kButtonLeft) == PDButtons::kButtonLeft {
+            self.position = self.position.saturating_sub(1);
+        } else if (pushed & PDButtons::kButtonRight) == PDButtons::kButtonRight {
+            // Stepping past the end adds a character, starting from the one
+            // before it since codes and seeds often repeat.
+            if self.position + 1 == self.chars.len() && self.chars.len() < self.max_len {
+                self.chars.push(self.chars[self.position]);
+            }
+            self.position = (self.position + 1).min(self.chars.len() - 1);
+        }
+        EntryEvent::Editing
+    }
+
+    // The text around the cursor, with the character being spun bracketed.
+    fn visible_text(&self) -> String {
+        let first = (self.position + 1).saturating_sub(VISIBLE_CHARACTERS);
+        self.chars
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_CHARACTERS)
+            .map(|(index, char_index)| {
+                let letter = self.alphabet[*char_index] as char;
+                if index == self.position {
+                    format!("[{}]", letter)
+                } else {
+                    format!("{}", letter)
+                }
+            })
+            .collect()
+    }
+
+    pub fn draw(&self) -> Result<(), Error> {
+        draw_panel(
+            &[
+                String::from(self.title),
+                self.visible_text(),
+                format!("{}/{}", self.chars.len(), self.max_len),
+                String::from("Crank: change  A: done  B: delete"),
+            ],
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_crank_entry() {
+        let mut entry = CrankEntry::new("Deal number", DIGITS, 3);
+        entry.crank(CRANK_DEGREES_PER_STEP * 3.0);
+        assert_eq!("3", entry.text());
+        assert_eq!(EntryEvent::Editing, entry.handle(PDButtons::kButtonRight));
+        entry.handle(PDButtons::kButtonDown);
+        entry.handle(PDButtons::kButtonRight);
+        entry.crank(-CRANK_DEGREES_PER_STEP * 5.0);
+        assert_eq!("327", entry.text());
+        assert_eq!("32[7]", entry.visible_text());
+        entry.handle(PDButtons::kButtonRight);
+        assert_eq!("327", entry.text());
+        entry.handle(PDButtons::kButtonB);
+        assert_eq!(
+            EntryEvent::Confirmed(String::from("32")),
+            entry.handle(PDButtons::kButtonA)
+        );
+        entry.handle(PDButtons::kButtonB);
+        assert_eq!(EntryEvent::Cancelled, entry.handle(PDButtons::kButtonB));
+
+        let named = CrankEntry::new("Slot name", NAME_CHARACTERS, 12).with_text("Mine!");
+        assert_eq!("MINE", named.text());
+        let named = CrankEntry::new("Slot name", NAME_CHARACTERS, 12).with_text("MY GAME");
+        assert_eq!("MY GAM[E]", named.visible_text());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    klondike::{RulesConfig, VariantId},
+    scoring::ScoringMode,
+    undo::UndoLimit,
+};
+use alloc::{string::String, vec::Vec};
+use enum_iterator::IntoEnumIterator;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+const MAX_MODIFIERS: usize = 2;
+
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, IntoEnumIterator, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum Modifier {
+    NoUndo,
+    OnePass,
+    Vegas,
+    Thoughtful,
+    LockedFoundations,
+}
+
+impl Modifier {
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::NoUndo => "No undo",
+            Modifier::OnePass => "One stock pass",
+            Modifier::Vegas => "Vegas scoring",
+            Modifier::Thoughtful => "Cards dealt face up",
+            Modifier::LockedFoundations => "Foundations locked",
+        }
+    }
+}
+
+// The same deal always draws the same modifiers, so replays and daily deals
+// match.
+pub fn modifiers_for(seed: u64) -> Vec<Modifier> {
+    let mut rng = rand_pcg::Pcg32::seed_from_u64(seed ^ 0x5749_4c44);
+    let all: Vec<Modifier> = Modifier::into_enum_iter().collect();
+    let count = 1 + rng.gen::<u32>() as usize % MAX_MODIFIERS;
+    let mut modifiers: Vec<Modifier> = all.choose_multiple(&mut rng, count).copied().collect();
+    modifiers.sort();
+    modifiers
+}
+
+pub fn describe(modifiers: &[Modifier]) -> String {
+    let names: Vec<&str> = modifiers.iter().map(|modifier| modifier.name()).collect();
+    names.join(", ")
+}
+
+pub fn adjust_rules(modifiers: &[Modifier], mut rules: RulesConfig) -> RulesConfig {
+    for modifier in modifiers {
+        match modifier {
+            Modifier::OnePass => rules.max_recycles = Some(0),
+            Modifier::LockedFoundations => rules.allow_foundation_pick_up = false,
+            Modifier::NoUndo | Modifier::Vegas | Modifier::Thoughtful => (),
+        }
+    }
+    rules
+}
+
+pub fn variant(modifiers: &[Modifier], variant: VariantId) -> VariantId {
+    if modifiers.contains(&Modifier::Thoughtful) {
+        VariantId::Thoughtful
+    } else {
+        variant
+    }
+}
+
+pub fn scoring(modifiers: &[Modifier], scoring: ScoringMode) -> ScoringMode {
+    if modifiers.contains(&Modifier::Vegas) {
+        ScoringMode::Vegas
+    } else {
+        scoring
+    }
+}
+
+pub fn undo_limit(modifiers: &[Modifier], limit: UndoLimit) -> UndoLimit {
+    if modifiers.contains(&Modifier::NoUndo) {
+        UndoLimit::Disabled
+    } else {
+        limit
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_modifiers_for_seed() {
+        for seed in 0..50 {
+            let modifiers = modifiers_for(seed);
+            assert_eq!(modifiers, modifiers_for(seed));
+            assert!(!modifiers.is_empty() && modifiers.len() <= MAX_MODIFIERS);
+            assert!(modifiers.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        let rules = adjust_rules(
+            &[Modifier::OnePass, Modifier::LockedFoundations],
+            RulesConfig::default(),
+        );
+        assert_eq!(Some(0), rules.max_recycles);
+        assert!(!rules.allow_foundation_pick_up);
+        assert_eq!(
+            VariantId::Thoughtful,
+            variant(&[Modifier::Thoughtful], VariantId::Klondike)
+        );
+        assert_eq!(
+            UndoLimit::Disabled,
+            undo_limit(&[Modifier::NoUndo], UndoLimit::Unlimited)
+        );
+    }
+}
@@ -0,0 +1,123 @@
+// The rules engine for web pages: previews of the daily deal and checks of
+// share codes made on the Playdate. Results go to JavaScript as JSON strings.
+extern crate alloc;
+
+#[path = "../../src/klondike/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod klondike;
+
+use crate::klondike::{daily_klondike_seed, KlondikeError, ShareCode, StackId, Table, VariantId};
+use enum_iterator::IntoEnumIterator;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Serialize)]
+struct StackCards {
+    stack: StackId,
+    cards: Vec<String>,
+}
+
+fn stacks(table: &Table) -> Vec<StackCards> {
+    StackId::into_enum_iter()
+        .map(|stack_id| StackCards {
+            stack: stack_id,
+            cards: table
+                .get_stack(stack_id)
+                .cards()
+                .iter()
+                .map(|card| card.to_notation())
+                .collect(),
+        })
+        .filter(|stack| !stack.cards.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct DealPreview {
+    day: u32,
+    seed: u64,
+    stacks: Vec<StackCards>,
+}
+
+impl DealPreview {
+    fn new(day: u32) -> Self {
+        let seed = daily_klondike_seed(day);
+        Self {
+            day,
+            seed,
+            stacks: stacks(&Table::new(seed)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ShareReport {
+    seed: u64,
+    variant: VariantId,
+    plays: usize,
+    cards_home: usize,
+    won: bool,
+    stacks: Vec<StackCards>,
+}
+
+impl ShareReport {
+    // Replays the code from its deal, so a code only checks out if every
+    // play in it was legal.
+    fn check(code: &str) -> Result<Self, KlondikeError> {
+        let share = ShareCode::decode(code)?;
+        let table = share.replay()?;
+        Ok(Self {
+            seed: share.seed,
+            variant: share.variant,
+            plays: share.plays.len(),
+            cards_home: table.foundation_count(),
+            won: table.winner(),
+            stacks: stacks(&table),
+        })
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, JsValue> {
+    serde_json::to_string(value).map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// The daily Klondike deal for a day counted from 2000-01-01, the Playdate
+/// epoch, in the player's local time. Today is
+/// `Math.floor((Date.now() - new Date().getTimezoneOffset() * 60000) / 86400000) - 10957`.
+#[wasm_bindgen]
+pub fn daily_deal(day: u32) -> Result<String, JsValue> {
+    to_json(&DealPreview::new(day))
+}
+
+/// Decodes and replays a share code, throwing if it is mistyped or contains
+/// a play the rules don't allow.
+#[wasm_bindgen]
+pub fn check_share_code(code: &str) -> Result<String, JsValue> {
+    let report = ShareReport::check(code).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    to_json(&report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::klondike::Play;
+
+    #[test]
+    fn test_daily_deal_preview() {
+        let preview = DealPreview::new(19_000);
+        assert_eq!(daily_klondike_seed(19_000), preview.seed);
+        let cards: usize = preview.stacks.iter().map(|stack| stack.cards.len()).sum();
+        assert_eq!(52, cards);
+        assert_eq!(StackId::Stock, preview.stacks[0].stack);
+    }
+
+    #[test]
+    fn test_share_code_report() {
+        let mut table = Table::new(322);
+        table.deal_from_stock().expect("deal");
+        let code = ShareCode::new(&table, 322, &[Play::DrawFromStock]).encode();
+        let report = ShareReport::check(&code).expect("report");
+        assert_eq!((322, 1, false), (report.seed, report.plays, report.won));
+        assert!(ShareReport::check("not a code").is_err());
+    }
+}